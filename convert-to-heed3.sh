@@ -27,6 +27,16 @@ for file in $(find heed/src -type f -name "*.rs"); do
     fi
 done
 
+# The heed-cli crate must depend on heed3 and enable the support of the encrypted environments,
+# the heed-derive crate must be tested against heed3.
+if [[ "$OSTYPE" == "darwin"* ]]; then
+    sed -i '' 's/^heed = { version/heed = { package = "heed3", version/' heed-cli/Cargo.toml heed-derive/Cargo.toml
+    sed -i '' 's/^default = \[\]/default = ["encryption"]/' heed-cli/Cargo.toml
+else
+    sed -i 's/^heed = { version/heed = { package = "heed3", version/' heed-cli/Cargo.toml heed-derive/Cargo.toml
+    sed -i 's/^default = \[\]/default = ["encryption"]/' heed-cli/Cargo.toml
+fi
+
 # Make it easier to rollback by doing a commit
 git config --local user.email "ci@github.com"
 git config --local user.name "The CI"
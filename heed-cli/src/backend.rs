@@ -0,0 +1,211 @@
+use std::ops::Bound;
+use std::path::Path;
+
+use heed::types::Bytes;
+use heed::{CompactionOption, DatabaseStat, Env, EnvInfo, ReaderInfo, Result};
+
+/// A key-value pair that was read from a database.
+pub type Entry = (Vec<u8>, Vec<u8>);
+
+/// The bounds of a scan, see [`Backend::scan`].
+pub enum Scan {
+    /// Every entry whose key starts with the prefix.
+    Prefix(Vec<u8>),
+    /// Every entry whose key is in the range.
+    Range(Bound<Vec<u8>>, Bound<Vec<u8>>),
+}
+
+/// The operations of the commands, implemented for the environments of heed
+/// and, when the encryption is supported, for the encrypted ones.
+///
+/// The databases are identified by their name, `None` being the unnamed database.
+pub trait Backend {
+    fn info(&self) -> EnvInfo;
+    fn real_disk_size(&self) -> Result<u64>;
+    fn copy_to_file(&self, path: &Path, option: CompactionOption) -> Result<()>;
+    fn readers(&self) -> Result<Vec<ReaderInfo>>;
+    fn clear_stale_readers(&self) -> Result<usize>;
+    /// Returns the names of the named databases, stored as keys in the unnamed one.
+    fn database_names(&self) -> Result<Vec<String>>;
+    fn stat(&self, database: Option<&str>) -> Result<Option<DatabaseStat>>;
+    fn get(&self, database: Option<&str>, key: &[u8]) -> Result<Option<Vec<u8>>>;
+    fn put(&self, database: Option<&str>, key: &[u8], value: &[u8]) -> Result<()>;
+    fn delete(&self, database: Option<&str>, key: &[u8]) -> Result<bool>;
+    /// Returns at most `limit` entries matching the scan.
+    fn scan(&self, database: Option<&str>, scan: &Scan, limit: usize) -> Result<Vec<Entry>>;
+}
+
+fn database_not_found(database: Option<&str>) -> heed::Error {
+    let msg = match database {
+        Some(name) => format!("no such database: {name}"),
+        None => "no such database: the unnamed one".to_owned(),
+    };
+    std::io::Error::new(std::io::ErrorKind::NotFound, msg).into()
+}
+
+impl<T> Backend for Env<T> {
+    fn info(&self) -> EnvInfo {
+        Env::info(self)
+    }
+
+    fn real_disk_size(&self) -> Result<u64> {
+        Env::real_disk_size(self)
+    }
+
+    fn copy_to_file(&self, path: &Path, option: CompactionOption) -> Result<()> {
+        Env::copy_to_file(self, path, option).map(drop)
+    }
+
+    fn readers(&self) -> Result<Vec<ReaderInfo>> {
+        Env::readers(self)
+    }
+
+    fn clear_stale_readers(&self) -> Result<usize> {
+        Env::clear_stale_readers(self)
+    }
+
+    fn database_names(&self) -> Result<Vec<String>> {
+        let rtxn = self.read_txn()?;
+        Env::database_names(self, &rtxn)
+    }
+
+    fn stat(&self, database: Option<&str>) -> Result<Option<DatabaseStat>> {
+        let rtxn = self.read_txn()?;
+        match self.open_database::<Bytes, Bytes>(&rtxn, database)? {
+            Some(db) => db.stat(&rtxn).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn get(&self, database: Option<&str>, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let rtxn = self.read_txn()?;
+        let db = self
+            .open_database::<Bytes, Bytes>(&rtxn, database)?
+            .ok_or_else(|| database_not_found(database))?;
+        Ok(db.get(&rtxn, key)?.map(ToOwned::to_owned))
+    }
+
+    fn put(&self, database: Option<&str>, key: &[u8], value: &[u8]) -> Result<()> {
+        let mut wtxn = self.write_txn()?;
+        let db = self.create_database::<Bytes, Bytes>(&mut wtxn, database)?;
+        db.put(&mut wtxn, key, value)?;
+        wtxn.commit()
+    }
+
+    fn delete(&self, database: Option<&str>, key: &[u8]) -> Result<bool> {
+        // The database is opened in a committed read transaction to be usable in
+        // the write one, creating it would turn a typo into an empty database.
+        let rtxn = self.read_txn()?;
+        let db = self
+            .open_database::<Bytes, Bytes>(&rtxn, database)?
+            .ok_or_else(|| database_not_found(database))?;
+        rtxn.commit()?;
+
+        let mut wtxn = self.write_txn()?;
+        let deleted = db.delete(&mut wtxn, key)?;
+        wtxn.commit()?;
+        Ok(deleted)
+    }
+
+    fn scan(&self, database: Option<&str>, scan: &Scan, limit: usize) -> Result<Vec<Entry>> {
+        let rtxn = self.read_txn()?;
+        let db = self
+            .open_database::<Bytes, Bytes>(&rtxn, database)?
+            .ok_or_else(|| database_not_found(database))?;
+        match scan {
+            Scan::Prefix(prefix) => {
+                db.prefix_iter(&rtxn, prefix)?.take(limit).map(to_entry).collect()
+            }
+            Scan::Range(start, end) => {
+                let range = (start.as_ref().map(Vec::as_slice), end.as_ref().map(Vec::as_slice));
+                db.range(&rtxn, &range)?.take(limit).map(to_entry).collect()
+            }
+        }
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl<T> Backend for heed::EncryptedEnv<T> {
+    fn info(&self) -> EnvInfo {
+        heed::EncryptedEnv::info(self)
+    }
+
+    fn real_disk_size(&self) -> Result<u64> {
+        heed::EncryptedEnv::real_disk_size(self)
+    }
+
+    fn copy_to_file(&self, path: &Path, option: CompactionOption) -> Result<()> {
+        heed::EncryptedEnv::copy_to_file(self, path, option).map(drop)
+    }
+
+    fn readers(&self) -> Result<Vec<ReaderInfo>> {
+        heed::EncryptedEnv::readers(self)
+    }
+
+    fn clear_stale_readers(&self) -> Result<usize> {
+        heed::EncryptedEnv::clear_stale_readers(self)
+    }
+
+    fn database_names(&self) -> Result<Vec<String>> {
+        let rtxn = self.read_txn()?;
+        heed::EncryptedEnv::database_names(self, &rtxn)
+    }
+
+    fn stat(&self, database: Option<&str>) -> Result<Option<DatabaseStat>> {
+        let rtxn = self.read_txn()?;
+        match self.open_database::<Bytes, Bytes>(&rtxn, database)? {
+            Some(db) => db.stat(&rtxn).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn get(&self, database: Option<&str>, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let mut rtxn = self.read_txn()?;
+        let db = self
+            .open_database::<Bytes, Bytes>(&rtxn, database)?
+            .ok_or_else(|| database_not_found(database))?;
+        Ok(db.get(&mut rtxn, key)?.map(ToOwned::to_owned))
+    }
+
+    fn put(&self, database: Option<&str>, key: &[u8], value: &[u8]) -> Result<()> {
+        let mut wtxn = self.write_txn()?;
+        let db = self.create_database::<Bytes, Bytes>(&mut wtxn, database)?;
+        db.put(&mut wtxn, key, value)?;
+        wtxn.commit()
+    }
+
+    fn delete(&self, database: Option<&str>, key: &[u8]) -> Result<bool> {
+        // The database is opened in a committed read transaction to be usable in
+        // the write one, creating it would turn a typo into an empty database.
+        let rtxn = self.read_txn()?;
+        let db = self
+            .open_database::<Bytes, Bytes>(&rtxn, database)?
+            .ok_or_else(|| database_not_found(database))?;
+        rtxn.commit()?;
+
+        let mut wtxn = self.write_txn()?;
+        let deleted = db.delete(&mut wtxn, key)?;
+        wtxn.commit()?;
+        Ok(deleted)
+    }
+
+    fn scan(&self, database: Option<&str>, scan: &Scan, limit: usize) -> Result<Vec<Entry>> {
+        let mut rtxn = self.read_txn()?;
+        let db = self
+            .open_database::<Bytes, Bytes>(&rtxn, database)?
+            .ok_or_else(|| database_not_found(database))?;
+        match scan {
+            Scan::Prefix(prefix) => {
+                db.prefix_iter(&mut rtxn, prefix)?.take(limit).map(to_entry).collect()
+            }
+            Scan::Range(start, end) => {
+                let range = (start.as_ref().map(Vec::as_slice), end.as_ref().map(Vec::as_slice));
+                db.range(&mut rtxn, &range)?.take(limit).map(to_entry).collect()
+            }
+        }
+    }
+}
+
+fn to_entry(result: Result<(&[u8], &[u8])>) -> Result<Entry> {
+    result.map(|(k, v)| (k.to_vec(), v.to_vec()))
+}
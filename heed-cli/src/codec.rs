@@ -0,0 +1,93 @@
+use std::error::Error;
+use std::fmt::Write as _;
+
+use clap::ValueEnum;
+
+/// How the keys and values are written on the command line and stored in the database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Codec {
+    /// UTF-8 strings.
+    Str,
+    /// Unsigned 64-bit integers stored in big-endian.
+    U64Be,
+    /// JSON values, stored with `serde_json`.
+    Json,
+    /// Raw bytes, written as hexadecimal on the command line.
+    Bytes,
+}
+
+impl Codec {
+    /// Converts the command-line representation into the stored bytes.
+    pub fn encode(self, input: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        match self {
+            Codec::Str => Ok(input.as_bytes().to_vec()),
+            Codec::U64Be => Ok(input.parse::<u64>()?.to_be_bytes().to_vec()),
+            Codec::Json => {
+                let value: serde_json::Value = serde_json::from_str(input)?;
+                Ok(serde_json::to_vec(&value)?)
+            }
+            Codec::Bytes => decode_hex(input),
+        }
+    }
+
+    /// Converts the stored bytes into their command-line representation.
+    pub fn decode(self, bytes: &[u8]) -> Result<String, Box<dyn Error>> {
+        match self {
+            Codec::Str => Ok(std::str::from_utf8(bytes)?.to_owned()),
+            Codec::U64Be => {
+                let array: [u8; 8] =
+                    bytes.try_into().map_err(|_| "expected 8 bytes for a big-endian u64")?;
+                Ok(u64::from_be_bytes(array).to_string())
+            }
+            Codec::Json => {
+                let value: serde_json::Value = serde_json::from_slice(bytes)?;
+                Ok(value.to_string())
+            }
+            Codec::Bytes => Ok(encode_hex(bytes)),
+        }
+    }
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    let mut output = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        write!(output, "{byte:02x}").unwrap();
+    }
+    output
+}
+
+fn decode_hex(input: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let pairs = input.as_bytes().chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return Err("expected an even number of hexadecimal digits".into());
+    }
+    let mut bytes = Vec::with_capacity(input.len() / 2);
+    for pair in pairs {
+        let pair = std::str::from_utf8(pair)?;
+        bytes.push(u8::from_str_radix(pair, 16)?);
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codecs_roundtrip() {
+        let inputs = [
+            (Codec::Str, "hello"),
+            (Codec::U64Be, "42"),
+            (Codec::Json, r#"{"a":[1,2]}"#),
+            (Codec::Bytes, "00ff10"),
+        ];
+        for (codec, input) in inputs {
+            let bytes = codec.encode(input).unwrap();
+            assert_eq!(codec.decode(&bytes).unwrap(), input);
+        }
+
+        assert_eq!(Codec::U64Be.encode("1").unwrap(), [0, 0, 0, 0, 0, 0, 0, 1]);
+        assert!(Codec::Bytes.encode("abc").is_err());
+        assert!(Codec::U64Be.decode(b"abc").is_err());
+    }
+}
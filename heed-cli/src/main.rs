@@ -0,0 +1,310 @@
+//! A command-line tool to inspect and edit heed and LMDB environments.
+//!
+//! ```text
+//! heed-cli path/to/env info
+//! heed-cli path/to/env list
+//! heed-cli path/to/env put --db users --value-codec json 42 '{"name":"Ferris"}' --key-codec u64-be
+//! heed-cli path/to/env scan --db users --prefix user- --limit 10
+//! ```
+//!
+//! Encrypted environments are supported when the tool is built from the heed3
+//! crate (with the `encryption` feature) and given a `--key-file`.
+
+use std::error::Error;
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand};
+use heed::{CompactionOption, EnvFlags, EnvOpenOptions};
+
+use self::backend::{Backend, Scan};
+use self::codec::Codec;
+
+mod backend;
+mod codec;
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    /// The path to the environment directory, or file with `--no-sub-dir`.
+    env: PathBuf,
+
+    /// The maximum number of named databases that can be opened.
+    #[arg(long, default_value_t = 128)]
+    max_dbs: u32,
+
+    /// The environment is a single file instead of a directory.
+    #[arg(long)]
+    no_sub_dir: bool,
+
+    /// The file containing the raw 32-byte ChaCha20-Poly1305 key of an encrypted environment.
+    #[arg(long)]
+    key_file: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Shows the information of the environment.
+    Info,
+    /// Shows the statistics of every database, or of a single one.
+    Stat {
+        /// The name of the database, every database by default.
+        #[arg(long)]
+        db: Option<String>,
+    },
+    /// Lists the named databases.
+    List,
+    /// Prints the value of a key.
+    Get {
+        #[command(flatten)]
+        codecs: Codecs,
+        key: String,
+    },
+    /// Writes a key-value pair, creating the database if needed.
+    Put {
+        #[command(flatten)]
+        codecs: Codecs,
+        key: String,
+        value: String,
+    },
+    /// Deletes a key.
+    Del {
+        #[command(flatten)]
+        codecs: Codecs,
+        key: String,
+    },
+    /// Prints the key-value pairs in a prefix or a range of keys.
+    Scan {
+        #[command(flatten)]
+        codecs: Codecs,
+        /// Only prints the keys starting with this prefix.
+        #[arg(long, conflicts_with = "range")]
+        prefix: Option<String>,
+        /// Only prints the keys in this range, written `start..end` where both bounds
+        /// can be omitted and the end is included when written `start..=end`.
+        #[arg(long)]
+        range: Option<String>,
+        /// The maximum number of key-value pairs to print.
+        #[arg(long, default_value_t = usize::MAX)]
+        limit: usize,
+    },
+    /// Copies the environment into a new file.
+    Copy {
+        /// The path of the file to create.
+        dest: PathBuf,
+        /// Omits the free pages and renumbers the pages of the copy.
+        #[arg(long)]
+        compact: bool,
+    },
+    /// Lists the reader slots in use and the snapshot read by each of them.
+    Readers,
+    /// Clears the reader slots of the dead processes.
+    ClearStaleReaders,
+}
+
+#[derive(Debug, clap::Args)]
+struct Codecs {
+    /// The name of the database, the unnamed one by default.
+    #[arg(long)]
+    db: Option<String>,
+    /// How the keys are written and stored.
+    #[arg(long, value_enum, default_value_t = Codec::Str)]
+    key_codec: Codec,
+    /// How the values are written and stored.
+    #[arg(long, value_enum, default_value_t = Codec::Str)]
+    value_codec: Codec,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(error) = run(cli) {
+        eprintln!("error: {error}");
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    let read_only = matches!(
+        cli.command,
+        Command::Info
+            | Command::Stat { .. }
+            | Command::List
+            | Command::Get { .. }
+            | Command::Scan { .. }
+            | Command::Copy { .. }
+            | Command::Readers
+    );
+
+    let mut flags = EnvFlags::empty();
+    if read_only {
+        flags |= EnvFlags::READ_ONLY;
+    }
+    if cli.no_sub_dir {
+        flags |= EnvFlags::NO_SUB_DIR;
+    }
+
+    let mut options = EnvOpenOptions::new();
+    options.max_dbs(cli.max_dbs);
+    // Safety: the flags are only the read-only and single file ones.
+    unsafe { options.flags(flags) };
+
+    match &cli.key_file {
+        Some(key_file) => {
+            let backend = open_encrypted(&options, key_file, &cli.env)?;
+            execute(&backend, cli.command)
+        }
+        None => {
+            let backend = unsafe { options.open(&cli.env)? };
+            execute(&backend, cli.command)
+        }
+    }
+}
+
+#[cfg(feature = "encryption")]
+fn open_encrypted<T: heed::TlsUsage>(
+    options: &EnvOpenOptions<T>,
+    key_file: &Path,
+    path: &Path,
+) -> Result<heed::EncryptedEnv<T>, Box<dyn Error>> {
+    use chacha20poly1305::{ChaCha20Poly1305, Key};
+
+    let bytes = std::fs::read(key_file)?;
+    let mut key = Key::default();
+    if bytes.len() != key.len() {
+        return Err(format!(
+            "the key file must contain {} bytes, found {}",
+            key.len(),
+            bytes.len()
+        )
+        .into());
+    }
+    key.copy_from_slice(&bytes);
+    Ok(unsafe { options.open_encrypted::<ChaCha20Poly1305, _>(key, path)? })
+}
+
+#[cfg(not(feature = "encryption"))]
+fn open_encrypted<T: heed::TlsUsage>(
+    _options: &EnvOpenOptions<T>,
+    _key_file: &Path,
+    _path: &Path,
+) -> Result<heed::Env<T>, Box<dyn Error>> {
+    Err("encrypted environments require heed-cli to be built from heed3 with the `encryption` feature".into())
+}
+
+fn execute(backend: &impl Backend, command: Command) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Info => {
+            let info = backend.info();
+            println!("map size: {}", info.map_size);
+            println!("page size: {}", info.page_size);
+            println!("last page number: {}", info.last_page_number);
+            println!("last transaction id: {}", info.last_txn_id);
+            println!("maximum number of readers: {}", info.maximum_number_of_readers);
+            println!("number of readers: {}", info.number_of_readers);
+            println!("disk size: {}", backend.real_disk_size()?);
+        }
+        Command::Stat { db } => {
+            let names = match db {
+                Some(name) => vec![Some(name)],
+                None => {
+                    let mut names = vec![None];
+                    names.extend(backend.database_names()?.into_iter().map(Some));
+                    names
+                }
+            };
+            for name in names {
+                let stat = backend.stat(name.as_deref())?.ok_or("database not found")?;
+                println!("{}:", name.as_deref().unwrap_or("<unnamed>"));
+                println!("  page size: {}", stat.page_size);
+                println!("  depth: {}", stat.depth);
+                println!("  branch pages: {}", stat.branch_pages);
+                println!("  leaf pages: {}", stat.leaf_pages);
+                println!("  overflow pages: {}", stat.overflow_pages);
+                println!("  entries: {}", stat.entries);
+            }
+        }
+        Command::List => {
+            for name in backend.database_names()? {
+                println!("{name}");
+            }
+        }
+        Command::Get { codecs, key } => {
+            let key = codecs.key_codec.encode(&key)?;
+            match backend.get(codecs.db.as_deref(), &key)? {
+                Some(value) => println!("{}", codecs.value_codec.decode(&value)?),
+                None => return Err("key not found".into()),
+            }
+        }
+        Command::Put { codecs, key, value } => {
+            let key = codecs.key_codec.encode(&key)?;
+            let value = codecs.value_codec.encode(&value)?;
+            backend.put(codecs.db.as_deref(), &key, &value)?;
+        }
+        Command::Del { codecs, key } => {
+            let key = codecs.key_codec.encode(&key)?;
+            if !backend.delete(codecs.db.as_deref(), &key)? {
+                return Err("key not found".into());
+            }
+        }
+        Command::Scan { codecs, prefix, range, limit } => {
+            let scan = match (prefix, range) {
+                (Some(prefix), _) => Scan::Prefix(codecs.key_codec.encode(&prefix)?),
+                (None, Some(range)) => parse_range(codecs.key_codec, &range)?,
+                (None, None) => Scan::Range(Bound::Unbounded, Bound::Unbounded),
+            };
+            for (key, value) in backend.scan(codecs.db.as_deref(), &scan, limit)? {
+                let key = codecs.key_codec.decode(&key)?;
+                let value = codecs.value_codec.decode(&value)?;
+                println!("{key}\t{value}");
+            }
+        }
+        Command::Copy { dest, compact } => {
+            let option =
+                if compact { CompactionOption::Enabled } else { CompactionOption::Disabled };
+            backend.copy_to_file(&dest, option)?;
+        }
+        Command::Readers => {
+            let info = backend.info();
+            println!(
+                "{}/{} reader slots used",
+                info.number_of_readers, info.maximum_number_of_readers
+            );
+            println!("pid\tthread\ttxnid");
+            for reader in backend.readers()? {
+                let txn_id = reader.txn_id.map_or_else(|| "-".to_owned(), |id| id.to_string());
+                println!("{}\t{:x}\t{txn_id}", reader.pid, reader.thread);
+            }
+        }
+        Command::ClearStaleReaders => {
+            println!("{} stale readers cleared", backend.clear_stale_readers()?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses a `start..end` or `start..=end` range, where both bounds are optional.
+fn parse_range(codec: Codec, range: &str) -> Result<Scan, Box<dyn Error>> {
+    let (start, end, inclusive) = match range.split_once("..=") {
+        Some((start, end)) => (start, end, true),
+        None => match range.split_once("..") {
+            Some((start, end)) => (start, end, false),
+            None => return Err("a range must be written `start..end` or `start..=end`".into()),
+        },
+    };
+
+    let start = match start {
+        "" => Bound::Unbounded,
+        start => Bound::Included(codec.encode(start)?),
+    };
+    let end = match end {
+        "" => Bound::Unbounded,
+        end if inclusive => Bound::Included(codec.encode(end)?),
+        end => Bound::Excluded(codec.encode(end)?),
+    };
+
+    Ok(Scan::Range(start, end))
+}
@@ -0,0 +1,43 @@
+use syn::{DeriveInput, LitStr, Path};
+
+/// The options of the `#[heed(...)]` attribute of the derived type.
+pub struct Attributes {
+    /// The path of the crate providing the traits, `heed` by default.
+    pub krate: Path,
+    /// The name of the codec to delegate to, only used by `HeedCodec`.
+    pub codec: Option<LitStr>,
+}
+
+impl Attributes {
+    pub fn parse(input: &DeriveInput) -> syn::Result<Attributes> {
+        let mut krate = None;
+        let mut codec = None;
+
+        for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("heed")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("crate") {
+                    let path: LitStr = meta.value()?.parse()?;
+                    krate = Some(path.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("codec") {
+                    codec = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported heed attribute, expected `crate` or `codec`"))
+                }
+            })?;
+        }
+
+        let krate = krate.unwrap_or_else(|| syn::parse_quote!(::heed));
+        Ok(Attributes { krate, codec })
+    }
+}
+
+/// Returns an error if the type has generic parameters, they are not supported.
+pub fn reject_generics(input: &DeriveInput) -> syn::Result<()> {
+    if input.generics.params.is_empty() {
+        Ok(())
+    } else {
+        Err(syn::Error::new_spanned(&input.generics, "generic types are not supported"))
+    }
+}
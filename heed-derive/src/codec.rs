@@ -0,0 +1,223 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, LitInt};
+
+use crate::attributes::{reject_generics, Attributes};
+use crate::key::Layout;
+
+pub fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    reject_generics(input)?;
+    let Attributes { krate, codec } = Attributes::parse(input)?;
+
+    let Some(codec) = codec else {
+        let msg = "missing `#[heed(codec = \"...\")]` attribute, \
+            expected json, bincode, rmp or zero-copy";
+        return Err(syn::Error::new_spanned(&input.ident, msg));
+    };
+    let codec_type = match codec.value().as_str() {
+        "json" => format_ident!("SerdeJson"),
+        "bincode" => format_ident!("SerdeBincode"),
+        "rmp" => format_ident!("SerdeRmp"),
+        "zero-copy" => return expand_zero_copy(input, &krate),
+        _ => {
+            return Err(syn::Error::new_spanned(
+                codec,
+                "unknown codec, expected json, bincode, rmp or zero-copy",
+            ))
+        }
+    };
+
+    let name = &input.ident;
+    let delegate = quote! { #krate::types::#codec_type<#name> };
+
+    Ok(quote! {
+        impl<'a> #krate::BytesEncode<'a> for #name {
+            type EItem = #name;
+
+            fn bytes_encode(
+                item: &'a Self::EItem,
+            ) -> ::std::result::Result<::std::borrow::Cow<'a, [u8]>, #krate::BoxedError> {
+                <#delegate as #krate::BytesEncode<'a>>::bytes_encode(item)
+            }
+        }
+
+        impl<'a> #krate::BytesDecode<'a> for #name {
+            type DItem = #name;
+
+            fn bytes_decode(
+                bytes: &'a [u8],
+            ) -> ::std::result::Result<Self::DItem, #krate::BoxedError> {
+                <#delegate as #krate::BytesDecode<'a>>::bytes_decode(bytes)
+            }
+        }
+    })
+}
+
+/// Implements the codec by reinterpreting the bytes of a `#[repr(C, packed)]` struct made of
+/// integers and byte arrays: it has no padding, an alignment of one and no invalid bit pattern.
+fn expand_zero_copy(input: &DeriveInput, krate: &syn::Path) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new_spanned(name, "the zero-copy codec only supports structs"))
+        }
+    };
+
+    if !is_repr_c_packed(input)? {
+        let msg = "the zero-copy codec requires a `#[repr(C, packed)]` struct";
+        return Err(syn::Error::new_spanned(name, msg));
+    }
+
+    let mut widths = Vec::new();
+    for field in fields {
+        match Layout::of(&field.ty) {
+            Some(layout @ (Layout::Unsigned(_) | Layout::Signed { .. } | Layout::ByteArray(_))) => {
+                widths.extend(layout.width())
+            }
+            _ => {
+                let msg = "unsupported field type for the zero-copy codec, \
+                    expected a fixed-width integer or a byte array";
+                return Err(syn::Error::new_spanned(&field.ty, msg));
+            }
+        }
+    }
+
+    let name_str = name.to_string();
+    Ok(quote! {
+        const _: () = ::std::assert!(::std::mem::size_of::<#name>() == 0 #(+ #widths)*);
+
+        impl<'a> #krate::BytesEncode<'a> for #name {
+            type EItem = #name;
+
+            fn bytes_encode(
+                item: &'a Self::EItem,
+            ) -> ::std::result::Result<::std::borrow::Cow<'a, [u8]>, #krate::BoxedError> {
+                // SAFETY: the struct is `repr(C, packed)` and only made of integers
+                // and byte arrays, all of its bytes are initialized.
+                let bytes = unsafe {
+                    ::std::slice::from_raw_parts(
+                        item as *const #name as *const u8,
+                        ::std::mem::size_of::<#name>(),
+                    )
+                };
+                ::std::result::Result::Ok(::std::borrow::Cow::Borrowed(bytes))
+            }
+        }
+
+        impl<'a> #krate::BytesDecode<'a> for #name {
+            type DItem = &'a #name;
+
+            fn bytes_decode(
+                bytes: &'a [u8],
+            ) -> ::std::result::Result<Self::DItem, #krate::BoxedError> {
+                if bytes.len() != ::std::mem::size_of::<#name>() {
+                    return ::std::result::Result::Err(
+                        ::std::format!(
+                            "expected {} bytes to decode a `{}`, got {}",
+                            ::std::mem::size_of::<#name>(),
+                            #name_str,
+                            bytes.len(),
+                        )
+                        .into(),
+                    );
+                }
+                // SAFETY: the struct is `repr(C, packed)`, it has an alignment of one, and
+                // it is only made of integers and byte arrays, any bytes are a valid value.
+                ::std::result::Result::Ok(unsafe { &*(bytes.as_ptr() as *const #name) })
+            }
+        }
+    })
+}
+
+/// Whether the struct is `#[repr(C, packed)]`, or `#[repr(C, packed(1))]`.
+fn is_repr_c_packed(input: &DeriveInput) -> syn::Result<bool> {
+    let (mut c, mut packed) = (false, false);
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") {
+                c = true;
+            } else if meta.path.is_ident("packed") {
+                packed = true;
+                if meta.input.peek(syn::token::Paren) {
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+                    packed = content.parse::<LitInt>()?.base10_parse::<u32>()? == 1;
+                }
+            } else if meta.input.peek(syn::token::Paren) {
+                let _content;
+                syn::parenthesized!(_content in meta.input);
+            }
+            Ok(())
+        })?;
+    }
+    Ok(c && packed)
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn error(input: DeriveInput) -> String {
+        expand(&input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn rejects_missing_and_unknown_codecs() {
+        assert!(error(parse_quote! { struct User; }).starts_with("missing `#[heed(codec"));
+        assert!(error(parse_quote! {
+            #[heed(codec = "yaml")]
+            struct User;
+        })
+        .starts_with("unknown codec"));
+        assert!(error(parse_quote! {
+            #[heed(format = "json")]
+            struct User;
+        })
+        .starts_with("unsupported heed attribute"));
+    }
+
+    #[test]
+    fn zero_copy_requires_a_packed_c_struct() {
+        let requires_repr = "the zero-copy codec requires a `#[repr(C, packed)]` struct";
+        assert_eq!(
+            error(parse_quote! {
+                #[heed(codec = "zero-copy")]
+                #[repr(C)]
+                struct Stats { count: u64 }
+            }),
+            requires_repr
+        );
+        assert_eq!(
+            error(parse_quote! {
+                #[heed(codec = "zero-copy")]
+                #[repr(packed)]
+                struct Stats { count: u64 }
+            }),
+            requires_repr
+        );
+        assert_eq!(
+            error(parse_quote! {
+                #[heed(codec = "zero-copy")]
+                #[repr(C, packed(2))]
+                struct Stats { count: u64 }
+            }),
+            requires_repr
+        );
+        assert!(error(parse_quote! {
+            #[heed(codec = "zero-copy")]
+            #[repr(C, packed)]
+            struct Stats { valid: bool }
+        })
+        .starts_with("unsupported field type for the zero-copy codec"));
+
+        let input = parse_quote! {
+            #[heed(codec = "zero-copy")]
+            #[repr(C, packed(1))]
+            struct Stats { count: u64, tag: [u8; 4] }
+        };
+        assert!(expand(&input).is_ok());
+    }
+}
@@ -0,0 +1,233 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Expr, Fields, GenericArgument, Ident, PathArguments, Type};
+
+use crate::attributes::{reject_generics, Attributes};
+
+/// How a field is laid out in the encoded key.
+pub enum Layout {
+    /// An unsigned integer of this type, stored in big-endian.
+    Unsigned(Ident),
+    /// A signed integer stored as the unsigned integer of the same width with its sign bit flipped.
+    Signed { signed: Ident, unsigned: Ident, bits: u32 },
+    /// A boolean stored as a single byte.
+    Bool,
+    /// A `[u8; N]` array stored as is.
+    ByteArray(Expr),
+    /// A `String` stored as is, only allowed as the last field.
+    String,
+    /// A `Vec<u8>` stored as is, only allowed as the last field.
+    Bytes,
+}
+
+impl Layout {
+    pub fn of(ty: &Type) -> Option<Layout> {
+        match ty {
+            Type::Array(array) if is_ident(&array.elem, "u8") => {
+                Some(Layout::ByteArray(array.len.clone()))
+            }
+            Type::Path(path) if path.qself.is_none() => {
+                let segment = path.path.segments.last()?;
+                let ident = segment.ident.to_string();
+                match ident.as_str() {
+                    "u8" | "u16" | "u32" | "u64" | "u128" => {
+                        Some(Layout::Unsigned(segment.ident.clone()))
+                    }
+                    "i8" | "i16" | "i32" | "i64" | "i128" => {
+                        let bits: u32 = ident[1..].parse().unwrap();
+                        let unsigned = Ident::new(&format!("u{bits}"), Span::call_site());
+                        Some(Layout::Signed { signed: segment.ident.clone(), unsigned, bits })
+                    }
+                    "bool" => Some(Layout::Bool),
+                    "String" => Some(Layout::String),
+                    "Vec" => match &segment.arguments {
+                        PathArguments::AngleBracketed(args) => match args.args.first() {
+                            Some(GenericArgument::Type(elem)) if is_ident(elem, "u8") => {
+                                Some(Layout::Bytes)
+                            }
+                            _ => None,
+                        },
+                        _ => None,
+                    },
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// The number of bytes of the field, `None` for the variable-length ones.
+    pub fn width(&self) -> Option<TokenStream> {
+        match self {
+            Layout::Unsigned(ty) => Some(quote! { ::std::mem::size_of::<#ty>() }),
+            Layout::Signed { unsigned, .. } => Some(quote! { ::std::mem::size_of::<#unsigned>() }),
+            Layout::Bool => Some(quote! { 1 }),
+            Layout::ByteArray(len) => Some(quote! { (#len) }),
+            Layout::String | Layout::Bytes => None,
+        }
+    }
+}
+
+fn is_ident(ty: &Type, ident: &str) -> bool {
+    matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident(ident))
+}
+
+pub fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    reject_generics(input)?;
+    let Attributes { krate, .. } = Attributes::parse(input)?;
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => return Err(syn::Error::new_spanned(&input.ident, "HeedKey only supports structs")),
+    };
+
+    let mut layouts = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let Some(layout) = Layout::of(&field.ty) else {
+            let msg = "unsupported field type, expected a fixed-width integer, \
+                a bool, a byte array, a String or a Vec<u8>";
+            return Err(syn::Error::new_spanned(&field.ty, msg));
+        };
+        if layout.width().is_none() && i + 1 != fields.len() {
+            let msg = "a variable-length field must be the last field of the key";
+            return Err(syn::Error::new_spanned(&field.ty, msg));
+        }
+        layouts.push(layout);
+    }
+
+    // The fields are decoded into bindings that can't collide with the locals of the
+    // generated code, whatever the names of the fields.
+    let members: Vec<_> = fields.members().collect();
+    let bindings: Vec<_> = (0..fields.len()).map(|i| format_ident!("__heed_field{i}")).collect();
+
+    let name = &input.ident;
+    let name_str = name.to_string();
+    let capacity = layouts.iter().filter_map(Layout::width);
+
+    let encode_fields = members.iter().zip(&layouts).map(|(member, layout)| match layout {
+        Layout::Unsigned(_) => quote! { bytes.extend_from_slice(&item.#member.to_be_bytes()); },
+        Layout::Signed { unsigned, bits, .. } => {
+            let shift = bits - 1;
+            quote! {
+                bytes.extend_from_slice(&((item.#member as #unsigned) ^ (1 << #shift)).to_be_bytes());
+            }
+        }
+        Layout::Bool => quote! { bytes.push(item.#member as u8); },
+        Layout::ByteArray(_) | Layout::Bytes => quote! { bytes.extend_from_slice(&item.#member); },
+        Layout::String => quote! { bytes.extend_from_slice(item.#member.as_bytes()); },
+    });
+
+    let decode_fields = bindings.iter().zip(&layouts).map(|(binding, layout)| {
+        let take = layout.width().map(|width| {
+            quote! {
+                if rest.len() < #width {
+                    return ::std::result::Result::Err(
+                        ::std::format!("not enough bytes to decode a `{}`", #name_str).into(),
+                    );
+                }
+                let (head, tail) = rest.split_at(#width);
+                rest = tail;
+            }
+        });
+        let value = match layout {
+            Layout::Unsigned(ty) => {
+                quote! { #ty::from_be_bytes(::std::convert::TryFrom::try_from(head).unwrap()) }
+            }
+            Layout::Signed { signed, unsigned, bits } => {
+                let shift = bits - 1;
+                quote! {
+                    (#unsigned::from_be_bytes(::std::convert::TryFrom::try_from(head).unwrap())
+                        ^ (1 << #shift)) as #signed
+                }
+            }
+            Layout::Bool => quote! {
+                match head[0] {
+                    0 => false,
+                    1 => true,
+                    _ => return ::std::result::Result::Err(
+                        ::std::format!("invalid bool while decoding a `{}`", #name_str).into(),
+                    ),
+                }
+            },
+            Layout::ByteArray(_) => quote! { ::std::convert::TryFrom::try_from(head).unwrap() },
+            Layout::String => quote! {
+                ::std::string::String::from_utf8(::std::mem::take(&mut rest).to_vec())?
+            },
+            Layout::Bytes => quote! { ::std::mem::take(&mut rest).to_vec() },
+        };
+        quote! {
+            #take
+            let #binding = #value;
+        }
+    });
+
+    let construct = match fields {
+        Fields::Named(_) => quote! { #name { #(#members: #bindings),* } },
+        Fields::Unnamed(_) => quote! { #name ( #(#bindings),* ) },
+        Fields::Unit => quote! { #name },
+    };
+
+    Ok(quote! {
+        impl<'a> #krate::BytesEncode<'a> for #name {
+            type EItem = #name;
+
+            #[allow(unused_mut, unused_variables)]
+            fn bytes_encode(
+                item: &'a Self::EItem,
+            ) -> ::std::result::Result<::std::borrow::Cow<'a, [u8]>, #krate::BoxedError> {
+                let mut bytes = ::std::vec::Vec::with_capacity(0 #(+ #capacity)*);
+                #(#encode_fields)*
+                ::std::result::Result::Ok(::std::borrow::Cow::Owned(bytes))
+            }
+        }
+
+        impl<'a> #krate::BytesDecode<'a> for #name {
+            type DItem = #name;
+
+            #[allow(unused_mut)]
+            fn bytes_decode(
+                bytes: &'a [u8],
+            ) -> ::std::result::Result<Self::DItem, #krate::BoxedError> {
+                let mut rest = bytes;
+                #(#decode_fields)*
+                if !rest.is_empty() {
+                    return ::std::result::Result::Err(
+                        ::std::format!("too many bytes to decode a `{}`", #name_str).into(),
+                    );
+                }
+                ::std::result::Result::Ok(#construct)
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn error(input: DeriveInput) -> String {
+        expand(&input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn rejects_unsupported_inputs() {
+        assert_eq!(error(parse_quote! { enum Key { A } }), "HeedKey only supports structs");
+        assert_eq!(
+            error(parse_quote! { struct Key { name: String, id: u32 } }),
+            "a variable-length field must be the last field of the key"
+        );
+        assert!(error(parse_quote! { struct Key(Vec<u16>); }).starts_with("unsupported field"));
+        assert!(error(parse_quote! { struct Key(f64); }).starts_with("unsupported field"));
+        assert_eq!(error(parse_quote! { struct Key<T>(T); }), "generic types are not supported");
+    }
+
+    #[test]
+    fn fields_are_decoded_into_reserved_bindings() {
+        let input = parse_quote! { struct Key { rest: u32, bytes: String } };
+        let output = expand(&input).unwrap().to_string();
+        assert!(output.contains("let __heed_field0 ="));
+        assert!(output.contains("Key { rest : __heed_field0 , bytes : __heed_field1 }"));
+    }
+}
@@ -0,0 +1,140 @@
+#![doc(
+    html_favicon_url = "https://raw.githubusercontent.com/meilisearch/heed/main/assets/heed-pigeon.ico?raw=true"
+)]
+#![doc(
+    html_logo_url = "https://raw.githubusercontent.com/meilisearch/heed/main/assets/heed-pigeon-logo.png?raw=true"
+)]
+
+//! Derive macros implementing the `BytesEncode` and `BytesDecode` traits of `heed`.
+//!
+//! The derived types are their own codecs: a `Database<UserKey, Unit>` stores `UserKey` keys.
+//! The generated code refers to the `heed` crate, the `#[heed(crate = "heed3")]` attribute
+//! makes it refer to the `heed3` crate instead. These macros are re-exported by `heed` and
+//! `heed3` when their `derive` feature is enabled.
+
+#![warn(missing_docs)]
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+mod attributes;
+mod codec;
+mod key;
+
+/// Implements `BytesEncode` and `BytesDecode` with an order-preserving big-endian layout.
+///
+/// The fields are encoded one after the other, in declaration order, so that the
+/// lexicographic order of the bytes is the order of the fields: the keys of a database are
+/// sorted by their first field, then by their second one, and so on. It makes the derived
+/// types suitable for keys, range queries and prefix iteration.
+///
+/// The supported fields are:
+/// - the fixed-width integers, `u8` to `u128` and `i8` to `i128`, stored in big-endian
+///   with the sign bit of the signed ones flipped so that negative numbers sort first,
+/// - `bool`, stored as a single byte,
+/// - byte arrays, `[u8; N]`, stored as is,
+/// - `String` and `Vec<u8>`, stored as is, but only as the last field as they are not
+///   delimited.
+///
+/// ```
+/// use heed::{BytesDecode, BytesEncode};
+/// use heed_derive::HeedKey;
+///
+/// #[derive(Debug, PartialEq, HeedKey)]
+/// struct EventKey {
+///     user_id: u32,
+///     timestamp: i64,
+///     kind: String,
+/// }
+///
+/// # fn main() -> Result<(), heed::BoxedError> {
+/// let key = EventKey { user_id: 42, timestamp: -1, kind: "login".to_string() };
+/// let bytes = EventKey::bytes_encode(&key)?;
+/// assert_eq!(&bytes[..4], &[0, 0, 0, 42]);
+/// assert_eq!(EventKey::bytes_decode(&bytes)?, key);
+///
+/// // The negative timestamps sort before the positive ones.
+/// let later = EventKey { user_id: 42, timestamp: 1, kind: "login".to_string() };
+/// assert!(bytes < EventKey::bytes_encode(&later)?);
+/// # Ok(()) }
+/// ```
+#[proc_macro_derive(HeedKey, attributes(heed))]
+pub fn derive_heed_key(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    key::expand(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// Implements `BytesEncode` and `BytesDecode` by delegating to a serde codec of `heed::types`
+/// or with a zero-copy layout.
+///
+/// The codec is chosen with the `#[heed(codec = "...")]` attribute:
+/// - `"json"` uses [`SerdeJson`](https://docs.rs/heed/latest/heed/types/struct.SerdeJson.html),
+///   it requires the `serde-json` feature of `heed`,
+/// - `"bincode"` uses [`SerdeBincode`](https://docs.rs/heed/latest/heed/types/struct.SerdeBincode.html),
+///   it requires the `serde-bincode` feature of `heed`,
+/// - `"rmp"` uses [`SerdeRmp`](https://docs.rs/heed/latest/heed/types/struct.SerdeRmp.html),
+///   it requires the `serde-rmp` feature of `heed`,
+/// - `"zero-copy"` stores the in-memory representation of the struct and decodes a reference
+///   to it that borrows the bytes of the database, without copying them.
+///
+/// With a serde codec, the type must implement `Serialize` and `Deserialize` without
+/// borrowing from the bytes.
+///
+/// The zero-copy codec requires a `#[repr(C, packed)]` struct whose fields are fixed-width
+/// integers or byte arrays, so that it has no padding, an alignment of one and no invalid
+/// value. The integers are stored in the native endianness: the entries can't be read on a
+/// machine of another endianness and the integers don't sort numerically, use
+/// [`HeedKey`](derive@HeedKey) for keys. The fields of a packed struct can't be borrowed,
+/// they must be copied out, `{ entry.count }`.
+///
+/// ```
+/// use heed::{BytesDecode, BytesEncode};
+/// use heed_derive::HeedCodec;
+///
+/// #[derive(Debug, PartialEq, HeedCodec)]
+/// #[heed(codec = "zero-copy")]
+/// #[repr(C, packed)]
+/// struct Stats {
+///     count: u64,
+///     sum: i64,
+///     tag: [u8; 4],
+/// }
+///
+/// # fn main() -> Result<(), heed::BoxedError> {
+/// let stats = Stats { count: 3, sum: -12, tag: *b"temp" };
+/// let bytes = Stats::bytes_encode(&stats)?;
+/// assert_eq!(bytes.len(), 20);
+///
+/// let decoded: &Stats = Stats::bytes_decode(&bytes)?;
+/// assert_eq!(decoded, &stats);
+/// assert_eq!({ decoded.sum }, -12);
+/// assert!(Stats::bytes_decode(&bytes[..19]).is_err());
+/// # Ok(()) }
+/// ```
+///
+/// A struct with a serde codec:
+///
+/// ```
+/// use heed::{BytesDecode, BytesEncode};
+/// use heed_derive::HeedCodec;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize, HeedCodec)]
+/// #[heed(codec = "json")]
+/// struct User {
+///     name: String,
+///     age: u8,
+/// }
+///
+/// # fn main() -> Result<(), heed::BoxedError> {
+/// let user = User { name: "ferris".to_string(), age: 9 };
+/// let bytes = User::bytes_encode(&user)?;
+/// assert_eq!(&bytes[..], br#"{"name":"ferris","age":9}"#);
+/// assert_eq!(User::bytes_decode(&bytes)?, user);
+/// # Ok(()) }
+/// ```
+#[proc_macro_derive(HeedCodec, attributes(heed))]
+pub fn derive_heed_codec(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    codec::expand(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
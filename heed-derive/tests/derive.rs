@@ -0,0 +1,64 @@
+use heed::{BytesDecode, BytesEncode};
+use heed_derive::{HeedCodec, HeedKey};
+
+/// The names of the fields are the names of the locals of the generated code.
+#[derive(Debug, PartialEq, HeedKey)]
+struct Shadowing {
+    rest: u16,
+    head: i32,
+    tail: bool,
+    item: [u8; 2],
+    bytes: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq, HeedKey)]
+struct Tuple(u8, i8, String);
+
+#[derive(Debug, PartialEq, HeedKey)]
+struct Unit;
+
+#[derive(Debug, PartialEq, HeedCodec)]
+#[heed(codec = "zero-copy")]
+#[repr(C, packed)]
+struct Stats {
+    count: u32,
+    sum: i64,
+}
+
+#[test]
+fn keys_with_shadowing_field_names_roundtrip() {
+    let key = Shadowing { rest: 1, head: -2, tail: true, item: *b"hi", bytes: vec![3, 4] };
+    let bytes = Shadowing::bytes_encode(&key).unwrap();
+    assert_eq!(&bytes[..], &[0, 1, 0x7f, 0xff, 0xff, 0xfe, 1, b'h', b'i', 3, 4]);
+    assert_eq!(Shadowing::bytes_decode(&bytes).unwrap(), key);
+}
+
+#[test]
+fn invalid_keys_are_rejected() {
+    let key = Tuple(1, -1, "a".to_string());
+    let bytes = Tuple::bytes_encode(&key).unwrap();
+    assert_eq!(Tuple::bytes_decode(&bytes).unwrap(), key);
+    assert!(Tuple::bytes_decode(&bytes[..1]).is_err());
+    assert!(Tuple::bytes_decode(&[0, 0, 0xff]).is_err());
+
+    let key = Shadowing { rest: 0, head: 0, tail: false, item: [0; 2], bytes: Vec::new() };
+    let bytes = Shadowing::bytes_encode(&key).unwrap();
+    let mut invalid_bool = bytes.to_vec();
+    invalid_bool[6] = 2;
+    assert!(Shadowing::bytes_decode(&invalid_bool).is_err());
+
+    assert!(Unit::bytes_encode(&Unit).unwrap().is_empty());
+    assert!(Unit::bytes_decode(&[0]).is_err());
+}
+
+#[test]
+fn zero_copy_borrows_the_bytes() {
+    let stats = Stats { count: 2, sum: -5 };
+    let bytes = Stats::bytes_encode(&stats).unwrap();
+    assert_eq!(bytes.len(), 12);
+
+    let decoded = Stats::bytes_decode(&bytes).unwrap();
+    assert_eq!(decoded as *const Stats as *const u8, bytes.as_ptr());
+    assert_eq!(decoded, &stats);
+    assert!(Stats::bytes_decode(&bytes[1..]).is_err());
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Mutex, RwLock};
+
+use crate::mdb::ffi;
+#[allow(unused)] // for cargo auto doc links
+use crate::{Database, Env, EnvOpenOptions, RwTxn};
+
+/// The operations committed by a single write transaction.
+///
+/// Batches are sent to the receivers returned by [`Env::subscribe`] once the transaction
+/// has been successfully committed, see [`EnvOpenOptions::change_capture`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeBatch {
+    /// The ID of the transaction that committed those changes.
+    pub txn_id: usize,
+    /// The operations, in the order they were performed in the transaction.
+    pub ops: Vec<ChangeOp>,
+}
+
+/// A raw write operation performed on a [`Database`] through a [`RwTxn`].
+///
+/// Databases are identified by their name, `None` being the unnamed database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeOp {
+    /// An entry has been written in the database.
+    Put {
+        /// The name of the database.
+        database: Option<String>,
+        /// The raw bytes of the key.
+        key: Vec<u8>,
+        /// The raw bytes of the data.
+        data: Vec<u8>,
+    },
+    /// An entry, or all the duplicates of a key, has been removed from the database.
+    Delete {
+        /// The name of the database.
+        database: Option<String>,
+        /// The raw bytes of the key.
+        key: Vec<u8>,
+        /// The raw bytes of the data when a single duplicate has been removed.
+        data: Option<Vec<u8>>,
+    },
+    /// All the entries of the database have been removed.
+    Clear {
+        /// The name of the database.
+        database: Option<String>,
+    },
+}
+
+impl ChangeOp {
+    /// Returns the name of the database the operation was performed on.
+    pub fn database(&self) -> Option<&str> {
+        match self {
+            ChangeOp::Put { database, .. }
+            | ChangeOp::Delete { database, .. }
+            | ChangeOp::Clear { database } => database.as_deref(),
+        }
+    }
+}
+
+/// Keeps track of the database names and the subscribers of an environment.
+pub(crate) struct ChangeFeed {
+    enabled: bool,
+    logged: bool,
+    databases: RwLock<HashMap<ffi::MDB_dbi, RegisteredDatabase>>,
+    subscribers: Mutex<Vec<Sender<ChangeBatch>>>,
+}
+
+/// What is known about a database opened in the environment.
+#[derive(Default)]
+struct RegisteredDatabase {
+    name: Option<String>,
+    custom_comparator: bool,
+}
+
+impl ChangeFeed {
+    pub(crate) fn new(enabled: bool, logged: bool) -> ChangeFeed {
+        ChangeFeed {
+            enabled: enabled || logged,
+            logged,
+            databases: RwLock::default(),
+            subscribers: Mutex::default(),
+        }
+    }
+
+    /// Whether the write transactions must record their operations.
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Whether the operations must be stored in the replication log.
+    pub(crate) fn is_logged(&self) -> bool {
+        self.logged
+    }
+
+    /// Remembers the name of a database that has just been opened and whether it has been
+    /// opened with a custom key comparator, returns `true` if it wasn't opened yet.
+    ///
+    /// LMDB frees the handles of the databases opened by a transaction that is aborted and reuses
+    /// them for the next databases opened, they must be forgotten with [`Self::forget_databases`].
+    pub(crate) fn register_database(
+        &self,
+        dbi: ffi::MDB_dbi,
+        name: Option<&str>,
+        custom_comparator: bool,
+    ) -> bool {
+        let mut databases = self.databases.write().unwrap();
+        match databases.get_mut(&dbi) {
+            Some(database) if database.name.as_deref() == name => {
+                // LMDB keeps the comparator of a database until its handle is freed.
+                database.custom_comparator |= custom_comparator;
+                false
+            }
+            _ => {
+                let name = name.map(ToOwned::to_owned);
+                databases.insert(dbi, RegisteredDatabase { name, custom_comparator });
+                true
+            }
+        }
+    }
+
+    /// Forgets the databases whose handles have been freed by LMDB.
+    pub(crate) fn forget_databases(&self, dbis: &[ffi::MDB_dbi]) {
+        if !dbis.is_empty() {
+            let mut databases = self.databases.write().unwrap();
+            dbis.iter().for_each(|dbi| drop(databases.remove(dbi)));
+        }
+    }
+
+    /// Returns the name of an opened database, `Some(None)` for the unnamed one
+    /// and `None` when the database has never been opened in this environment.
+    pub(crate) fn database_name(&self, dbi: ffi::MDB_dbi) -> Option<Option<String>> {
+        let databases = self.databases.read().unwrap();
+        databases.get(&dbi).map(|database| database.name.clone())
+    }
+
+    /// Whether the opened database uses a custom key comparator.
+    pub(crate) fn has_custom_comparator(&self, dbi: ffi::MDB_dbi) -> bool {
+        let databases = self.databases.read().unwrap();
+        databases.get(&dbi).is_some_and(|database| database.custom_comparator)
+    }
+
+    pub(crate) fn subscribe(&self) -> Receiver<ChangeBatch> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    /// Sends the batch to every subscriber and forgets the disconnected ones.
+    pub(crate) fn publish(&self, batch: ChangeBatch) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|sender| sender.send(batch.clone()).is_ok());
+    }
+}
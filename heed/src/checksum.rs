@@ -0,0 +1,189 @@
+//! Page checksums to detect the corruption of unencrypted environments.
+//!
+//! An environment opened with [`EnvOpenOptions::open_checksummed`] stores a checksum
+//! computed by a [`PageChecksum`] next to every page. The checksum of a page is verified
+//! every time it is read, a mismatch is reported as an [`MdbError::BadChecksum`]. LMDB only
+//! returns an error code, the number of the corrupted page is not available.
+//!
+//! The [`Crc32c`] and [`Xxh3`] implementations are available
+//! with the `crc32c` and `xxhash` features respectively.
+//!
+//! ```
+//! use heed3::checksum::PageChecksum;
+//! use heed3::types::*;
+//! use heed3::EnvOpenOptions;
+//!
+//! /// A toy checksum, use a real hash function instead.
+//! enum Sum {}
+//!
+//! impl PageChecksum for Sum {
+//!     const SIZE: u32 = 8;
+//!
+//!     fn checksum(page: &[u8], output: &mut [u8]) {
+//!         let sum = page.iter().fold(0u64, |sum, &b| sum.wrapping_mul(31).wrapping_add(b as u64));
+//!         output.copy_from_slice(&sum.to_le_bytes());
+//!     }
+//! }
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let dir = tempfile::tempdir()?;
+//! let env = unsafe { EnvOpenOptions::new().open_checksummed::<Sum, _>(dir.path())? };
+//!
+//! let mut wtxn = env.write_txn()?;
+//! let db = env.create_database::<Str, Str>(&mut wtxn, None)?;
+//! db.put(&mut wtxn, "hello", "world")?;
+//! wtxn.commit()?;
+//!
+//! let rtxn = env.read_txn()?;
+//! assert_eq!(db.get(&rtxn, "hello")?, Some("world"));
+//! # Ok(()) }
+//! ```
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::process::abort;
+
+use crate::mdb::ffi;
+#[allow(unused)] // for cargo auto doc links
+use crate::{EnvOpenOptions, MdbError};
+
+/// A checksum used by LMDB to verify the integrity of the pages of an environment.
+///
+/// The checksum is not keyed and must be deterministic: the same page must always
+/// give the same checksum, across processes and machines.
+pub trait PageChecksum {
+    /// The size in bytes of the checksum stored with every page.
+    const SIZE: u32;
+
+    /// Computes the checksum of the `page` into the `output`, which is [`PageChecksum::SIZE`] long.
+    fn checksum(page: &[u8], output: &mut [u8]);
+}
+
+/// The CRC-32C (Castagnoli) checksum, hardware accelerated on most CPUs.
+#[cfg(feature = "crc32c")]
+pub enum Crc32c {}
+
+#[cfg(feature = "crc32c")]
+impl PageChecksum for Crc32c {
+    const SIZE: u32 = 4;
+
+    fn checksum(page: &[u8], output: &mut [u8]) {
+        output.copy_from_slice(&crc32c::crc32c(page).to_le_bytes());
+    }
+}
+
+/// The 64 bits XXH3 hash, a fast non-cryptographic hash function.
+#[cfg(feature = "xxhash")]
+pub enum Xxh3 {}
+
+#[cfg(feature = "xxhash")]
+impl PageChecksum for Xxh3 {
+    const SIZE: u32 = 8;
+
+    fn checksum(page: &[u8], output: &mut [u8]) {
+        output.copy_from_slice(&xxhash_rust::xxh3::xxh3_64(page).to_le_bytes());
+    }
+}
+
+/// The wrapper function that is called by LMDB that directly calls
+/// the Rust idiomatic function internally.
+pub(crate) unsafe extern "C" fn checksum_func_wrapper<H: PageChecksum>(
+    src: *const ffi::MDB_val,
+    dst: *mut ffi::MDB_val,
+    _key: *const ffi::MDB_val,
+) {
+    let input = std::slice::from_raw_parts((*src).mv_data as *const u8, (*src).mv_size);
+    let output = std::slice::from_raw_parts_mut((*dst).mv_data as *mut u8, (*dst).mv_size);
+    // LMDB has no way to report an error from a checksum function,
+    // the output is never used after a panic as the process is aborted.
+    if catch_unwind(AssertUnwindSafe(|| H::checksum(input, output))).is_err() {
+        abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::types::*;
+    use crate::{EnvOpenOptions, Error};
+
+    /// A toy checksum, use a real hash function instead.
+    enum Sum {}
+
+    impl PageChecksum for Sum {
+        const SIZE: u32 = 8;
+
+        fn checksum(page: &[u8], output: &mut [u8]) {
+            let sum = page.iter().fold(0u64, |s, &b| s.wrapping_mul(31).wrapping_add(b as u64));
+            output.copy_from_slice(&sum.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        let marker = b"corrupt-me-please";
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().open_checksummed::<Sum, _>(dir.path()).unwrap() };
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<Str, Bytes>(&mut wtxn, None).unwrap();
+        db.put(&mut wtxn, "key", marker).unwrap();
+        wtxn.commit().unwrap();
+        env.prepare_for_closing().wait();
+
+        let env = unsafe { EnvOpenOptions::new().open_checksummed::<Sum, _>(dir.path()).unwrap() };
+        let rtxn = env.read_txn().unwrap();
+        let db = env.open_database::<Str, Bytes>(&rtxn, None).unwrap().unwrap();
+        assert_eq!(db.get(&rtxn, "key").unwrap(), Some(&marker[..]));
+        drop(rtxn);
+        env.prepare_for_closing().wait();
+
+        // Flip a byte of the value directly in the data file.
+        let path = dir.path().join("data.mdb");
+        let mut bytes = fs::read(&path).unwrap();
+        let offset = bytes.windows(marker.len()).position(|w| w == marker).unwrap();
+        bytes[offset] ^= 0xff;
+        fs::write(&path, bytes).unwrap();
+
+        let env = unsafe { EnvOpenOptions::new().open_checksummed::<Sum, _>(dir.path()).unwrap() };
+        let rtxn = env.read_txn().unwrap();
+        let db = env.open_database::<Str, Bytes>(&rtxn, None).unwrap().unwrap();
+        let result = db.get(&rtxn, "key");
+        assert!(matches!(result, Err(Error::Mdb(MdbError::BadChecksum))));
+    }
+
+    #[test]
+    fn compaction_keeps_the_checksum() {
+        let marker = b"corrupt-me-please";
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().open_checksummed::<Sum, _>(dir.path()).unwrap() };
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<Str, Bytes>(&mut wtxn, None).unwrap();
+        for i in 0..100 {
+            db.put(&mut wtxn, &format!("key-{i}"), b"value").unwrap();
+        }
+        db.put(&mut wtxn, "key", marker).unwrap();
+        wtxn.commit().unwrap();
+
+        let env = unsafe { env.compact() }.unwrap();
+        let rtxn = env.read_txn().unwrap();
+        let db = env.open_database::<Str, Bytes>(&rtxn, None).unwrap().unwrap();
+        assert_eq!(db.len(&rtxn).unwrap(), 101);
+        assert_eq!(db.get(&rtxn, "key").unwrap(), Some(&marker[..]));
+        drop(rtxn);
+        env.prepare_for_closing().wait();
+
+        // The compacted pages are still verified.
+        let path = dir.path().join("data.mdb");
+        let mut bytes = fs::read(&path).unwrap();
+        let offset = bytes.windows(marker.len()).position(|w| w == marker).unwrap();
+        bytes[offset] ^= 0xff;
+        fs::write(&path, bytes).unwrap();
+
+        let env = unsafe { EnvOpenOptions::new().open_checksummed::<Sum, _>(dir.path()).unwrap() };
+        let rtxn = env.read_txn().unwrap();
+        let db = env.open_database::<Str, Bytes>(&rtxn, None).unwrap().unwrap();
+        let result = db.get(&rtxn, "key");
+        assert!(matches!(result, Err(Error::Mdb(MdbError::BadChecksum))));
+    }
+}
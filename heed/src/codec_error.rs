@@ -0,0 +1,203 @@
+use std::fmt::{self, Write as _};
+use std::{any, error};
+
+use crate::{BoxedError, Error};
+
+/// The number of bytes shown in the preview of a [`DecodingError`].
+const PREVIEW_LEN: usize = 32;
+
+/// The part of an entry a codec failed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntrySide {
+    /// The key of the entry, handled by the key codec.
+    Key,
+    /// The data of the entry, handled by the data codec.
+    Data,
+}
+
+impl fmt::Display for EntrySide {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EntrySide::Key => f.write_str("key"),
+            EntrySide::Data => f.write_str("data"),
+        }
+    }
+}
+
+/// An error returned by a [`BytesEncode`](crate::BytesEncode) implementation,
+/// with the database and the codec that failed.
+#[derive(Debug)]
+pub struct EncodingError {
+    database: Option<String>,
+    side: EntrySide,
+    codec: &'static str,
+    error: BoxedError,
+}
+
+impl EncodingError {
+    pub(crate) fn new<Codec: ?Sized>(
+        database: Option<String>,
+        side: EntrySide,
+        error: BoxedError,
+    ) -> EncodingError {
+        EncodingError { database, side, codec: any::type_name::<Codec>(), error }
+    }
+
+    /// The name of the database, `None` for the unnamed database.
+    pub fn database(&self) -> Option<&str> {
+        self.database.as_deref()
+    }
+
+    /// Whether the key or the data failed to encode.
+    pub fn side(&self) -> EntrySide {
+        self.side
+    }
+
+    /// The type name of the codec that failed.
+    pub fn codec_name(&self) -> &'static str {
+        self.codec
+    }
+
+    /// Returns the error returned by the codec.
+    pub fn into_inner(self) -> BoxedError {
+        self.error
+    }
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "error while encoding the {} with `{}`", self.side, self.codec)?;
+        write_database(f, self.database.as_deref())?;
+        write!(f, ": {}", self.error)
+    }
+}
+
+impl error::Error for EncodingError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&*self.error)
+    }
+}
+
+/// An error returned by a [`BytesDecode`](crate::BytesDecode) implementation,
+/// with the database, the codec that failed and a preview of the bytes.
+#[derive(Debug)]
+pub struct DecodingError {
+    database: Option<String>,
+    side: EntrySide,
+    codec: &'static str,
+    bytes_preview: String,
+    error: BoxedError,
+}
+
+impl DecodingError {
+    pub(crate) fn new<Codec: ?Sized>(
+        database: Option<String>,
+        side: EntrySide,
+        bytes: &[u8],
+        error: BoxedError,
+    ) -> DecodingError {
+        let mut bytes_preview = String::with_capacity(PREVIEW_LEN * 2 + 16);
+        for byte in bytes.iter().take(PREVIEW_LEN) {
+            write!(bytes_preview, "{byte:02x}").unwrap();
+        }
+        if bytes.len() > PREVIEW_LEN {
+            write!(bytes_preview, "... ({} bytes)", bytes.len()).unwrap();
+        }
+
+        DecodingError { database, side, codec: any::type_name::<Codec>(), bytes_preview, error }
+    }
+
+    /// The name of the database, `None` for the unnamed database.
+    pub fn database(&self) -> Option<&str> {
+        self.database.as_deref()
+    }
+
+    /// Whether the key or the data failed to decode.
+    pub fn side(&self) -> EntrySide {
+        self.side
+    }
+
+    /// The type name of the codec that failed.
+    pub fn codec_name(&self) -> &'static str {
+        self.codec
+    }
+
+    /// The hexadecimal representation of the first 32 bytes that failed to decode.
+    pub fn bytes_preview(&self) -> &str {
+        &self.bytes_preview
+    }
+
+    /// Returns the error returned by the codec.
+    pub fn into_inner(self) -> BoxedError {
+        self.error
+    }
+}
+
+impl fmt::Display for DecodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "error while decoding the {} with `{}`", self.side, self.codec)?;
+        write_database(f, self.database.as_deref())?;
+        write!(f, " (bytes: {}): {}", self.bytes_preview, self.error)
+    }
+}
+
+impl error::Error for DecodingError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&*self.error)
+    }
+}
+
+fn write_database(f: &mut fmt::Formatter, database: Option<&str>) -> fmt::Result {
+    match database {
+        Some(name) => write!(f, " in the `{name}` database"),
+        None => Ok(()),
+    }
+}
+
+/// Wraps the error of the `Codec` into an [`Error::Encoding`].
+pub(crate) fn encoding_error<Codec: ?Sized>(
+    database: Option<&str>,
+    side: EntrySide,
+    error: BoxedError,
+) -> Error {
+    let database = database.map(ToOwned::to_owned);
+    Error::Encoding(EncodingError::new::<Codec>(database, side, error))
+}
+
+/// Wraps the error of the `Codec` into an [`Error::Decoding`].
+pub(crate) fn decoding_error<Codec: ?Sized>(
+    database: Option<&str>,
+    side: EntrySide,
+    bytes: &[u8],
+    error: BoxedError,
+) -> Error {
+    let database = database.map(ToOwned::to_owned);
+    Error::Decoding(DecodingError::new::<Codec>(database, side, bytes, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decoding_error_display() {
+        let error = DecodingError::new::<crate::types::Str>(
+            Some("users".to_owned()),
+            EntrySide::Data,
+            &[0xff; 40],
+            "invalid utf-8".into(),
+        );
+
+        assert_eq!(error.database(), Some("users"));
+        assert_eq!(error.side(), EntrySide::Data);
+        assert_eq!(error.bytes_preview(), format!("{}... (40 bytes)", "ff".repeat(32)));
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "error while decoding the data with `heed_types::str::Str` in the `users` \
+                database (bytes: {}... (40 bytes)): invalid utf-8",
+                "ff".repeat(32)
+            )
+        );
+    }
+}
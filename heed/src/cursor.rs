@@ -1,22 +1,49 @@
 use std::ops::{Deref, DerefMut};
 use std::{marker, mem, ptr};
 
+use crate::codec_error::{decoding_error, encoding_error};
 use crate::mdb::error::mdb_result;
 use crate::mdb::ffi;
 use crate::*;
 
 pub struct RoCursor<'txn, T> {
     cursor: *mut ffi::MDB_cursor,
+    dbi: ffi::MDB_dbi,
+    database: Option<&'static str>,
     _marker: marker::PhantomData<&'txn T>,
 }
 
 impl<'txn, T> RoCursor<'txn, T> {
     // TODO should I ask for a &mut RoTxn<'_, T>, here?
-    pub(crate) fn new(txn: &'txn RoTxn<'_, T>, dbi: ffi::MDB_dbi) -> Result<RoCursor<'txn, T>> {
+    /// Opens a cursor on the database, whose name is used in the codec errors.
+    pub(crate) fn new(
+        txn: &'txn RoTxn<'_, T>,
+        dbi: ffi::MDB_dbi,
+        database: Option<&'static str>,
+    ) -> Result<RoCursor<'txn, T>> {
         let mut cursor: *mut ffi::MDB_cursor = ptr::null_mut();
-        let mut txn = txn.txn.unwrap();
-        unsafe { mdb_result(ffi::mdb_cursor_open(txn.as_mut(), dbi, &mut cursor))? }
-        Ok(RoCursor { cursor, _marker: marker::PhantomData })
+        let mut raw_txn = txn.txn.unwrap();
+        unsafe { mdb_result(ffi::mdb_cursor_open(raw_txn.as_mut(), dbi, &mut cursor))? }
+        Ok(RoCursor { cursor, dbi, database, _marker: marker::PhantomData })
+    }
+
+    /// Wraps the error of the `Codec` with the name of the database of this cursor.
+    pub(crate) fn encoding_error<Codec: ?Sized>(
+        &self,
+        side: EntrySide,
+        error: BoxedError,
+    ) -> Error {
+        encoding_error::<Codec>(self.database, side, error)
+    }
+
+    /// Wraps the error of the `Codec` with the name of the database of this cursor.
+    pub(crate) fn decoding_error<Codec: ?Sized>(
+        &self,
+        side: EntrySide,
+        bytes: &[u8],
+        error: BoxedError,
+    ) -> Error {
+        decoding_error::<Codec>(self.database, side, bytes, error)
     }
 
     pub fn current(&mut self) -> Result<Option<(&'txn [u8], &'txn [u8])>> {
@@ -245,11 +272,48 @@ impl<T> Drop for RoCursor<'_, T> {
 
 pub struct RwCursor<'txn> {
     cursor: RoCursor<'txn, WithoutTls>,
+    /// Used to record the writes when the change capture is enabled.
+    txn: &'txn RwTxn<'txn>,
 }
 
 impl<'txn> RwCursor<'txn> {
-    pub(crate) fn new(txn: &'txn RwTxn, dbi: ffi::MDB_dbi) -> Result<RwCursor<'txn>> {
-        Ok(RwCursor { cursor: RoCursor::new(txn, dbi)? })
+    pub(crate) fn new(
+        txn: &'txn RwTxn,
+        dbi: ffi::MDB_dbi,
+        database: Option<&'static str>,
+    ) -> Result<RwCursor<'txn>> {
+        Ok(RwCursor { cursor: RoCursor::new(txn, dbi, database)?, txn })
+    }
+
+    /// Returns the entry the cursor is pointing to when the writes must be recorded.
+    fn current_change(&mut self) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+        if !self.txn.captures_changes() {
+            return Ok(None);
+        }
+        let current = self.cursor.current()?;
+        Ok(current.map(|(key, data)| (key.to_vec(), data.to_vec())))
+    }
+
+    /// Whether the database of this cursor stores sorted duplicate data items.
+    fn is_dup_sort(&self) -> Result<bool> {
+        let mut flags = 0;
+        let mut raw_txn = self.txn.txn.txn.unwrap();
+        unsafe { mdb_result(ffi::mdb_dbi_flags(raw_txn.as_mut(), self.cursor.dbi, &mut flags))? };
+        Ok(DatabaseFlags::from_bits_truncate(flags).contains(DatabaseFlags::DUP_SORT))
+    }
+
+    /// Records the entry the cursor has just written, replacing the `replaced`
+    /// duplicate data item when it has been overwritten.
+    fn record_put(&mut self, replaced: Option<(Vec<u8>, Vec<u8>)>) -> Result<()> {
+        let dbi = self.cursor.dbi;
+        if let Some((key, data)) = replaced {
+            let data = Some(data);
+            self.txn.record_change(dbi, |database| ChangeOp::Delete { database, key, data })?;
+        }
+        if let Some((key, data)) = self.current_change()? {
+            self.txn.record_change(dbi, |database| ChangeOp::Put { database, key, data })?;
+        }
+        Ok(())
     }
 
     /// Delete the entry the cursor is currently pointing to.
@@ -266,11 +330,25 @@ impl<'txn> RwCursor<'txn> {
     ///
     /// [undefined behavior]: https://doc.rust-lang.org/reference/behavior-considered-undefined.html
     pub unsafe fn del_current(&mut self) -> Result<bool> {
+        let deleted = self.current_change()?;
+
         // Delete the current entry
         let result = mdb_result(ffi::mdb_cursor_del(self.cursor.cursor, 0));
 
         match result {
-            Ok(()) => Ok(true),
+            Ok(()) => {
+                if let Some((key, data)) = deleted {
+                    // Only the current duplicate data item is deleted.
+                    let data = self.is_dup_sort()?.then_some(data);
+                    let dbi = self.cursor.dbi;
+                    self.txn.record_change(dbi, |database| ChangeOp::Delete {
+                        database,
+                        key,
+                        data,
+                    })?;
+                }
+                Ok(true)
+            }
             Err(e) if e.not_found() => Ok(false),
             Err(e) => Err(e.into()),
         }
@@ -300,6 +378,11 @@ impl<'txn> RwCursor<'txn> {
     ///
     /// [undefined behavior]: https://doc.rust-lang.org/reference/behavior-considered-undefined.html
     pub unsafe fn put_current(&mut self, key: &[u8], data: &[u8]) -> Result<bool> {
+        let replaced = match self.current_change()? {
+            Some(current) if self.is_dup_sort()? => Some(current),
+            _ => None,
+        };
+
         let mut key_val = crate::into_val(key);
         let mut data_val = crate::into_val(data);
 
@@ -312,7 +395,10 @@ impl<'txn> RwCursor<'txn> {
         ));
 
         match result {
-            Ok(()) => Ok(true),
+            Ok(()) => {
+                self.record_put(replaced)?;
+                Ok(true)
+            }
             Err(e) if e.not_found() => Ok(false),
             Err(e) => Err(e.into()),
         }
@@ -358,6 +444,9 @@ impl<'txn> RwCursor<'txn> {
         write_func(&mut reserved)?;
 
         if reserved.remaining() == 0 {
+            if found {
+                self.record_put(None)?;
+            }
             Ok(found)
         } else {
             Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())
@@ -399,7 +488,8 @@ impl<'txn> RwCursor<'txn> {
             flags.bits(),
         ));
 
-        result.map_err(Into::into)
+        result?;
+        self.record_put(None)
     }
 }
 
@@ -1,11 +1,15 @@
 use std::borrow::Cow;
+use std::collections::HashSet;
 use std::ops::{Bound, RangeBounds};
+use std::sync::{LazyLock, Mutex};
 use std::{any, fmt, marker, mem, ptr};
 
 use heed_traits::{Comparator, LexicographicComparator};
 use types::{DecodeIgnore, LazyDecode};
 
+use crate::codec_error::{decoding_error, encoding_error};
 use crate::cursor::MoveOperation;
+use crate::databases::fingerprint::{check_fingerprint, check_or_store_fingerprint};
 use crate::envs::DefaultComparator;
 use crate::iteration_method::MoveOnCurrentKeyDuplicates;
 use crate::mdb::error::mdb_result;
@@ -60,6 +64,7 @@ pub struct DatabaseOpenOptions<'e, 'n, T, KC, DC, C = DefaultComparator> {
     types: marker::PhantomData<(KC, DC, C)>,
     name: Option<&'n str>,
     flags: AllDatabaseFlags,
+    fingerprint: Option<&'n str>,
 }
 
 impl<'e, T> DatabaseOpenOptions<'e, 'static, T, Unspecified, Unspecified> {
@@ -70,6 +75,7 @@ impl<'e, T> DatabaseOpenOptions<'e, 'static, T, Unspecified, Unspecified> {
             types: Default::default(),
             name: None,
             flags: AllDatabaseFlags::empty(),
+            fingerprint: None,
         }
     }
 }
@@ -85,6 +91,7 @@ impl<'e, 'n, T, KC, DC, C> DatabaseOpenOptions<'e, 'n, T, KC, DC, C> {
             types: Default::default(),
             name: self.name,
             flags: self.flags,
+            fingerprint: self.fingerprint,
         }
     }
 
@@ -97,6 +104,7 @@ impl<'e, 'n, T, KC, DC, C> DatabaseOpenOptions<'e, 'n, T, KC, DC, C> {
             types: Default::default(),
             name: self.name,
             flags: self.flags,
+            fingerprint: self.fingerprint,
         }
     }
 
@@ -114,6 +122,52 @@ impl<'e, 'n, T, KC, DC, C> DatabaseOpenOptions<'e, 'n, T, KC, DC, C> {
         self
     }
 
+    /// Checks that the database is opened with the codecs, comparator and flags it was
+    /// created with. By default the database is not fingerprinted.
+    ///
+    /// The codecs and comparator are identified by `id`, a stable name chosen by the
+    /// application, for example `"scores: str to u32 big-endian"`, that must be changed
+    /// whenever the key codec, data codec or comparator of the database changes.
+    /// The type names of the codecs are only recorded to be reported in the errors.
+    ///
+    /// When set, [`Self::create`] stores a [`DatabaseFingerprint`] of the database in
+    /// an internal database named `heed.fingerprints`, unless one is already stored, and both
+    /// [`Self::open`] and [`Self::create`] return an [`Error::FingerprintMismatch`] if the stored
+    /// identifier, comparator type name or flags differ. The flags are the ones the database
+    /// has been created with, they don't need to be given again when opening it.
+    /// The internal database counts in the [`EnvOpenOptions::max_dbs`].
+    ///
+    /// ```
+    /// use heed::types::*;
+    /// use heed::byteorder::{BigEndian, LittleEndian};
+    /// use heed::{EnvOpenOptions, Error};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir = tempfile::tempdir()?;
+    /// let env = unsafe { EnvOpenOptions::new().max_dbs(2).open(dir.path())? };
+    ///
+    /// let mut wtxn = env.write_txn()?;
+    /// env.database_options()
+    ///     .types::<Str, U32<BigEndian>>()
+    ///     .name("scores")
+    ///     .fingerprint("scores-v1")
+    ///     .create(&mut wtxn)?;
+    /// wtxn.commit()?;
+    ///
+    /// let rtxn = env.read_txn()?;
+    /// let result = env.database_options()
+    ///     .types::<Str, U32<LittleEndian>>()
+    ///     .name("scores")
+    ///     .fingerprint("scores-v2")
+    ///     .open(&rtxn);
+    /// assert!(matches!(result, Err(Error::FingerprintMismatch(_))));
+    /// # Ok(()) }
+    /// ```
+    pub fn fingerprint(&mut self, id: &'n str) -> &mut Self {
+        self.fingerprint = Some(id);
+        self
+    }
+
     /// Opens a typed database that already exists in this environment.
     ///
     /// If the database was previously opened in this program run, types will be checked.
@@ -140,11 +194,18 @@ impl<'e, 'n, T, KC, DC, C> DatabaseOpenOptions<'e, 'n, T, KC, DC, C> {
     {
         assert_eq_env_txn!(self.env, rtxn);
 
-        match self.env.raw_init_database::<C>(rtxn.txn.unwrap(), self.name, self.flags) {
-            Ok(dbi) => Ok(Some(Database::new(self.env.env_mut_ptr().as_ptr() as _, dbi))),
-            Err(Error::Mdb(e)) if e.not_found() => Ok(None),
-            Err(e) => Err(e),
+        let dbi = match self.env.raw_init_database::<C, _>(rtxn, self.name, self.flags) {
+            Ok(dbi) => dbi,
+            Err(Error::Mdb(e)) if e.not_found() => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        if let Some(id) = self.fingerprint {
+            check_fingerprint(self.env, rtxn, self.name, self.fingerprint_of(id, rtxn, dbi)?)?;
         }
+
+        let name = self.name.map(intern_database_name);
+        Ok(Some(Database::new(self.env.env_mut_ptr().as_ptr() as _, dbi, name)))
     }
 
     /// Creates a typed database that can already exist in this environment.
@@ -165,10 +226,29 @@ impl<'e, 'n, T, KC, DC, C> DatabaseOpenOptions<'e, 'n, T, KC, DC, C> {
         assert_eq_env_txn!(self.env, wtxn);
 
         let flags = self.flags | AllDatabaseFlags::CREATE;
-        match self.env.raw_init_database::<C>(wtxn.txn.txn.unwrap(), self.name, flags) {
-            Ok(dbi) => Ok(Database::new(self.env.env_mut_ptr().as_ptr() as _, dbi)),
-            Err(e) => Err(e),
+        let dbi = self.env.raw_init_database::<C, _>(wtxn, self.name, flags)?;
+
+        if let Some(id) = self.fingerprint {
+            let fingerprint = self.fingerprint_of(id, wtxn, dbi)?;
+            check_or_store_fingerprint(self.env, wtxn, self.name, fingerprint)?;
         }
+
+        let name = self.name.map(intern_database_name);
+        Ok(Database::new(self.env.env_mut_ptr().as_ptr() as _, dbi, name))
+    }
+
+    /// The fingerprint of the opened database, with the flags it has been created with
+    /// as the ones given to open an existing database can be omitted.
+    fn fingerprint_of<U>(
+        &self,
+        id: &str,
+        txn: &RoTxn<U>,
+        dbi: ffi::MDB_dbi,
+    ) -> Result<DatabaseFingerprint> {
+        let mut flags = 0;
+        let mut raw_txn = txn.txn.unwrap();
+        unsafe { mdb_result(ffi::mdb_dbi_flags(raw_txn.as_mut(), dbi, &mut flags))? };
+        DatabaseFingerprint::of::<KC, DC, C>(id, DatabaseFlags::from_bits_truncate(flags))
     }
 }
 
@@ -295,12 +375,18 @@ impl<T, KC, DC, C> Copy for DatabaseOpenOptions<'_, '_, T, KC, DC, C> {}
 pub struct Database<KC, DC, C = DefaultComparator> {
     pub(crate) env_ident: usize,
     pub(crate) dbi: ffi::MDB_dbi,
+    /// The name given when opening the database, reported in the codec errors.
+    pub(crate) name: Option<&'static str>,
     marker: marker::PhantomData<(KC, DC, C)>,
 }
 
 impl<KC, DC, C> Database<KC, DC, C> {
-    pub(crate) fn new(env_ident: usize, dbi: ffi::MDB_dbi) -> Database<KC, DC, C> {
-        Database { env_ident, dbi, marker: std::marker::PhantomData }
+    pub(crate) fn new(
+        env_ident: usize,
+        dbi: ffi::MDB_dbi,
+        name: Option<&'static str>,
+    ) -> Database<KC, DC, C> {
+        Database { env_ident, dbi, name, marker: std::marker::PhantomData }
     }
 
     /// Retrieves the value associated with a key.
@@ -351,7 +437,7 @@ impl<KC, DC, C> Database<KC, DC, C> {
     {
         assert_eq_env_db_txn!(self, txn);
 
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(|e| self.key_encoding_error(e))?;
 
         let mut key_val = unsafe { crate::into_val(&key_bytes) };
         let mut data_val = mem::MaybeUninit::uninit();
@@ -368,7 +454,7 @@ impl<KC, DC, C> Database<KC, DC, C> {
         match result {
             Ok(()) => {
                 let data = unsafe { crate::from_val(data_val.assume_init()) };
-                let data = DC::bytes_decode(data).map_err(Error::Decoding)?;
+                let data = DC::bytes_decode(data).map_err(|e| self.data_decoding_error(data, e))?;
                 Ok(Some(data))
             }
             Err(e) if e.not_found() => Ok(None),
@@ -439,8 +525,8 @@ impl<KC, DC, C> Database<KC, DC, C> {
     {
         assert_eq_env_db_txn!(self, txn);
 
-        let mut cursor = RoCursor::new(txn, self.dbi)?;
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        let mut cursor = RoCursor::new(txn, self.dbi, self.name)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(|e| self.key_encoding_error(e))?;
         if cursor.move_on_key(&key_bytes)? {
             Ok(Some(RoIter::new(cursor)))
         } else {
@@ -503,14 +589,15 @@ impl<KC, DC, C> Database<KC, DC, C> {
     {
         assert_eq_env_db_txn!(self, txn);
 
-        let mut cursor = RoCursor::new(txn, self.dbi)?;
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        let mut cursor = RoCursor::new(txn, self.dbi, self.name)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(|e| self.key_encoding_error(e))?;
         cursor.move_on_key_greater_than_or_equal_to(&key_bytes)?;
 
         match cursor.move_on_prev(MoveOperation::NoDup) {
             Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                 (Ok(key), Ok(data)) => Ok(Some((key, data))),
-                (Err(e), _) | (_, Err(e)) => Err(Error::Decoding(e)),
+                (Err(e), _) => Err(self.key_decoding_error(key, e)),
+                (_, Err(e)) => Err(self.data_decoding_error(data, e)),
             },
             Ok(None) => Ok(None),
             Err(e) => Err(e),
@@ -572,8 +659,8 @@ impl<KC, DC, C> Database<KC, DC, C> {
     {
         assert_eq_env_db_txn!(self, txn);
 
-        let mut cursor = RoCursor::new(txn, self.dbi)?;
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        let mut cursor = RoCursor::new(txn, self.dbi, self.name)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(|e| self.key_encoding_error(e))?;
         let result = match cursor.move_on_key_greater_than_or_equal_to(&key_bytes) {
             Ok(Some((key, data))) if key == &key_bytes[..] => Ok(Some((key, data))),
             Ok(_) => cursor.move_on_prev(MoveOperation::NoDup),
@@ -583,7 +670,8 @@ impl<KC, DC, C> Database<KC, DC, C> {
         match result {
             Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                 (Ok(key), Ok(data)) => Ok(Some((key, data))),
-                (Err(e), _) | (_, Err(e)) => Err(Error::Decoding(e)),
+                (Err(e), _) => Err(self.key_decoding_error(key, e)),
+                (_, Err(e)) => Err(self.data_decoding_error(data, e)),
             },
             Ok(None) => Ok(None),
             Err(e) => Err(e),
@@ -645,8 +733,8 @@ impl<KC, DC, C> Database<KC, DC, C> {
     {
         assert_eq_env_db_txn!(self, txn);
 
-        let mut cursor = RoCursor::new(txn, self.dbi)?;
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        let mut cursor = RoCursor::new(txn, self.dbi, self.name)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(|e| self.key_encoding_error(e))?;
         let entry = match cursor.move_on_key_greater_than_or_equal_to(&key_bytes)? {
             Some((key, data)) if key > &key_bytes[..] => Some((key, data)),
             Some((_key, _data)) => cursor.move_on_next(MoveOperation::NoDup)?,
@@ -656,7 +744,8 @@ impl<KC, DC, C> Database<KC, DC, C> {
         match entry {
             Some((key, data)) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                 (Ok(key), Ok(data)) => Ok(Some((key, data))),
-                (Err(e), _) | (_, Err(e)) => Err(Error::Decoding(e)),
+                (Err(e), _) => Err(self.key_decoding_error(key, e)),
+                (_, Err(e)) => Err(self.data_decoding_error(data, e)),
             },
             None => Ok(None),
         }
@@ -717,12 +806,13 @@ impl<KC, DC, C> Database<KC, DC, C> {
     {
         assert_eq_env_db_txn!(self, txn);
 
-        let mut cursor = RoCursor::new(txn, self.dbi)?;
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        let mut cursor = RoCursor::new(txn, self.dbi, self.name)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(|e| self.key_encoding_error(e))?;
         match cursor.move_on_key_greater_than_or_equal_to(&key_bytes) {
             Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                 (Ok(key), Ok(data)) => Ok(Some((key, data))),
-                (Err(e), _) | (_, Err(e)) => Err(Error::Decoding(e)),
+                (Err(e), _) => Err(self.key_decoding_error(key, e)),
+                (_, Err(e)) => Err(self.data_decoding_error(data, e)),
             },
             Ok(None) => Ok(None),
             Err(e) => Err(e),
@@ -772,11 +862,12 @@ impl<KC, DC, C> Database<KC, DC, C> {
     {
         assert_eq_env_db_txn!(self, txn);
 
-        let mut cursor = RoCursor::new(txn, self.dbi)?;
+        let mut cursor = RoCursor::new(txn, self.dbi, self.name)?;
         match cursor.move_on_first(MoveOperation::Any) {
             Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                 (Ok(key), Ok(data)) => Ok(Some((key, data))),
-                (Err(e), _) | (_, Err(e)) => Err(Error::Decoding(e)),
+                (Err(e), _) => Err(self.key_decoding_error(key, e)),
+                (_, Err(e)) => Err(self.data_decoding_error(data, e)),
             },
             Ok(None) => Ok(None),
             Err(e) => Err(e),
@@ -826,11 +917,12 @@ impl<KC, DC, C> Database<KC, DC, C> {
     {
         assert_eq_env_db_txn!(self, txn);
 
-        let mut cursor = RoCursor::new(txn, self.dbi)?;
+        let mut cursor = RoCursor::new(txn, self.dbi, self.name)?;
         match cursor.move_on_last(MoveOperation::Any) {
             Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                 (Ok(key), Ok(data)) => Ok(Some((key, data))),
-                (Err(e), _) | (_, Err(e)) => Err(Error::Decoding(e)),
+                (Err(e), _) => Err(self.key_decoding_error(key, e)),
+                (_, Err(e)) => Err(self.data_decoding_error(data, e)),
             },
             Ok(None) => Ok(None),
             Err(e) => Err(e),
@@ -1028,7 +1120,7 @@ impl<KC, DC, C> Database<KC, DC, C> {
     /// ```
     pub fn iter<'txn, T>(&self, txn: &'txn RoTxn<T>) -> Result<RoIter<'txn, T, KC, DC>> {
         assert_eq_env_db_txn!(self, txn);
-        RoCursor::new(txn, self.dbi).map(|cursor| RoIter::new(cursor))
+        RoCursor::new(txn, self.dbi, self.name).map(|cursor| RoIter::new(cursor))
     }
 
     /// Return a mutable ordered iterator of all key-value pairs in this database.
@@ -1084,7 +1176,7 @@ impl<KC, DC, C> Database<KC, DC, C> {
     pub fn iter_mut<'txn>(&self, txn: &'txn mut RwTxn) -> Result<RwIter<'txn, KC, DC>> {
         assert_eq_env_db_txn!(self, txn);
 
-        RwCursor::new(txn, self.dbi).map(|cursor| RwIter::new(cursor))
+        RwCursor::new(txn, self.dbi, self.name).map(|cursor| RwIter::new(cursor))
     }
 
     /// Return a reverse ordered iterator of all key-value pairs in this database.
@@ -1131,7 +1223,7 @@ impl<KC, DC, C> Database<KC, DC, C> {
     pub fn rev_iter<'txn, T>(&self, txn: &'txn RoTxn<T>) -> Result<RoRevIter<'txn, T, KC, DC>> {
         assert_eq_env_db_txn!(self, txn);
 
-        RoCursor::new(txn, self.dbi).map(|cursor| RoRevIter::new(cursor))
+        RoCursor::new(txn, self.dbi, self.name).map(|cursor| RoRevIter::new(cursor))
     }
 
     /// Return a mutable reverse ordered iterator of all key-value\
@@ -1188,7 +1280,7 @@ impl<KC, DC, C> Database<KC, DC, C> {
     pub fn rev_iter_mut<'txn>(&self, txn: &'txn mut RwTxn) -> Result<RwRevIter<'txn, KC, DC>> {
         assert_eq_env_db_txn!(self, txn);
 
-        RwCursor::new(txn, self.dbi).map(|cursor| RwRevIter::new(cursor))
+        RwCursor::new(txn, self.dbi, self.name).map(|cursor| RwRevIter::new(cursor))
     }
 
     /// Return an ordered iterator of a range of key-value pairs in this database.
@@ -1248,11 +1340,11 @@ impl<KC, DC, C> Database<KC, DC, C> {
 
         let start_bound = match range.start_bound() {
             Bound::Included(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
+                let bytes = KC::bytes_encode(bound).map_err(|e| self.key_encoding_error(e))?;
                 Bound::Included(bytes.into_owned())
             }
             Bound::Excluded(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
+                let bytes = KC::bytes_encode(bound).map_err(|e| self.key_encoding_error(e))?;
                 Bound::Excluded(bytes.into_owned())
             }
             Bound::Unbounded => Bound::Unbounded,
@@ -1260,17 +1352,18 @@ impl<KC, DC, C> Database<KC, DC, C> {
 
         let end_bound = match range.end_bound() {
             Bound::Included(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
+                let bytes = KC::bytes_encode(bound).map_err(|e| self.key_encoding_error(e))?;
                 Bound::Included(bytes.into_owned())
             }
             Bound::Excluded(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
+                let bytes = KC::bytes_encode(bound).map_err(|e| self.key_encoding_error(e))?;
                 Bound::Excluded(bytes.into_owned())
             }
             Bound::Unbounded => Bound::Unbounded,
         };
 
-        RoCursor::new(txn, self.dbi).map(|cursor| RoRange::new(cursor, start_bound, end_bound))
+        RoCursor::new(txn, self.dbi, self.name)
+            .map(|cursor| RoRange::new(cursor, start_bound, end_bound))
     }
 
     /// Return a mutable ordered iterator of a range of key-value pairs in this database.
@@ -1339,11 +1432,11 @@ impl<KC, DC, C> Database<KC, DC, C> {
 
         let start_bound = match range.start_bound() {
             Bound::Included(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
+                let bytes = KC::bytes_encode(bound).map_err(|e| self.key_encoding_error(e))?;
                 Bound::Included(bytes.into_owned())
             }
             Bound::Excluded(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
+                let bytes = KC::bytes_encode(bound).map_err(|e| self.key_encoding_error(e))?;
                 Bound::Excluded(bytes.into_owned())
             }
             Bound::Unbounded => Bound::Unbounded,
@@ -1351,17 +1444,18 @@ impl<KC, DC, C> Database<KC, DC, C> {
 
         let end_bound = match range.end_bound() {
             Bound::Included(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
+                let bytes = KC::bytes_encode(bound).map_err(|e| self.key_encoding_error(e))?;
                 Bound::Included(bytes.into_owned())
             }
             Bound::Excluded(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
+                let bytes = KC::bytes_encode(bound).map_err(|e| self.key_encoding_error(e))?;
                 Bound::Excluded(bytes.into_owned())
             }
             Bound::Unbounded => Bound::Unbounded,
         };
 
-        RwCursor::new(txn, self.dbi).map(|cursor| RwRange::new(cursor, start_bound, end_bound))
+        RwCursor::new(txn, self.dbi, self.name)
+            .map(|cursor| RwRange::new(cursor, start_bound, end_bound))
     }
 
     /// Return a reverse ordered iterator of a range of key-value pairs in this database.
@@ -1421,11 +1515,11 @@ impl<KC, DC, C> Database<KC, DC, C> {
 
         let start_bound = match range.start_bound() {
             Bound::Included(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
+                let bytes = KC::bytes_encode(bound).map_err(|e| self.key_encoding_error(e))?;
                 Bound::Included(bytes.into_owned())
             }
             Bound::Excluded(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
+                let bytes = KC::bytes_encode(bound).map_err(|e| self.key_encoding_error(e))?;
                 Bound::Excluded(bytes.into_owned())
             }
             Bound::Unbounded => Bound::Unbounded,
@@ -1433,17 +1527,18 @@ impl<KC, DC, C> Database<KC, DC, C> {
 
         let end_bound = match range.end_bound() {
             Bound::Included(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
+                let bytes = KC::bytes_encode(bound).map_err(|e| self.key_encoding_error(e))?;
                 Bound::Included(bytes.into_owned())
             }
             Bound::Excluded(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
+                let bytes = KC::bytes_encode(bound).map_err(|e| self.key_encoding_error(e))?;
                 Bound::Excluded(bytes.into_owned())
             }
             Bound::Unbounded => Bound::Unbounded,
         };
 
-        RoCursor::new(txn, self.dbi).map(|cursor| RoRevRange::new(cursor, start_bound, end_bound))
+        RoCursor::new(txn, self.dbi, self.name)
+            .map(|cursor| RoRevRange::new(cursor, start_bound, end_bound))
     }
 
     /// Return a mutable reverse ordered iterator of a range of key-value pairs in this database.
@@ -1512,11 +1607,11 @@ impl<KC, DC, C> Database<KC, DC, C> {
 
         let start_bound = match range.start_bound() {
             Bound::Included(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
+                let bytes = KC::bytes_encode(bound).map_err(|e| self.key_encoding_error(e))?;
                 Bound::Included(bytes.into_owned())
             }
             Bound::Excluded(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
+                let bytes = KC::bytes_encode(bound).map_err(|e| self.key_encoding_error(e))?;
                 Bound::Excluded(bytes.into_owned())
             }
             Bound::Unbounded => Bound::Unbounded,
@@ -1524,17 +1619,18 @@ impl<KC, DC, C> Database<KC, DC, C> {
 
         let end_bound = match range.end_bound() {
             Bound::Included(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
+                let bytes = KC::bytes_encode(bound).map_err(|e| self.key_encoding_error(e))?;
                 Bound::Included(bytes.into_owned())
             }
             Bound::Excluded(bound) => {
-                let bytes = KC::bytes_encode(bound).map_err(Error::Encoding)?;
+                let bytes = KC::bytes_encode(bound).map_err(|e| self.key_encoding_error(e))?;
                 Bound::Excluded(bytes.into_owned())
             }
             Bound::Unbounded => Bound::Unbounded,
         };
 
-        RwCursor::new(txn, self.dbi).map(|cursor| RwRevRange::new(cursor, start_bound, end_bound))
+        RwCursor::new(txn, self.dbi, self.name)
+            .map(|cursor| RwRevRange::new(cursor, start_bound, end_bound))
     }
 
     /// Return a lexicographically ordered iterator of all key-value pairs
@@ -1594,9 +1690,9 @@ impl<KC, DC, C> Database<KC, DC, C> {
     {
         assert_eq_env_db_txn!(self, txn);
 
-        let prefix_bytes = KC::bytes_encode(prefix).map_err(Error::Encoding)?;
+        let prefix_bytes = KC::bytes_encode(prefix).map_err(|e| self.key_encoding_error(e))?;
         let prefix_bytes = prefix_bytes.into_owned();
-        RoCursor::new(txn, self.dbi).map(|cursor| RoPrefix::new(cursor, prefix_bytes))
+        RoCursor::new(txn, self.dbi, self.name).map(|cursor| RoPrefix::new(cursor, prefix_bytes))
     }
 
     /// Return a mutable lexicographically ordered iterator of all key-value pairs
@@ -1665,9 +1761,9 @@ impl<KC, DC, C> Database<KC, DC, C> {
     {
         assert_eq_env_db_txn!(self, txn);
 
-        let prefix_bytes = KC::bytes_encode(prefix).map_err(Error::Encoding)?;
+        let prefix_bytes = KC::bytes_encode(prefix).map_err(|e| self.key_encoding_error(e))?;
         let prefix_bytes = prefix_bytes.into_owned();
-        RwCursor::new(txn, self.dbi).map(|cursor| RwPrefix::new(cursor, prefix_bytes))
+        RwCursor::new(txn, self.dbi, self.name).map(|cursor| RwPrefix::new(cursor, prefix_bytes))
     }
 
     /// Return a reversed lexicographically ordered iterator of all key-value pairs
@@ -1727,9 +1823,9 @@ impl<KC, DC, C> Database<KC, DC, C> {
     {
         assert_eq_env_db_txn!(self, txn);
 
-        let prefix_bytes = KC::bytes_encode(prefix).map_err(Error::Encoding)?;
+        let prefix_bytes = KC::bytes_encode(prefix).map_err(|e| self.key_encoding_error(e))?;
         let prefix_bytes = prefix_bytes.into_owned();
-        RoCursor::new(txn, self.dbi).map(|cursor| RoRevPrefix::new(cursor, prefix_bytes))
+        RoCursor::new(txn, self.dbi, self.name).map(|cursor| RoRevPrefix::new(cursor, prefix_bytes))
     }
 
     /// Return a mutable reversed lexicographically ordered iterator of all key-value pairs
@@ -1798,9 +1894,9 @@ impl<KC, DC, C> Database<KC, DC, C> {
     {
         assert_eq_env_db_txn!(self, txn);
 
-        let prefix_bytes = KC::bytes_encode(prefix).map_err(Error::Encoding)?;
+        let prefix_bytes = KC::bytes_encode(prefix).map_err(|e| self.key_encoding_error(e))?;
         let prefix_bytes = prefix_bytes.into_owned();
-        RwCursor::new(txn, self.dbi).map(|cursor| RwRevPrefix::new(cursor, prefix_bytes))
+        RwCursor::new(txn, self.dbi, self.name).map(|cursor| RwRevPrefix::new(cursor, prefix_bytes))
     }
 
     /// Insert a key-value pair in this database, replacing any previous value. The entry is
@@ -1845,8 +1941,9 @@ impl<KC, DC, C> Database<KC, DC, C> {
     {
         assert_eq_env_db_txn!(self, txn);
 
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let data_bytes: Cow<[u8]> = DC::bytes_encode(data).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(|e| self.key_encoding_error(e))?;
+        let data_bytes: Cow<[u8]> =
+            DC::bytes_encode(data).map_err(|e| self.data_encoding_error(e))?;
 
         let mut key_val = unsafe { crate::into_val(&key_bytes) };
         let mut data_val = unsafe { crate::into_val(&data_bytes) };
@@ -1862,6 +1959,12 @@ impl<KC, DC, C> Database<KC, DC, C> {
             ))?
         }
 
+        txn.record_change(self.dbi, |database| ChangeOp::Put {
+            database,
+            key: key_bytes.to_vec(),
+            data: data_bytes.to_vec(),
+        })?;
+
         Ok(())
     }
 
@@ -1914,7 +2017,7 @@ impl<KC, DC, C> Database<KC, DC, C> {
     {
         assert_eq_env_db_txn!(self, txn);
 
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(|e| self.key_encoding_error(e))?;
         let mut key_val = unsafe { crate::into_val(&key_bytes) };
         let mut reserved = ffi::reserve_size_val(data_size);
         let flags = ffi::MDB_RESERVE;
@@ -1932,6 +2035,11 @@ impl<KC, DC, C> Database<KC, DC, C> {
         let mut reserved = unsafe { ReservedSpace::from_val(reserved) };
         write_func(&mut reserved)?;
         if reserved.remaining() == 0 {
+            txn.record_change(self.dbi, |database| ChangeOp::Put {
+                database,
+                key: key_bytes.to_vec(),
+                data: reserved.written_mut().to_vec(),
+            })?;
             Ok(())
         } else {
             Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())
@@ -2010,8 +2118,9 @@ impl<KC, DC, C> Database<KC, DC, C> {
     {
         assert_eq_env_db_txn!(self, txn);
 
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let data_bytes: Cow<[u8]> = DC::bytes_encode(data).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(|e| self.key_encoding_error(e))?;
+        let data_bytes: Cow<[u8]> =
+            DC::bytes_encode(data).map_err(|e| self.data_encoding_error(e))?;
 
         let mut key_val = unsafe { crate::into_val(&key_bytes) };
         let mut data_val = unsafe { crate::into_val(&data_bytes) };
@@ -2027,6 +2136,12 @@ impl<KC, DC, C> Database<KC, DC, C> {
             ))?
         }
 
+        txn.record_change(self.dbi, |database| ChangeOp::Put {
+            database,
+            key: key_bytes.to_vec(),
+            data: data_bytes.to_vec(),
+        })?;
+
         Ok(())
     }
 
@@ -2123,8 +2238,9 @@ impl<KC, DC, C> Database<KC, DC, C> {
     {
         assert_eq_env_db_txn!(self, txn);
 
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let data_bytes: Cow<[u8]> = DC::bytes_encode(data).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(|e| self.key_encoding_error(e))?;
+        let data_bytes: Cow<[u8]> =
+            DC::bytes_encode(data).map_err(|e| self.data_encoding_error(e))?;
 
         let mut key_val = unsafe { crate::into_val(&key_bytes) };
         let mut data_val = unsafe { crate::into_val(&data_bytes) };
@@ -2142,11 +2258,19 @@ impl<KC, DC, C> Database<KC, DC, C> {
 
         match result {
             // the value was successfully inserted
-            Ok(()) => Ok(None),
+            Ok(()) => {
+                txn.record_change(self.dbi, |database| ChangeOp::Put {
+                    database,
+                    key: key_bytes.to_vec(),
+                    data: data_bytes.to_vec(),
+                })?;
+                Ok(None)
+            }
             // the key already exists: the previous value is stored in the data parameter
             Err(MdbError::KeyExist) => {
                 let bytes = unsafe { crate::from_val(data_val) };
-                let data = DC::bytes_decode(bytes).map_err(Error::Decoding)?;
+                let data =
+                    DC::bytes_decode(bytes).map_err(|e| self.data_decoding_error(bytes, e))?;
                 Ok(Some(data))
             }
             Err(error) => Err(error.into()),
@@ -2280,7 +2404,7 @@ impl<KC, DC, C> Database<KC, DC, C> {
     {
         assert_eq_env_db_txn!(self, txn);
 
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(|e| self.key_encoding_error(e))?;
 
         let mut key_val = unsafe { crate::into_val(&key_bytes) };
         let mut reserved = ffi::reserve_size_val(data_size);
@@ -2302,6 +2426,11 @@ impl<KC, DC, C> Database<KC, DC, C> {
                 let mut reserved = unsafe { ReservedSpace::from_val(reserved) };
                 write_func(&mut reserved)?;
                 if reserved.remaining() == 0 {
+                    txn.record_change(self.dbi, |database| ChangeOp::Put {
+                        database,
+                        key: key_bytes.to_vec(),
+                        data: reserved.written_mut().to_vec(),
+                    })?;
                     Ok(None)
                 } else {
                     Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())
@@ -2310,7 +2439,8 @@ impl<KC, DC, C> Database<KC, DC, C> {
             // the key already exists: the previous value is stored in the data parameter
             Err(MdbError::KeyExist) => {
                 let bytes = unsafe { crate::from_val(reserved) };
-                let data = DC::bytes_decode(bytes).map_err(Error::Decoding)?;
+                let data =
+                    DC::bytes_decode(bytes).map_err(|e| self.data_decoding_error(bytes, e))?;
                 Ok(Some(data))
             }
             Err(error) => Err(error.into()),
@@ -2366,7 +2496,7 @@ impl<KC, DC, C> Database<KC, DC, C> {
     {
         assert_eq_env_db_txn!(self, txn);
 
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(|e| self.key_encoding_error(e))?;
         let mut key_val = unsafe { crate::into_val(&key_bytes) };
 
         let result = unsafe {
@@ -2379,7 +2509,14 @@ impl<KC, DC, C> Database<KC, DC, C> {
         };
 
         match result {
-            Ok(()) => Ok(true),
+            Ok(()) => {
+                txn.record_change(self.dbi, |database| ChangeOp::Delete {
+                    database,
+                    key: key_bytes.to_vec(),
+                    data: None,
+                })?;
+                Ok(true)
+            }
             Err(e) if e.not_found() => Ok(false),
             Err(e) => Err(e.into()),
         }
@@ -2456,8 +2593,9 @@ impl<KC, DC, C> Database<KC, DC, C> {
     {
         assert_eq_env_db_txn!(self, txn);
 
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let data_bytes: Cow<[u8]> = DC::bytes_encode(data).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(|e| self.key_encoding_error(e))?;
+        let data_bytes: Cow<[u8]> =
+            DC::bytes_encode(data).map_err(|e| self.data_encoding_error(e))?;
         let mut key_val = unsafe { crate::into_val(&key_bytes) };
         let mut data_val = unsafe { crate::into_val(&data_bytes) };
 
@@ -2471,7 +2609,14 @@ impl<KC, DC, C> Database<KC, DC, C> {
         };
 
         match result {
-            Ok(()) => Ok(true),
+            Ok(()) => {
+                txn.record_change(self.dbi, |database| ChangeOp::Delete {
+                    database,
+                    key: key_bytes.to_vec(),
+                    data: Some(data_bytes.to_vec()),
+                })?;
+                Ok(true)
+            }
             Err(e) if e.not_found() => Ok(false),
             Err(e) => Err(e.into()),
         }
@@ -2592,10 +2737,10 @@ impl<KC, DC, C> Database<KC, DC, C> {
     pub fn clear(&self, txn: &mut RwTxn) -> Result<()> {
         assert_eq_env_db_txn!(self, txn);
 
-        unsafe {
-            mdb_result(ffi::mdb_drop(txn.txn.txn.unwrap().as_mut(), self.dbi, 0))
-                .map_err(Into::into)
-        }
+        unsafe { mdb_result(ffi::mdb_drop(txn.txn.txn.unwrap().as_mut(), self.dbi, 0))? };
+        txn.record_change(self.dbi, |database| ChangeOp::Clear { database })?;
+
+        Ok(())
     }
 
     /// Change the codec types of this database, specifying the codecs.
@@ -2640,7 +2785,7 @@ impl<KC, DC, C> Database<KC, DC, C> {
     /// # Ok(()) }
     /// ```
     pub fn remap_types<KC2, DC2>(&self) -> Database<KC2, DC2, C> {
-        Database::new(self.env_ident, self.dbi)
+        Database::new(self.env_ident, self.dbi, self.name)
     }
 
     /// Change the key codec type of this database, specifying the new codec.
@@ -2657,6 +2802,22 @@ impl<KC, DC, C> Database<KC, DC, C> {
     pub fn lazily_decode_data(&self) -> Database<KC, LazyDecode<DC>, C> {
         self.remap_types::<KC, LazyDecode<DC>>()
     }
+
+    fn key_encoding_error(&self, error: BoxedError) -> Error {
+        encoding_error::<KC>(self.name, EntrySide::Key, error)
+    }
+
+    fn data_encoding_error(&self, error: BoxedError) -> Error {
+        encoding_error::<DC>(self.name, EntrySide::Data, error)
+    }
+
+    pub(crate) fn key_decoding_error(&self, bytes: &[u8], error: BoxedError) -> Error {
+        decoding_error::<KC>(self.name, EntrySide::Key, bytes, error)
+    }
+
+    pub(crate) fn data_decoding_error(&self, bytes: &[u8], error: BoxedError) -> Error {
+        decoding_error::<DC>(self.name, EntrySide::Data, bytes, error)
+    }
 }
 
 impl<KC, DC, C> Clone for Database<KC, DC, C> {
@@ -2677,6 +2838,22 @@ impl<KC, DC, C> fmt::Debug for Database<KC, DC, C> {
     }
 }
 
+/// The names given to the [`Database`]s, kept for the whole program run to keep the handles `Copy`.
+static DATABASE_NAMES: LazyLock<Mutex<HashSet<&'static str>>> = LazyLock::new(Mutex::default);
+
+/// Returns the name with a `'static` lifetime, leaking it the first time it is seen.
+fn intern_database_name(name: &str) -> &'static str {
+    let mut names = DATABASE_NAMES.lock().unwrap();
+    match names.get(name) {
+        Some(name) => name,
+        None => {
+            let name = Box::leak(name.to_owned().into_boxed_str());
+            names.insert(name);
+            name
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use byteorder::*;
@@ -2753,4 +2930,23 @@ mod tests {
         assert_eq!(i, range.end - range.start);
         Ok(())
     }
+
+    #[test]
+    fn codec_errors_report_the_name_of_the_handle() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let env = unsafe { EnvOpenOptions::new().max_dbs(1).open(dir.path())? };
+        let mut txn = env.write_txn()?;
+        let db: Database<Str, Str> = env.create_database(&mut txn, Some("users"))?;
+        db.remap_types::<Bytes, Bytes>().put(&mut txn, b"hello", &[0xff])?;
+
+        let Err(Error::Decoding(error)) = db.get(&txn, "hello") else { panic!() };
+        assert_eq!(error.database(), Some("users"));
+        let Some(Err(Error::Decoding(error))) = db.iter(&txn)?.next() else { panic!() };
+        assert_eq!(error.database(), Some("users"));
+
+        let main: Database<Str, Unit> = env.create_database(&mut txn, None)?;
+        let Err(Error::Decoding(error)) = main.get(&txn, "users") else { panic!() };
+        assert_eq!(error.database(), None);
+        Ok(())
+    }
 }
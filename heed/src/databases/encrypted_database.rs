@@ -6,9 +6,11 @@ use types::LazyDecode;
 
 use crate::envs::DefaultComparator;
 use crate::iteration_method::MoveOnCurrentKeyDuplicates;
+use crate::iterator::pin_and_decode;
 #[allow(unused)] // for cargo auto doc links
 use crate::mdb::ffi;
 use crate::mdb::lmdb_flags::DatabaseFlags;
+use crate::types::Bytes;
 use crate::*;
 
 /// Options and flags which can be used to configure how a [`Database`] is opened.
@@ -93,6 +95,13 @@ impl<'e, 'n, T, KC, DC, C> EncryptedDatabaseOpenOptions<'e, 'n, T, KC, DC, C> {
         self
     }
 
+    /// Checks that the database is opened with the codecs, comparator and flags it was
+    /// created with, identified by `id`, see [`DatabaseOpenOptions::fingerprint`].
+    pub fn fingerprint(&mut self, id: &'n str) -> &mut Self {
+        self.inner.fingerprint(id);
+        self
+    }
+
     /// Opens a typed database that already exists in this environment.
     ///
     /// If the database was previously opened in this program run, types will be checked.
@@ -693,6 +702,175 @@ impl<KC, DC, C> EncryptedDatabase<KC, DC, C> {
         self.inner.last(txn)
     }
 
+    /// Retrieves the value associated with a key and pins it in the transaction,
+    /// it stays valid for the whole transaction. See [`Self::get`] and [`PinnedRoTxn`].
+    ///
+    /// ```
+    /// use chacha20poly1305::{ChaCha20Poly1305, Key};
+    /// use heed3::types::*;
+    /// use heed3::EnvOpenOptions;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let dir = tempfile::tempdir()?;
+    /// # let key = Key::from([1; 32]);
+    /// # let env = unsafe { EnvOpenOptions::new().open_encrypted::<ChaCha20Poly1305, _>(key, dir.path())? };
+    /// let mut wtxn = env.write_txn()?;
+    /// let db = env.create_database::<Str, Bytes>(&mut wtxn, None)?;
+    /// db.put(&mut wtxn, "first", &[1; 1024])?;
+    /// db.put(&mut wtxn, "second", &[2; 1024])?;
+    /// wtxn.commit()?;
+    ///
+    /// let rtxn = env.pinned_read_txn()?;
+    /// let first = db.get_pinned(&rtxn, "first")?.unwrap();
+    /// let second = db.get_pinned(&rtxn, "second")?.unwrap();
+    /// assert_ne!(first, second);
+    /// assert_eq!(rtxn.pinned_size(), 2048);
+    /// # Ok(()) }
+    /// ```
+    pub fn get_pinned<'a, 'p, T>(
+        &self,
+        txn: &'p PinnedRoTxn<T>,
+        key: &'a KC::EItem,
+    ) -> Result<Option<DC::DItem>>
+    where
+        KC: BytesEncode<'a>,
+        DC: BytesDecode<'p>,
+    {
+        let mut pins = txn.lock();
+        match self.inner.remap_data_type::<Bytes>().get(pins.txn(), key)? {
+            Some(data) => {
+                let data = pins.pin(data);
+                drop(pins);
+                let data =
+                    DC::bytes_decode(data).map_err(|e| self.inner.data_decoding_error(data, e))?;
+                Ok(Some(data))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieves the first key/value pair of this database and pins it in the transaction,
+    /// see [`Self::first`] and [`PinnedRoTxn`].
+    pub fn first_pinned<'p, T>(
+        &self,
+        txn: &'p PinnedRoTxn<T>,
+    ) -> Result<Option<(KC::DItem, DC::DItem)>>
+    where
+        KC: BytesDecode<'p>,
+        DC: BytesDecode<'p>,
+    {
+        let mut pins = txn.lock();
+        match self.inner.remap_types::<Bytes, Bytes>().first(pins.txn())? {
+            Some((key, data)) => pin_and_decode(&self.inner, &mut pins, key, data).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieves the last key/value pair of this database and pins it in the transaction,
+    /// see [`Self::last`] and [`PinnedRoTxn`].
+    pub fn last_pinned<'p, T>(
+        &self,
+        txn: &'p PinnedRoTxn<T>,
+    ) -> Result<Option<(KC::DItem, DC::DItem)>>
+    where
+        KC: BytesDecode<'p>,
+        DC: BytesDecode<'p>,
+    {
+        let mut pins = txn.lock();
+        match self.inner.remap_types::<Bytes, Bytes>().last(pins.txn())? {
+            Some((key, data)) => pin_and_decode(&self.inner, &mut pins, key, data).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns an iterator over all of the key/value pairs of this database that pins
+    /// them in the transaction, see [`Self::iter`] and [`PinnedRoTxn`].
+    pub fn iter_pinned<'p, T>(
+        &self,
+        txn: &'p PinnedRoTxn<T>,
+    ) -> Result<PinnedIter<'p, T, KC, DC, C>> {
+        let pins = txn.lock();
+        let iter = self.inner.remap_types::<Bytes, Bytes>().iter(pins.txn())?;
+        Ok(PinnedIter::new(iter, txn, self.inner))
+    }
+
+    /// Returns an iterator over a range of key/value pairs of this database that pins
+    /// them in the transaction, see [`Self::range`] and [`PinnedRoTxn`].
+    ///
+    /// ```
+    /// use chacha20poly1305::{ChaCha20Poly1305, Key};
+    /// use heed3::byteorder::BigEndian;
+    /// use heed3::types::*;
+    /// use heed3::EnvOpenOptions;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let dir = tempfile::tempdir()?;
+    /// # let key = Key::from([1; 32]);
+    /// # let env = unsafe { EnvOpenOptions::new().open_encrypted::<ChaCha20Poly1305, _>(key, dir.path())? };
+    /// let mut wtxn = env.write_txn()?;
+    /// let db = env.create_database::<U32<BigEndian>, Str>(&mut wtxn, None)?;
+    /// db.put(&mut wtxn, &13, "i-am-thirteen")?;
+    /// db.put(&mut wtxn, &27, "i-am-twenty-seven")?;
+    /// db.put(&mut wtxn, &42, "i-am-forty-two")?;
+    /// wtxn.commit()?;
+    ///
+    /// let rtxn = env.pinned_read_txn()?;
+    /// let entries: Vec<_> = db.range_pinned(&rtxn, &(27..))?.collect::<heed3::Result<_>>()?;
+    /// assert_eq!(entries, [(27, "i-am-twenty-seven"), (42, "i-am-forty-two")]);
+    /// # Ok(()) }
+    /// ```
+    pub fn range_pinned<'a, 'p, R, T>(
+        &self,
+        txn: &'p PinnedRoTxn<T>,
+        range: &'a R,
+    ) -> Result<PinnedRange<'p, T, KC, DC, C>>
+    where
+        KC: BytesEncode<'a>,
+        R: RangeBounds<KC::EItem>,
+    {
+        let pins = txn.lock();
+        let range = self.inner.range(pins.txn(), range)?.remap_types::<Bytes, Bytes>();
+        Ok(PinnedRange::new(range, txn, self.inner))
+    }
+
+    /// Returns an iterator over the key/value pairs of this database starting with the
+    /// prefix that pins them in the transaction, see [`Self::prefix_iter`] and [`PinnedRoTxn`].
+    ///
+    /// ```
+    /// use chacha20poly1305::{ChaCha20Poly1305, Key};
+    /// use heed3::types::*;
+    /// use heed3::EnvOpenOptions;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let dir = tempfile::tempdir()?;
+    /// # let key = Key::from([1; 32]);
+    /// # let env = unsafe { EnvOpenOptions::new().open_encrypted::<ChaCha20Poly1305, _>(key, dir.path())? };
+    /// let mut wtxn = env.write_txn()?;
+    /// let db = env.create_database::<Str, Unit>(&mut wtxn, None)?;
+    /// db.put(&mut wtxn, "bonjour", &())?;
+    /// db.put(&mut wtxn, "hello", &())?;
+    /// db.put(&mut wtxn, "help", &())?;
+    /// wtxn.commit()?;
+    ///
+    /// let rtxn = env.pinned_read_txn()?;
+    /// let entries: Vec<_> = db.prefix_iter_pinned(&rtxn, "hel")?.collect::<heed3::Result<_>>()?;
+    /// assert_eq!(entries, [("hello", ()), ("help", ())]);
+    /// # Ok(()) }
+    /// ```
+    pub fn prefix_iter_pinned<'a, 'p, T>(
+        &self,
+        txn: &'p PinnedRoTxn<T>,
+        prefix: &'a KC::EItem,
+    ) -> Result<PinnedPrefix<'p, T, KC, DC, C>>
+    where
+        KC: BytesEncode<'a>,
+        C: LexicographicComparator,
+    {
+        let pins = txn.lock();
+        let prefix = self.inner.prefix_iter(pins.txn(), prefix)?.remap_types::<Bytes, Bytes>();
+        Ok(PinnedPrefix::new(prefix, txn, self.inner))
+    }
+
     /// Returns the number of elements in this database.
     ///
     /// ```
@@ -2209,6 +2387,8 @@ impl<KC, DC, C> fmt::Debug for EncryptedDatabase<KC, DC, C> {
 
 #[cfg(test)]
 mod tests {
+    use std::ops::Bound;
+
     use heed_types::*;
 
     use super::*;
@@ -2252,4 +2432,129 @@ mod tests {
 
         Ok(())
     }
+
+    fn encrypted_env(path: &std::path::Path) -> Result<EncryptedEnv<WithTls>> {
+        let key = aead::Key::<chacha20poly1305::ChaCha20Poly1305>::from([1; 32]);
+        unsafe {
+            EnvOpenOptions::new().open_encrypted::<chacha20poly1305::ChaCha20Poly1305, _>(key, path)
+        }
+    }
+
+    #[test]
+    fn pinned_reads() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let env = encrypted_env(dir.path())?;
+        let mut wtxn = env.write_txn()?;
+        let db = env.create_database::<Str, Str>(&mut wtxn, None)?;
+        for (key, data) in [("apple", "red"), ("apricot", "orange"), ("banana", "yellow")] {
+            db.put(&mut wtxn, key, data)?;
+        }
+        wtxn.commit()?;
+
+        let rtxn = env.pinned_read_txn()?;
+        assert_eq!(rtxn.pinned_size(), 0);
+        let apple = db.get_pinned(&rtxn, "apple")?;
+        assert_eq!(rtxn.pinned_size(), "red".len());
+        assert_eq!(db.get_pinned(&rtxn, "cherry")?, None);
+        assert_eq!(db.first_pinned(&rtxn)?, Some(("apple", "red")));
+        assert_eq!(db.last_pinned(&rtxn)?, Some(("banana", "yellow")));
+
+        let range = (Bound::Excluded("apple"), Bound::Unbounded);
+        let entries: Vec<_> = db.range_pinned(&rtxn, &range)?.collect::<Result<_>>()?;
+        assert_eq!(entries, [("apricot", "orange"), ("banana", "yellow")]);
+
+        let entries: Vec<_> = db.prefix_iter_pinned(&rtxn, "ap")?.collect::<Result<_>>()?;
+        assert_eq!(entries, [("apple", "red"), ("apricot", "orange")]);
+        assert_eq!(db.prefix_iter_pinned(&rtxn, "cherry")?.count(), 0);
+
+        // Every read is copied again, nothing is freed before the end of the transaction.
+        let copied = [
+            "red", "apple", "red", "banana", "yellow", "apricot", "orange", "banana", "yellow",
+            "apple", "red", "apricot", "orange",
+        ];
+        let copied: usize = copied.iter().map(|bytes| bytes.len()).sum();
+        assert_eq!(rtxn.pinned_size(), copied);
+        assert_eq!(apple, Some("red"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn pinned_reads_report_decoding_errors() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let env = encrypted_env(dir.path())?;
+        let mut wtxn = env.write_txn()?;
+        let db = env.create_database::<Bytes, Bytes>(&mut wtxn, None)?;
+        db.put(&mut wtxn, b"invalid", &[0xff, 0xfe])?;
+        wtxn.commit()?;
+
+        let db = db.remap_types::<Str, Str>();
+        let rtxn = env.pinned_read_txn()?;
+        assert!(matches!(db.get_pinned(&rtxn, "invalid"), Err(Error::Decoding(_))));
+        assert!(matches!(db.first_pinned(&rtxn), Err(Error::Decoding(_))));
+        let mut range = db.range_pinned(&rtxn, &(Bound::Included("i"), Bound::Unbounded))?;
+        assert!(matches!(range.next(), Some(Err(Error::Decoding(_)))));
+        let mut prefix = db.prefix_iter_pinned(&rtxn, "in")?;
+        assert!(matches!(prefix.next(), Some(Err(Error::Decoding(_)))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn pinned_reads_from_several_threads() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let key = aead::Key::<chacha20poly1305::ChaCha20Poly1305>::from([1; 32]);
+        let env = unsafe {
+            EnvOpenOptions::new()
+                .read_txn_without_tls()
+                .open_encrypted::<chacha20poly1305::ChaCha20Poly1305, _>(key, dir.path())?
+        };
+        let mut wtxn = env.write_txn()?;
+        let db = env.create_database::<U32<byteorder::BigEndian>, Bytes>(&mut wtxn, None)?;
+        for i in 0..1000 {
+            db.put(&mut wtxn, &i, &[i as u8; 100])?;
+        }
+        wtxn.commit()?;
+
+        let rtxn = env.pinned_read_txn()?;
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    let values: Vec<_> = (0..1000).map(|i| db.get_pinned(&rtxn, &i)).collect();
+                    let entries = db.iter_pinned(&rtxn).unwrap();
+                    for (i, (entry, value)) in entries.zip(values).enumerate() {
+                        let (key, data) = entry.unwrap();
+                        assert_eq!(key, i as u32);
+                        assert_eq!(data, &[i as u8; 100][..]);
+                        assert_eq!(value.unwrap(), Some(data));
+                    }
+                });
+            }
+        });
+        assert_eq!(rtxn.pinned_size(), 4 * 2 * 1000 * 100 + 4 * 1000 * 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn reset_pins() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let env = encrypted_env(dir.path())?;
+        let mut wtxn = env.write_txn()?;
+        let db = env.create_database::<Str, Str>(&mut wtxn, Some("fruits"))?;
+        db.put(&mut wtxn, "apple", "red")?;
+        wtxn.commit()?;
+
+        let mut rtxn = env.pinned_read_txn()?;
+        let db = rtxn.with_txn(|txn| env.open_database::<Str, Str>(txn, Some("fruits")))?.unwrap();
+        assert_eq!(db.get_pinned(&rtxn, "apple")?, Some("red"));
+        assert_eq!(rtxn.pinned_size(), "red".len());
+
+        rtxn.reset_pins();
+        assert_eq!(rtxn.pinned_size(), 0);
+        assert_eq!(db.first_pinned(&rtxn)?, Some(("apple", "red")));
+        assert_eq!(rtxn.pinned_size(), "apple".len() + "red".len());
+
+        Ok(())
+    }
 }
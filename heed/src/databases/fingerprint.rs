@@ -0,0 +1,302 @@
+use std::{any, fmt};
+
+use types::Str;
+
+use crate::*;
+
+/// The name of the database storing the fingerprints, keyed by database name.
+const FINGERPRINTS_DATABASE_NAME: &str = "heed.fingerprints";
+
+/// The key of the fingerprint of the unnamed database,
+/// the NUL byte prevents it from being the name of a database.
+const UNNAMED_DATABASE_KEY: &str = "\0";
+
+/// The codecs, comparator and flags a database was created with,
+/// see [`DatabaseOpenOptions::fingerprint`].
+///
+/// Two fingerprints are equal when their identifiers, flags and comparators are equal.
+/// The flags are the ones the database has been created with, read from LMDB. A different
+/// comparator changes the order of the keys and makes the database unusable, its type name
+/// is compared even though it may change with the version of the crate defining it.
+/// The type names of the codecs are only kept to be reported in a [`FingerprintMismatch`].
+#[derive(Debug, Clone)]
+pub struct DatabaseFingerprint {
+    /// The identifier of the codecs and comparator, chosen by the application.
+    pub id: String,
+    /// The flags the database was created with.
+    pub flags: DatabaseFlags,
+    /// The type name of the key codec.
+    pub key_codec: String,
+    /// The type name of the data codec.
+    pub data_codec: String,
+    /// The type name of the key comparator.
+    pub comparator: String,
+}
+
+impl DatabaseFingerprint {
+    pub(crate) fn of<KC, DC, C>(id: &str, flags: DatabaseFlags) -> Result<DatabaseFingerprint> {
+        if id.contains('\n') {
+            let error = "the fingerprint identifier must not contain a newline";
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, error).into());
+        }
+
+        Ok(DatabaseFingerprint {
+            id: id.to_owned(),
+            flags,
+            key_codec: any::type_name::<KC>().to_owned(),
+            data_codec: any::type_name::<DC>().to_owned(),
+            comparator: any::type_name::<C>().to_owned(),
+        })
+    }
+
+    fn encode(&self) -> String {
+        format!(
+            "{}\n{}\n{}\n{}\n{}",
+            self.id,
+            self.flags.bits(),
+            self.key_codec,
+            self.data_codec,
+            self.comparator,
+        )
+    }
+
+    fn decode(s: &str) -> Option<DatabaseFingerprint> {
+        let mut lines = s.split('\n');
+        let fingerprint = DatabaseFingerprint {
+            id: lines.next()?.to_owned(),
+            flags: DatabaseFlags::from_bits_truncate(lines.next()?.parse().ok()?),
+            key_codec: lines.next()?.to_owned(),
+            data_codec: lines.next()?.to_owned(),
+            comparator: lines.next()?.to_owned(),
+        };
+        lines.next().is_none().then_some(fingerprint)
+    }
+}
+
+impl PartialEq for DatabaseFingerprint {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.flags == other.flags && self.comparator == other.comparator
+    }
+}
+
+impl Eq for DatabaseFingerprint {}
+
+impl fmt::Display for DatabaseFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "`{}` (Database<{}, {}, {}>) with flags {:?}",
+            self.id, self.key_codec, self.data_codec, self.comparator, self.flags
+        )
+    }
+}
+
+/// The error returned when a database is opened with a fingerprint
+/// that differs from the one it was created with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FingerprintMismatch {
+    /// The name of the database, `None` for the unnamed one.
+    pub database: Option<String>,
+    /// The fingerprint stored when the database was created.
+    pub stored: DatabaseFingerprint,
+    /// The fingerprint of the database being opened.
+    pub requested: DatabaseFingerprint,
+}
+
+impl fmt::Display for FingerprintMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.database {
+            Some(name) => write!(f, "the `{name}` database")?,
+            None => f.write_str("the unnamed database")?,
+        }
+        write!(f, " was created as {} but is opened as {}", self.stored, self.requested)
+    }
+}
+
+/// Checks the fingerprint of the database against the stored one, if any.
+pub(crate) fn check_fingerprint<T>(
+    env: &Env<T>,
+    rtxn: &RoTxn<T>,
+    name: Option<&str>,
+    requested: DatabaseFingerprint,
+) -> Result<()> {
+    match env.open_database::<Str, Str>(rtxn, Some(FINGERPRINTS_DATABASE_NAME))? {
+        Some(db) => compare_fingerprint(db, rtxn, name, requested).map(drop),
+        None => Ok(()),
+    }
+}
+
+/// Checks the fingerprint of the database against the stored one or stores it if there is none.
+pub(crate) fn check_or_store_fingerprint<T>(
+    env: &Env<T>,
+    wtxn: &mut RwTxn,
+    name: Option<&str>,
+    requested: DatabaseFingerprint,
+) -> Result<()> {
+    let db = env.create_database::<Str, Str>(wtxn, Some(FINGERPRINTS_DATABASE_NAME))?;
+    let encoded = requested.encode();
+    if !compare_fingerprint(db, wtxn, name, requested)? {
+        db.put(wtxn, name.unwrap_or(UNNAMED_DATABASE_KEY), &encoded)?;
+    }
+    Ok(())
+}
+
+/// Returns `false` if no fingerprint is stored for this database.
+fn compare_fingerprint<T>(
+    db: Database<Str, Str>,
+    txn: &RoTxn<T>,
+    name: Option<&str>,
+    requested: DatabaseFingerprint,
+) -> Result<bool> {
+    let Some(stored) = db.get(txn, name.unwrap_or(UNNAMED_DATABASE_KEY))? else {
+        return Ok(false);
+    };
+
+    let stored = DatabaseFingerprint::decode(stored).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "invalid database fingerprint")
+    })?;
+    if stored == requested {
+        Ok(true)
+    } else {
+        let database = name.map(ToOwned::to_owned);
+        Err(Error::FingerprintMismatch(Box::new(FingerprintMismatch {
+            database,
+            stored,
+            requested,
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::{BigEndian, LittleEndian};
+    use types::{Bytes, U32};
+
+    use super::*;
+
+    #[test]
+    fn fingerprint_roundtrip() {
+        let fingerprint =
+            DatabaseFingerprint::of::<Str, Bytes, DefaultComparator>("id", DatabaseFlags::DUP_SORT)
+                .unwrap();
+        let decoded = DatabaseFingerprint::decode(&fingerprint.encode()).unwrap();
+        assert_eq!(decoded, fingerprint);
+        assert_eq!(decoded.key_codec, fingerprint.key_codec);
+        assert_eq!(DatabaseFingerprint::decode("a\n0\nb\nc"), None);
+        assert!(DatabaseFingerprint::of::<Str, Bytes, DefaultComparator>(
+            "two\nlines",
+            DatabaseFlags::empty()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn the_codec_names_are_not_compared() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().max_dbs(3).open(dir.path()).unwrap() };
+
+        let mut wtxn = env.write_txn().unwrap();
+        env.database_options()
+            .types::<Str, U32<BigEndian>>()
+            .name("scores")
+            .fingerprint("scores")
+            .create(&mut wtxn)
+            .unwrap();
+        wtxn.commit().unwrap();
+
+        // Other type names with the same identifier are accepted.
+        let rtxn = env.read_txn().unwrap();
+        let db = env
+            .database_options()
+            .types::<Str, U32<LittleEndian>>()
+            .name("scores")
+            .fingerprint("scores")
+            .open(&rtxn)
+            .unwrap();
+        assert!(db.is_some());
+
+        let result = env
+            .database_options()
+            .types::<Str, U32<BigEndian>>()
+            .name("scores")
+            .fingerprint("scores-v2")
+            .open(&rtxn);
+        let Err(Error::FingerprintMismatch(mismatch)) = result else {
+            panic!("expected a fingerprint mismatch");
+        };
+        assert_eq!(mismatch.database.as_deref(), Some("scores"));
+        assert_eq!(
+            (mismatch.stored.id.as_str(), mismatch.requested.id.as_str()),
+            ("scores", "scores-v2")
+        );
+        assert!(mismatch.to_string().contains("`scores-v2` (Database<"));
+        drop(rtxn);
+
+        let requested = DatabaseFingerprint::of::<Str, Str, DefaultComparator>(
+            "scores",
+            DatabaseFlags::DUP_SORT,
+        )
+        .unwrap();
+        assert_ne!(mismatch.stored, requested);
+    }
+
+    #[test]
+    fn the_flags_are_read_from_the_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().max_dbs(3).open(dir.path()).unwrap() };
+
+        let mut wtxn = env.write_txn().unwrap();
+        env.database_options()
+            .types::<Str, Str>()
+            .name("tags")
+            .flags(DatabaseFlags::DUP_SORT)
+            .fingerprint("tags")
+            .create(&mut wtxn)
+            .unwrap();
+        wtxn.commit().unwrap();
+
+        // The flags of an existing database don't need to be given again.
+        let rtxn = env.read_txn().unwrap();
+        let db = env
+            .database_options()
+            .types::<Str, Str>()
+            .name("tags")
+            .fingerprint("tags")
+            .open(&rtxn)
+            .unwrap();
+        assert!(db.is_some());
+    }
+
+    #[test]
+    fn the_comparators_are_compared() {
+        enum Reverse {}
+
+        impl Comparator for Reverse {
+            fn compare(a: &[u8], b: &[u8]) -> std::cmp::Ordering {
+                b.cmp(a)
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().max_dbs(3).open(dir.path()).unwrap() };
+
+        let mut wtxn = env.write_txn().unwrap();
+        env.database_options()
+            .types::<Str, Str>()
+            .key_comparator::<Reverse>()
+            .name("ids")
+            .fingerprint("ids")
+            .create(&mut wtxn)
+            .unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = env.read_txn().unwrap();
+        let result =
+            env.database_options().types::<Str, Str>().name("ids").fingerprint("ids").open(&rtxn);
+        let Err(Error::FingerprintMismatch(mismatch)) = result else {
+            panic!("expected a fingerprint mismatch");
+        };
+        assert!(mismatch.stored.comparator.ends_with("Reverse"));
+        assert!(mismatch.requested.comparator.ends_with("DefaultComparator"));
+    }
+}
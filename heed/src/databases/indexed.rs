@@ -0,0 +1,488 @@
+use std::borrow::Cow;
+use std::ops::RangeBounds;
+use std::{fmt, io};
+
+use types::Bytes;
+
+use crate::codec_error::{decoding_error, encoding_error};
+use crate::iteration_method::MoveOnCurrentKeyDuplicates;
+use crate::*;
+
+/// The prefix of the names of the index databases, the names of the databases
+/// starting with `heed.` are reserved to heed.
+const INDEX_DATABASE_PREFIX: &str = "heed.index.";
+
+/// A key of a secondary index, as returned by the extractors of an [`IndexedDatabase`].
+pub type IndexKey = Vec<u8>;
+
+type ExtractorFn = dyn Fn(&[u8]) -> Result<Vec<IndexKey>> + Send + Sync;
+
+struct SecondaryIndex {
+    name: String,
+    database: Database<Bytes, Bytes>,
+    extract: Box<ExtractorFn>,
+}
+
+/// A database with secondary indexes that are updated along with its entries.
+///
+/// Every secondary index is stored in its own [`DatabaseFlags::DUP_SORT`] database, named
+/// `"heed.index.{primary}.{index}"` after the primary database and the index, that maps the keys
+/// returned by the extractor of the index to the keys of the primary entries. The names of the
+/// indexes can't contain a dot, so that two indexes never share a database. The [`IndexedDatabase::put`]
+/// and [`IndexedDatabase::delete`] methods update the primary database and the indexes in the
+/// same write transaction, the indexes are never out of sync as long as the primary database is
+/// only written through them.
+///
+/// The indexes are not persisted with their extractors: they must be registered again, in any
+/// order, with [`IndexedDatabase::add_index`] every time the environment is opened. When the
+/// extractor of an index changes, the index must be rebuilt with [`IndexedDatabase::rebuild_index`].
+///
+/// ```
+/// use heed::types::*;
+/// use heed::{EnvOpenOptions, IndexedDatabase};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let dir = tempfile::tempdir()?;
+/// let env = unsafe { EnvOpenOptions::new().max_dbs(10).open(dir.path())? };
+///
+/// let mut wtxn = env.write_txn()?;
+/// let mut emails = IndexedDatabase::<Str, Str>::create(&env, &mut wtxn, "emails")?;
+/// emails.add_index(&env, &mut wtxn, "domain", |email: &&str| {
+///     email.split_once('@').map(|(_, domain)| domain.as_bytes().to_vec()).into_iter().collect()
+/// })?;
+///
+/// emails.put(&mut wtxn, "ferris", "ferris@rust-lang.org")?;
+/// emails.put(&mut wtxn, "kerollmops", "kero@example.com")?;
+/// emails.put(&mut wtxn, "corro", "corro@rust-lang.org")?;
+///
+/// let rust = emails.get_by_index(&wtxn, "domain", b"rust-lang.org")?;
+/// let rust = rust.collect::<Result<Vec<_>, _>>()?;
+/// assert_eq!(rust, vec![("corro", "corro@rust-lang.org"), ("ferris", "ferris@rust-lang.org")]);
+///
+/// // The old index keys are removed when an entry is replaced or deleted.
+/// emails.put(&mut wtxn, "ferris", "ferris@example.com")?;
+/// emails.delete(&mut wtxn, "kerollmops")?;
+/// let example = emails.get_by_index(&wtxn, "domain", b"example.com")?;
+/// let example = example.collect::<Result<Vec<_>, _>>()?;
+/// assert_eq!(example, vec![("ferris", "ferris@example.com")]);
+///
+/// wtxn.commit()?;
+/// # Ok(()) }
+/// ```
+pub struct IndexedDatabase<KC, DC> {
+    name: String,
+    primary: Database<KC, DC>,
+    indexes: Vec<SecondaryIndex>,
+}
+
+impl<KC, DC> IndexedDatabase<KC, DC> {
+    /// Creates the primary database, if it doesn't already exist, without any index.
+    pub fn create<T>(env: &Env<T>, wtxn: &mut RwTxn, name: &str) -> Result<IndexedDatabase<KC, DC>>
+    where
+        KC: 'static,
+        DC: 'static,
+    {
+        let primary = env.create_database(wtxn, Some(name))?;
+        Ok(IndexedDatabase { name: name.to_owned(), primary, indexes: Vec::new() })
+    }
+
+    /// Registers a secondary index, creating its database if it doesn't already exist.
+    ///
+    /// The extractor returns the index keys of the data of an entry, possibly none.
+    /// When the database of the index is empty but the primary database isn't, for example
+    /// when an index is added to an existing database, the index is built from the entries.
+    ///
+    /// Returns an error if an index with the same name is already registered
+    /// or if the name contains a dot.
+    pub fn add_index<T, F>(
+        &mut self,
+        env: &Env<T>,
+        wtxn: &mut RwTxn,
+        index: &str,
+        extractor: F,
+    ) -> Result<()>
+    where
+        DC: for<'a> BytesDecode<'a> + 'static,
+        F: for<'a> Fn(&<DC as BytesDecode<'a>>::DItem) -> Vec<IndexKey> + Send + Sync + 'static,
+    {
+        if index.contains('.') {
+            let msg = format!("the name of the `{index}` index contains a dot");
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, msg).into());
+        }
+        if self.indexes.iter().any(|i| i.name == index) {
+            let msg = format!("the `{index}` index is already registered");
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, msg).into());
+        }
+
+        let database = env
+            .database_options()
+            .types::<Bytes, Bytes>()
+            .name(&format!("{INDEX_DATABASE_PREFIX}{}.{index}", self.name))
+            .flags(DatabaseFlags::DUP_SORT)
+            .create(wtxn)?;
+
+        let primary_name = self.name.clone();
+        let extract = move |bytes: &[u8]| match DC::bytes_decode(bytes) {
+            Ok(item) => Ok(extractor(&item)),
+            Err(e) => Err(decoding_error::<DC>(Some(&primary_name), EntrySide::Data, bytes, e)),
+        };
+        let index = SecondaryIndex { name: index.to_owned(), database, extract: Box::new(extract) };
+
+        if index.database.is_empty(wtxn)? {
+            self.build_index(wtxn, &index)?;
+        }
+
+        self.indexes.push(index);
+        Ok(())
+    }
+
+    /// Clears a registered index and builds it again from the entries of the primary database.
+    ///
+    /// An index must be rebuilt when its extractor changes, as the existing index keys are
+    /// only built from the entries when the index database is empty, or when the primary
+    /// database has been written without going through this type.
+    ///
+    /// Returns an error if the index isn't registered.
+    pub fn rebuild_index(&self, wtxn: &mut RwTxn, index: &str) -> Result<()> {
+        let index = self.index(index)?;
+        index.database.clear(wtxn)?;
+        self.build_index(wtxn, index)
+    }
+
+    /// Returns the primary database, to read its entries.
+    ///
+    /// Writing to it directly doesn't update the indexes, use the methods of this type instead.
+    pub fn primary(&self) -> Database<KC, DC> {
+        self.primary
+    }
+
+    /// Retrieves the value associated with a key in the primary database.
+    pub fn get<'a, 'txn, T>(
+        &self,
+        txn: &'txn RoTxn<T>,
+        key: &'a KC::EItem,
+    ) -> Result<Option<DC::DItem>>
+    where
+        KC: BytesEncode<'a>,
+        DC: BytesDecode<'txn>,
+    {
+        self.primary.get(txn, key)
+    }
+
+    /// Inserts a key-value pair in the primary database, replacing the previous one,
+    /// and updates the indexes accordingly.
+    pub fn put<'a>(&self, wtxn: &mut RwTxn, key: &'a KC::EItem, data: &'a DC::EItem) -> Result<()>
+    where
+        KC: BytesEncode<'a>,
+        DC: BytesEncode<'a>,
+    {
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key)
+            .map_err(|e| encoding_error::<KC>(Some(&self.name), EntrySide::Key, e))?;
+        let data_bytes: Cow<[u8]> = DC::bytes_encode(data)
+            .map_err(|e| encoding_error::<DC>(Some(&self.name), EntrySide::Data, e))?;
+
+        self.remove_from_indexes(wtxn, &key_bytes)?;
+        self.raw_primary().put(wtxn, &key_bytes, &data_bytes)?;
+        for index in &self.indexes {
+            for index_key in (index.extract)(&data_bytes)? {
+                index.database.put(wtxn, &index_key, &key_bytes)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Deletes an entry from the primary database and its keys from the indexes.
+    ///
+    /// Returns `true` if the entry was found.
+    pub fn delete<'a>(&self, wtxn: &mut RwTxn, key: &'a KC::EItem) -> Result<bool>
+    where
+        KC: BytesEncode<'a>,
+    {
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key)
+            .map_err(|e| encoding_error::<KC>(Some(&self.name), EntrySide::Key, e))?;
+
+        if self.remove_from_indexes(wtxn, &key_bytes)? {
+            self.raw_primary().delete(wtxn, &key_bytes)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Deletes every entry of the primary database and of the registered indexes.
+    pub fn clear(&self, wtxn: &mut RwTxn) -> Result<()> {
+        self.primary.clear(wtxn)?;
+        self.indexes.iter().try_for_each(|index| index.database.clear(wtxn))
+    }
+
+    /// Returns an iterator over the entries of the primary database indexed under this
+    /// index key, ordered by primary key.
+    ///
+    /// Returns an error if the index isn't registered.
+    pub fn get_by_index<'a, 'txn, T>(
+        &'a self,
+        txn: &'txn RoTxn<T>,
+        index: &str,
+        index_key: &[u8],
+    ) -> Result<IndexedEntries<'a, 'txn, T, KC, DC>> {
+        let index = self.index(index)?;
+        let keys = match index.database.get_duplicates(txn, index_key)? {
+            Some(keys) => IndexKeys::Duplicates(keys),
+            None => IndexKeys::Empty,
+        };
+        Ok(IndexedEntries { database: self, index, txn, keys })
+    }
+
+    /// Returns an iterator over the entries of the primary database indexed under the
+    /// index keys in this range, ordered by index key and then by primary key.
+    ///
+    /// An entry is returned once for every one of its index keys in the range.
+    /// Returns an error if the index isn't registered.
+    pub fn range_by_index<'a, 'txn, T, R>(
+        &'a self,
+        txn: &'txn RoTxn<T>,
+        index: &str,
+        range: &R,
+    ) -> Result<IndexedEntries<'a, 'txn, T, KC, DC>>
+    where
+        R: RangeBounds<[u8]>,
+    {
+        let index = self.index(index)?;
+        let keys = IndexKeys::Range(index.database.range(txn, range)?);
+        Ok(IndexedEntries { database: self, index, txn, keys })
+    }
+
+    fn raw_primary(&self) -> Database<Bytes, Bytes> {
+        self.primary.remap_types()
+    }
+
+    fn build_index(&self, wtxn: &mut RwTxn, index: &SecondaryIndex) -> Result<()> {
+        let mut entries = Vec::new();
+        for result in self.raw_primary().iter(wtxn)? {
+            let (key, data) = result?;
+            entries.push((key.to_vec(), (index.extract)(data)?));
+        }
+        for (key, index_keys) in entries {
+            for index_key in index_keys {
+                index.database.put(wtxn, &index_key, &key)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn index(&self, name: &str) -> Result<&SecondaryIndex> {
+        self.indexes.iter().find(|i| i.name == name).ok_or_else(|| {
+            let msg = format!("the `{name}` index is not registered");
+            io::Error::new(io::ErrorKind::InvalidInput, msg).into()
+        })
+    }
+
+    /// Removes the index keys of the entry, if any, and returns `true` if it was found.
+    fn remove_from_indexes(&self, wtxn: &mut RwTxn, key: &[u8]) -> Result<bool> {
+        let Some(data) = self.raw_primary().get(wtxn, key)? else {
+            return Ok(false);
+        };
+
+        let data = data.to_vec();
+        for index in &self.indexes {
+            for index_key in (index.extract)(&data)? {
+                index.database.delete_one_duplicate(wtxn, &index_key, key)?;
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn primary_entry<'txn, T>(
+        &self,
+        txn: &'txn RoTxn<T>,
+        index: &SecondaryIndex,
+        key: &'txn [u8],
+    ) -> Result<(KC::DItem, DC::DItem)>
+    where
+        KC: BytesDecode<'txn>,
+        DC: BytesDecode<'txn>,
+    {
+        let Some(data) = self.raw_primary().get(txn, key)? else {
+            let msg =
+                format!("the `{}` index refers to a missing entry of `{}`", index.name, self.name);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, msg).into());
+        };
+
+        let name = || Some(self.name.as_str());
+        let key_item = KC::bytes_decode(key)
+            .map_err(|e| decoding_error::<KC>(name(), EntrySide::Key, key, e))?;
+        let data_item = DC::bytes_decode(data)
+            .map_err(|e| decoding_error::<DC>(name(), EntrySide::Data, data, e))?;
+        Ok((key_item, data_item))
+    }
+}
+
+impl<KC, DC> fmt::Debug for IndexedDatabase<KC, DC> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let indexes: Vec<_> = self.indexes.iter().map(|i| &i.name).collect();
+        f.debug_struct("IndexedDatabase")
+            .field("name", &self.name)
+            .field("primary", &self.primary)
+            .field("indexes", &indexes)
+            .finish()
+    }
+}
+
+enum IndexKeys<'txn, T> {
+    Duplicates(RoIter<'txn, T, Bytes, Bytes, MoveOnCurrentKeyDuplicates>),
+    Range(RoRange<'txn, T, Bytes, Bytes>),
+    Empty,
+}
+
+/// An iterator over the entries of an [`IndexedDatabase`] found through one of its indexes,
+/// returned by [`IndexedDatabase::get_by_index`] and [`IndexedDatabase::range_by_index`].
+pub struct IndexedEntries<'a, 'txn, T, KC, DC> {
+    database: &'a IndexedDatabase<KC, DC>,
+    index: &'a SecondaryIndex,
+    txn: &'txn RoTxn<'txn, T>,
+    keys: IndexKeys<'txn, T>,
+}
+
+impl<'txn, T, KC, DC> Iterator for IndexedEntries<'_, 'txn, T, KC, DC>
+where
+    KC: BytesDecode<'txn>,
+    DC: BytesDecode<'txn>,
+{
+    type Item = Result<(KC::DItem, DC::DItem)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = match &mut self.keys {
+            IndexKeys::Duplicates(keys) => keys.next()?,
+            IndexKeys::Range(keys) => keys.next()?,
+            IndexKeys::Empty => return None,
+        };
+        Some(result.and_then(|(_, key)| self.database.primary_entry(self.txn, self.index, key)))
+    }
+}
+
+impl<KC, DC, T> fmt::Debug for IndexedEntries<'_, '_, T, KC, DC> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IndexedEntries")
+            .field("database", &self.database.name)
+            .field("index", &self.index.name)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use types::Str;
+
+    use super::*;
+
+    fn domain(email: &&str) -> Vec<IndexKey> {
+        email.split_once('@').map(|(_, domain)| domain.as_bytes().to_vec()).into_iter().collect()
+    }
+
+    fn keys<'txn, T>(entries: IndexedEntries<'_, 'txn, T, Str, Str>) -> Vec<&'txn str> {
+        entries.map(|r| r.unwrap().0).collect()
+    }
+
+    fn setup(env: &Env<WithTls>, wtxn: &mut RwTxn) -> IndexedDatabase<Str, Str> {
+        let mut emails = IndexedDatabase::<Str, Str>::create(env, wtxn, "emails").unwrap();
+        emails.add_index(env, wtxn, "domain", domain).unwrap();
+        emails.put(wtxn, "ferris", "ferris@rust-lang.org").unwrap();
+        emails.put(wtxn, "kero", "kero@example.com").unwrap();
+        emails.put(wtxn, "corro", "corro@rust-lang.org").unwrap();
+        emails
+    }
+
+    #[test]
+    fn iterates_over_the_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().max_dbs(10).open(dir.path()).unwrap() };
+        let mut wtxn = env.write_txn().unwrap();
+        let emails = setup(&env, &mut wtxn);
+
+        let rust = emails.get_by_index(&wtxn, "domain", b"rust-lang.org").unwrap();
+        assert_eq!(keys(rust), ["corro", "ferris"]);
+        let missing = emails.get_by_index(&wtxn, "domain", b"missing.org").unwrap();
+        assert_eq!(keys(missing), Vec::<&str>::new());
+        let range = (Bound::Included(&b"e"[..]), Bound::Excluded(&b"s"[..]));
+        let range = emails.range_by_index(&wtxn, "domain", &range).unwrap();
+        assert_eq!(keys(range), ["kero", "corro", "ferris"]);
+    }
+
+    #[test]
+    fn invalid_indexes_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().max_dbs(10).open(dir.path()).unwrap() };
+        let mut wtxn = env.write_txn().unwrap();
+        let mut emails = setup(&env, &mut wtxn);
+
+        let invalid_input = |result: Result<()>| match result {
+            Err(Error::Io(e)) => e.kind() == io::ErrorKind::InvalidInput,
+            _ => false,
+        };
+        assert!(invalid_input(emails.add_index(&env, &mut wtxn, "domain", domain)));
+        assert!(invalid_input(emails.add_index(&env, &mut wtxn, "by.domain", domain)));
+        assert!(invalid_input(emails.rebuild_index(&mut wtxn, "missing")));
+        assert!(emails.get_by_index(&wtxn, "missing", b"example.com").is_err());
+        assert!(emails.range_by_index(&wtxn, "missing", &(..)).is_err());
+    }
+
+    #[test]
+    fn index_databases_do_not_collide_with_user_databases() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().max_dbs(10).open(dir.path()).unwrap() };
+        let mut wtxn = env.write_txn().unwrap();
+        let emails = setup(&env, &mut wtxn);
+
+        let user = env.create_database::<Str, Str>(&mut wtxn, Some("emails.domain")).unwrap();
+        assert!(user.is_empty(&wtxn).unwrap());
+        user.put(&mut wtxn, "example.com", "not an index key").unwrap();
+        let example = emails.get_by_index(&wtxn, "domain", b"example.com").unwrap();
+        assert_eq!(keys(example), ["kero"]);
+    }
+
+    #[test]
+    fn rebuild_index_uses_the_new_extractor() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().max_dbs(10).open(dir.path()).unwrap() };
+        let mut wtxn = env.write_txn().unwrap();
+        setup(&env, &mut wtxn);
+
+        // The index isn't empty, registering another extractor keeps the old index keys.
+        let mut emails = IndexedDatabase::<Str, Str>::create(&env, &mut wtxn, "emails").unwrap();
+        emails
+            .add_index(&env, &mut wtxn, "domain", |email: &&str| vec![email.to_uppercase().into()])
+            .unwrap();
+        let upper = emails.get_by_index(&wtxn, "domain", b"KERO@EXAMPLE.COM").unwrap();
+        assert_eq!(keys(upper), Vec::<&str>::new());
+
+        emails.rebuild_index(&mut wtxn, "domain").unwrap();
+        let upper = emails.get_by_index(&wtxn, "domain", b"KERO@EXAMPLE.COM").unwrap();
+        assert_eq!(keys(upper), ["kero"]);
+        let old = emails.get_by_index(&wtxn, "domain", b"example.com").unwrap();
+        assert_eq!(keys(old), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn missing_primary_entries_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().max_dbs(10).open(dir.path()).unwrap() };
+        let mut wtxn = env.write_txn().unwrap();
+        let emails = setup(&env, &mut wtxn);
+
+        // Writing directly to the primary database leaves the index out of sync.
+        emails.primary().delete(&mut wtxn, "kero").unwrap();
+        let mut example = emails.get_by_index(&wtxn, "domain", b"example.com").unwrap();
+        match example.next() {
+            Some(Err(Error::Io(e))) => assert_eq!(e.kind(), io::ErrorKind::InvalidData),
+            otherwise => panic!("expected an invalid data error, got {otherwise:?}"),
+        }
+        drop(example);
+
+        emails.rebuild_index(&mut wtxn, "domain").unwrap();
+        let example = emails.get_by_index(&wtxn, "domain", b"example.com").unwrap();
+        assert_eq!(keys(example), Vec::<&str>::new());
+    }
+}
@@ -0,0 +1,292 @@
+use std::borrow::Borrow;
+use std::io;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use types::*;
+
+use crate::codec_error::{decoding_error, encoding_error};
+use crate::*;
+
+/// A line of the JSON Lines format.
+#[derive(Serialize, Deserialize)]
+struct JsonlEntry {
+    key: Value,
+    value: Value,
+}
+
+impl<KC, DC, C> Database<KC, DC, C> {
+    /// Writes every entry of this database in the [JSON Lines] format, one
+    /// `{"key": ..., "value": ...}` object per line, and returns the number of entries.
+    ///
+    /// The keys and values are decoded with the `KC` and `DC` codecs and serialized with serde.
+    /// The raw bytes of the [`Bytes`](crate::types::Bytes) codec and of the [`Unspecified`]
+    /// types are written as base64 strings instead.
+    ///
+    /// [JSON Lines]: https://jsonlines.org
+    ///
+    /// ```
+    /// # use heed::EnvOpenOptions;
+    /// use heed::Database;
+    /// use heed::types::*;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let dir = tempfile::tempdir()?;
+    /// # let env = unsafe { EnvOpenOptions::new()
+    /// #     .map_size(10 * 1024 * 1024) // 10MB
+    /// #     .max_dbs(3000)
+    /// #     .open(dir.path())?
+    /// # };
+    /// let mut wtxn = env.write_txn()?;
+    /// let db: Database<Str, Bytes> = env.create_database(&mut wtxn, Some("jsonl"))?;
+    /// db.put(&mut wtxn, "hello", b"world")?;
+    ///
+    /// let mut output = Vec::new();
+    /// assert_eq!(db.export_jsonl(&wtxn, &mut output)?, 1);
+    /// assert_eq!(output, b"{\"key\":\"hello\",\"value\":\"d29ybGQ=\"}\n");
+    ///
+    /// db.clear(&mut wtxn)?;
+    /// assert_eq!(db.import_jsonl(&mut wtxn, &output[..])?, 1);
+    /// assert_eq!(db.get(&wtxn, "hello")?, Some(&b"world"[..]));
+    /// wtxn.commit()?;
+    /// # Ok(()) }
+    /// ```
+    pub fn export_jsonl<'txn, T, W>(&self, txn: &'txn RoTxn<T>, mut writer: W) -> Result<usize>
+    where
+        KC: JsonlDecode<'txn>,
+        DC: JsonlDecode<'txn>,
+        W: io::Write,
+    {
+        let database = self.name;
+
+        let mut count = 0;
+        for result in self.remap_types::<Bytes, Bytes>().iter(txn)? {
+            let (key, value) = result?;
+            let entry = JsonlEntry {
+                key: KC::decode_to_json(database, EntrySide::Key, key)?,
+                value: DC::decode_to_json(database, EntrySide::Data, value)?,
+            };
+            serde_json::to_writer(&mut writer, &entry).map_err(io::Error::from)?;
+            writer.write_all(b"\n")?;
+            count += 1;
+        }
+
+        writer.flush()?;
+        Ok(count)
+    }
+
+    /// Reads the entries written by [`Database::export_jsonl`] and puts them in
+    /// this database, replacing existing ones, and returns the number of entries.
+    ///
+    /// The keys and values are deserialized into the owned version of the items
+    /// of the `KC` and `DC` codecs before being encoded with them. The raw bytes of
+    /// the [`Bytes`](crate::types::Bytes) codec and of the [`Unspecified`] types
+    /// are read from base64 strings.
+    pub fn import_jsonl<KI, DI, R>(&self, txn: &mut RwTxn, reader: R) -> Result<usize>
+    where
+        KC: JsonlEncode<KI>,
+        DC: JsonlEncode<DI>,
+        KI: ?Sized,
+        DI: ?Sized,
+        R: io::BufRead,
+    {
+        let database = self.name;
+
+        let mut count = 0;
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry: JsonlEntry = serde_json::from_str(&line).map_err(io::Error::from)?;
+            let key = KC::encode_from_json(database, EntrySide::Key, entry.key)?;
+            let value = DC::encode_from_json(database, EntrySide::Data, entry.value)?;
+            self.remap_types::<Bytes, Bytes>().put(txn, &key, &value)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+}
+
+/// A codec whose items can be exported by [`Database::export_jsonl`].
+///
+/// It is implemented for the [`JsonlSerde`] codecs, whose decoded items are serialized
+/// with serde, and for the [`Bytes`](crate::types::Bytes) codec and the [`Unspecified`]
+/// type, written as raw bytes.
+pub trait JsonlDecode<'a> {
+    /// Decodes the raw bytes with the codec and converts them into a JSON value.
+    fn decode_to_json(database: Option<&str>, side: EntrySide, bytes: &'a [u8]) -> Result<Value>;
+}
+
+impl<'a, Codec> JsonlDecode<'a> for Codec
+where
+    Codec: BytesDecode<'a> + JsonlSerde,
+    Codec::DItem: Serialize,
+{
+    fn decode_to_json(database: Option<&str>, side: EntrySide, bytes: &'a [u8]) -> Result<Value> {
+        let item = Codec::bytes_decode(bytes)
+            .map_err(|e| decoding_error::<Codec>(database, side, bytes, e))?;
+        serde_json::to_value(item).map_err(|e| io::Error::from(e).into())
+    }
+}
+
+impl<'a> JsonlDecode<'a> for Bytes {
+    fn decode_to_json(_database: Option<&str>, _side: EntrySide, bytes: &'a [u8]) -> Result<Value> {
+        Ok(bytes_to_json(bytes))
+    }
+}
+
+impl<'a> JsonlDecode<'a> for Unspecified {
+    fn decode_to_json(_database: Option<&str>, _side: EntrySide, bytes: &'a [u8]) -> Result<Value> {
+        Ok(bytes_to_json(bytes))
+    }
+}
+
+/// A codec whose items can be imported by [`Database::import_jsonl`].
+///
+/// It is implemented for the [`JsonlSerde`] codecs, whose items are deserialized into
+/// their owned version with serde, and for the [`Bytes`](crate::types::Bytes) codec
+/// and the [`Unspecified`] type, read as raw bytes.
+pub trait JsonlEncode<I: ?Sized> {
+    /// Converts the JSON value back into the item of the codec and encodes it into raw bytes.
+    fn encode_from_json(database: Option<&str>, side: EntrySide, value: Value) -> Result<Vec<u8>>;
+}
+
+impl<Codec, I> JsonlEncode<I> for Codec
+where
+    Codec: for<'a> BytesEncode<'a, EItem = I> + JsonlSerde,
+    I: ToOwned + ?Sized,
+    I::Owned: DeserializeOwned,
+{
+    fn encode_from_json(database: Option<&str>, side: EntrySide, value: Value) -> Result<Vec<u8>> {
+        let item: I::Owned = serde_json::from_value(value).map_err(io::Error::from)?;
+        let bytes = Codec::bytes_encode(item.borrow())
+            .map_err(|e| encoding_error::<Codec>(database, side, e))?;
+        Ok(bytes.into_owned())
+    }
+}
+
+impl JsonlEncode<[u8]> for Bytes {
+    fn encode_from_json(
+        _database: Option<&str>,
+        _side: EntrySide,
+        value: Value,
+    ) -> Result<Vec<u8>> {
+        bytes_from_json(value)
+    }
+}
+
+impl JsonlEncode<[u8]> for Unspecified {
+    fn encode_from_json(
+        _database: Option<&str>,
+        _side: EntrySide,
+        value: Value,
+    ) -> Result<Vec<u8>> {
+        bytes_from_json(value)
+    }
+}
+
+/// A marker for the codecs whose items are exported and imported with serde by
+/// [`Database::export_jsonl`] and [`Database::import_jsonl`].
+///
+/// Implement it for your own codecs whose items implement [`Serialize`] and
+/// whose owned items implement [`Deserialize`].
+pub trait JsonlSerde {}
+
+impl JsonlSerde for Str {}
+impl JsonlSerde for Unit {}
+impl JsonlSerde for DecodeIgnore {}
+impl JsonlSerde for U8 {}
+impl JsonlSerde for I8 {}
+impl<O> JsonlSerde for U16<O> {}
+impl<O> JsonlSerde for U32<O> {}
+impl<O> JsonlSerde for U64<O> {}
+impl<O> JsonlSerde for U128<O> {}
+impl<O> JsonlSerde for I16<O> {}
+impl<O> JsonlSerde for I32<O> {}
+impl<O> JsonlSerde for I64<O> {}
+impl<O> JsonlSerde for I128<O> {}
+impl<T> JsonlSerde for SerdeJson<T> {}
+#[cfg(feature = "serde-bincode")]
+impl<T> JsonlSerde for SerdeBincode<T> {}
+#[cfg(feature = "serde-rmp")]
+impl<T> JsonlSerde for SerdeRmp<T> {}
+
+/// Raw bytes are written as a base64 string.
+fn bytes_to_json(bytes: &[u8]) -> Value {
+    Value::String(BASE64.encode(bytes))
+}
+
+/// Raw bytes are read from a base64 string.
+fn bytes_from_json(value: Value) -> Result<Vec<u8>> {
+    match value {
+        Value::String(s) => BASE64.decode(s).map_err(|e| invalid_jsonl(e.to_string())),
+        _ => Err(invalid_jsonl("expected a base64 string for raw bytes".to_owned())),
+    }
+}
+
+fn invalid_jsonl(msg: String) -> Error {
+    Error::Io(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid JSON Lines input: {msg}"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use types::{Str, U32};
+
+    use crate::*;
+
+    #[test]
+    fn typed_and_raw_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().max_dbs(3).open(dir.path()).unwrap() };
+        let mut wtxn = env.write_txn().unwrap();
+
+        let typed: Database<Str, U32<byteorder::BigEndian>> =
+            env.create_database(&mut wtxn, Some("typed")).unwrap();
+        typed.put(&mut wtxn, "hello", &42).unwrap();
+        typed.put(&mut wtxn, "world", &43).unwrap();
+
+        let mut output = Vec::new();
+        assert_eq!(typed.export_jsonl(&wtxn, &mut output).unwrap(), 2);
+        assert_eq!(output, b"{\"key\":\"hello\",\"value\":42}\n{\"key\":\"world\",\"value\":43}\n");
+        typed.clear(&mut wtxn).unwrap();
+        assert_eq!(typed.import_jsonl(&mut wtxn, &output[..]).unwrap(), 2);
+        assert_eq!(typed.get(&wtxn, "world").unwrap(), Some(43));
+
+        // The raw bytes of the unspecified types are written as base64.
+        let raw = env.database_options().name("raw").create(&mut wtxn).unwrap();
+        raw.remap_types::<Str, Str>().put(&mut wtxn, "key", "value").unwrap();
+        let mut output = Vec::new();
+        assert_eq!(raw.export_jsonl(&wtxn, &mut output).unwrap(), 1);
+        assert_eq!(output, b"{\"key\":\"a2V5\",\"value\":\"dmFsdWU=\"}\n");
+        raw.clear(&mut wtxn).unwrap();
+        assert_eq!(raw.import_jsonl(&mut wtxn, &output[..]).unwrap(), 1);
+        assert_eq!(raw.remap_types::<Str, Str>().get(&wtxn, "key").unwrap(), Some("value"));
+    }
+
+    #[test]
+    fn invalid_lines_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().max_dbs(3).open(dir.path()).unwrap() };
+        let mut wtxn = env.write_txn().unwrap();
+        let raw = env.database_options().name("raw").create(&mut wtxn).unwrap();
+
+        let not_base64 = &b"{\"key\":\"not base64!\",\"value\":\"\"}\n"[..];
+        assert!(matches!(raw.import_jsonl(&mut wtxn, not_base64), Err(Error::Io(_))));
+        let not_a_string = &b"{\"key\":42,\"value\":\"\"}\n"[..];
+        assert!(matches!(raw.import_jsonl(&mut wtxn, not_a_string), Err(Error::Io(_))));
+
+        let typed = raw.remap_types::<Str, U32<byteorder::BigEndian>>();
+        let not_a_number = &b"{\"key\":\"hello\",\"value\":\"world\"}\n"[..];
+        assert!(typed.import_jsonl(&mut wtxn, not_a_number).is_err());
+        assert_eq!(raw.len(&wtxn).unwrap(), 0);
+    }
+}
@@ -0,0 +1,307 @@
+use std::ops::RangeBounds;
+
+use heed_traits::LexicographicComparator;
+
+use crate::envs::DefaultComparator;
+use crate::*;
+
+/// The operations shared by the [`Database`] and the encrypted database types,
+/// to write code that works with both.
+///
+/// The read operations take the transaction as a [`KvDatabase::ReadTxn`]: a shared reference
+/// for a [`Database`] and a mutable one for an encrypted database, which needs it to decrypt
+/// the entries. Generic code gets one from a read transaction with [`KvDatabase::read_txn`]
+/// and from a write transaction with [`KvDatabase::write_txn`]. The inherent methods of the
+/// types are preferred by the method resolution, this trait is only useful in generic code.
+///
+/// ```
+/// use heed::byteorder::BigEndian;
+/// use heed::types::*;
+/// use heed::{Database, EnvOpenOptions, KvDatabase, RwTxn};
+///
+/// fn increment<D>(db: &D, wtxn: &mut RwTxn, key: &str) -> heed::Result<u32>
+/// where
+///     D: KvDatabase<Str, U32<BigEndian>>,
+/// {
+///     let count = db.get(D::write_txn(wtxn), key)?.unwrap_or(0) + 1;
+///     db.put(wtxn, key, &count)?;
+///     Ok(count)
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let dir = tempfile::tempdir()?;
+/// let env = unsafe { EnvOpenOptions::new().max_dbs(1).open(dir.path())? };
+///
+/// let mut wtxn = env.write_txn()?;
+/// let db: Database<Str, U32<BigEndian>> = env.create_database(&mut wtxn, Some("counters"))?;
+/// assert_eq!(increment(&db, &mut wtxn, "visits")?, 1);
+/// assert_eq!(increment(&db, &mut wtxn, "visits")?, 2);
+/// assert_eq!(KvDatabase::len(&db, &wtxn)?, 1);
+/// wtxn.commit()?;
+/// # Ok(()) }
+/// ```
+pub trait KvDatabase<KC, DC, C = DefaultComparator> {
+    /// How the read operations borrow a transaction,
+    /// `&'txn RoTxn<'e, T>` or `&'txn mut RoTxn<'e, T>`.
+    type ReadTxn<'txn, 'e: 'txn, T: 'e>;
+
+    /// Borrows a read transaction to read from the database.
+    fn read_txn<'txn, 'e, T>(txn: &'txn mut RoTxn<'e, T>) -> Self::ReadTxn<'txn, 'e, T>;
+
+    /// Borrows a write transaction to read from the database.
+    fn write_txn<'txn, 'p>(wtxn: &'txn mut RwTxn<'p>) -> Self::ReadTxn<'txn, 'p, WithoutTls>;
+
+    /// Retrieves the value associated with a key, see [`Database::get`].
+    fn get<'a, 'txn, 'e, T>(
+        &self,
+        txn: Self::ReadTxn<'txn, 'e, T>,
+        key: &'a KC::EItem,
+    ) -> Result<Option<DC::DItem>>
+    where
+        KC: BytesEncode<'a>,
+        DC: BytesDecode<'txn>;
+
+    /// Inserts a key-value pair, replacing the previous value, see [`Database::put`].
+    fn put<'a>(&self, txn: &mut RwTxn, key: &'a KC::EItem, data: &'a DC::EItem) -> Result<()>
+    where
+        KC: BytesEncode<'a>,
+        DC: BytesEncode<'a>;
+
+    /// Deletes an entry, returns `true` if it was found, see [`Database::delete`].
+    fn delete<'a>(&self, txn: &mut RwTxn, key: &'a KC::EItem) -> Result<bool>
+    where
+        KC: BytesEncode<'a>;
+
+    /// Returns an ordered iterator of all the entries, see [`Database::iter`].
+    fn iter<'txn, 'e, T>(&self, txn: Self::ReadTxn<'txn, 'e, T>)
+        -> Result<RoIter<'txn, T, KC, DC>>;
+
+    /// Returns an ordered iterator of a range of entries, see [`Database::range`].
+    fn range<'a, 'txn, 'e, R, T>(
+        &self,
+        txn: Self::ReadTxn<'txn, 'e, T>,
+        range: &'a R,
+    ) -> Result<RoRange<'txn, T, KC, DC, C>>
+    where
+        KC: BytesEncode<'a>,
+        R: RangeBounds<KC::EItem>;
+
+    /// Returns an ordered iterator of the entries starting with a prefix,
+    /// see [`Database::prefix_iter`].
+    fn prefix_iter<'a, 'txn, 'e, T>(
+        &self,
+        txn: Self::ReadTxn<'txn, 'e, T>,
+        prefix: &'a KC::EItem,
+    ) -> Result<RoPrefix<'txn, T, KC, DC, C>>
+    where
+        KC: BytesEncode<'a>,
+        C: LexicographicComparator;
+
+    /// Returns some statistics of the database, see [`Database::stat`].
+    fn stat<T>(&self, txn: &RoTxn<T>) -> Result<DatabaseStat>;
+
+    /// Returns the number of entries, see [`Database::len`].
+    fn len<T>(&self, txn: &RoTxn<T>) -> Result<u64>;
+
+    /// Returns `true` if the database is empty, see [`Database::is_empty`].
+    fn is_empty<T>(&self, txn: &RoTxn<T>) -> Result<bool>;
+
+    /// Deletes every entry, see [`Database::clear`].
+    fn clear(&self, txn: &mut RwTxn) -> Result<()>;
+}
+
+/// Implements [`KvDatabase`] by delegating to the inherent methods of the type,
+/// the read operations borrow the transaction mutably when `mut` is given.
+macro_rules! impl_kv_database {
+    ($database:ident $(, $mut:ident)?) => {
+        impl<KC, DC, C> KvDatabase<KC, DC, C> for $database<KC, DC, C> {
+            type ReadTxn<'txn, 'e: 'txn, T: 'e> = &'txn $($mut)? RoTxn<'e, T>;
+
+            fn read_txn<'txn, 'e, T>(txn: &'txn mut RoTxn<'e, T>) -> Self::ReadTxn<'txn, 'e, T> {
+                txn
+            }
+
+            fn write_txn<'txn, 'p>(
+                wtxn: &'txn mut RwTxn<'p>,
+            ) -> Self::ReadTxn<'txn, 'p, WithoutTls> {
+                &$($mut)? **wtxn
+            }
+
+            fn get<'a, 'txn, 'e, T>(
+                &self,
+                txn: Self::ReadTxn<'txn, 'e, T>,
+                key: &'a KC::EItem,
+            ) -> Result<Option<DC::DItem>>
+            where
+                KC: BytesEncode<'a>,
+                DC: BytesDecode<'txn>,
+            {
+                $database::get(self, txn, key)
+            }
+
+            fn put<'a>(
+                &self,
+                txn: &mut RwTxn,
+                key: &'a KC::EItem,
+                data: &'a DC::EItem,
+            ) -> Result<()>
+            where
+                KC: BytesEncode<'a>,
+                DC: BytesEncode<'a>,
+            {
+                $database::put(self, txn, key, data)
+            }
+
+            fn delete<'a>(&self, txn: &mut RwTxn, key: &'a KC::EItem) -> Result<bool>
+            where
+                KC: BytesEncode<'a>,
+            {
+                $database::delete(self, txn, key)
+            }
+
+            fn iter<'txn, 'e, T>(
+                &self,
+                txn: Self::ReadTxn<'txn, 'e, T>,
+            ) -> Result<RoIter<'txn, T, KC, DC>> {
+                $database::iter(self, txn)
+            }
+
+            fn range<'a, 'txn, 'e, R, T>(
+                &self,
+                txn: Self::ReadTxn<'txn, 'e, T>,
+                range: &'a R,
+            ) -> Result<RoRange<'txn, T, KC, DC, C>>
+            where
+                KC: BytesEncode<'a>,
+                R: RangeBounds<KC::EItem>,
+            {
+                $database::range(self, txn, range)
+            }
+
+            fn prefix_iter<'a, 'txn, 'e, T>(
+                &self,
+                txn: Self::ReadTxn<'txn, 'e, T>,
+                prefix: &'a KC::EItem,
+            ) -> Result<RoPrefix<'txn, T, KC, DC, C>>
+            where
+                KC: BytesEncode<'a>,
+                C: LexicographicComparator,
+            {
+                $database::prefix_iter(self, txn, prefix)
+            }
+
+            fn stat<T>(&self, txn: &RoTxn<T>) -> Result<DatabaseStat> {
+                $database::stat(self, txn)
+            }
+
+            fn len<T>(&self, txn: &RoTxn<T>) -> Result<u64> {
+                $database::len(self, txn)
+            }
+
+            fn is_empty<T>(&self, txn: &RoTxn<T>) -> Result<bool> {
+                $database::is_empty(self, txn)
+            }
+
+            fn clear(&self, txn: &mut RwTxn) -> Result<()> {
+                $database::clear(self, txn)
+            }
+        }
+    };
+}
+
+impl_kv_database!(Database);
+#[cfg(master3)]
+impl_kv_database!(EncryptedDatabase, mut);
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use byteorder::BigEndian;
+    use types::{Bytes, Str, U32};
+
+    use super::*;
+
+    /// Exercises the trait the same way for every implementation,
+    /// `raw` must be the same database with untyped entries.
+    fn exercise<D, R>(db: &D, raw: &R, wtxn: &mut RwTxn)
+    where
+        D: KvDatabase<Str, U32<BigEndian>>,
+        R: KvDatabase<Bytes, Bytes>,
+    {
+        assert!(db.is_empty(wtxn).unwrap());
+        db.put(wtxn, "apple", &1).unwrap();
+        db.put(wtxn, "apricot", &2).unwrap();
+        db.put(wtxn, "banana", &3).unwrap();
+        assert_eq!(db.len(wtxn).unwrap(), 3);
+
+        assert_eq!(db.get(D::write_txn(wtxn), "apricot").unwrap(), Some(2));
+        assert_eq!(db.get(D::write_txn(wtxn), "cherry").unwrap(), None);
+        let all: Vec<_> = db.iter(D::write_txn(wtxn)).unwrap().map(|r| r.unwrap().1).collect();
+        assert_eq!(all, [1, 2, 3]);
+        let range = (Bound::Included("apricot"), Bound::Unbounded);
+        let range = db.range(D::write_txn(wtxn), &range).unwrap();
+        assert_eq!(range.map(|r| r.unwrap().0).collect::<Vec<_>>(), ["apricot", "banana"]);
+        let prefix = db.prefix_iter(D::write_txn(wtxn), "ap").unwrap();
+        assert_eq!(prefix.count(), 2);
+
+        // Entries that don't decode are reported whatever the implementation.
+        raw.put(wtxn, b"broken", &[1, 2]).unwrap();
+        assert!(matches!(db.get(D::write_txn(wtxn), "broken"), Err(Error::Decoding(_))));
+        raw.put(wtxn, &[0xff], &[0, 0, 0, 4]).unwrap();
+        let last = db.iter(D::write_txn(wtxn)).unwrap().last().unwrap();
+        assert!(matches!(last, Err(Error::Decoding(_))));
+
+        assert!(db.delete(wtxn, "banana").unwrap());
+        assert!(!db.delete(wtxn, "banana").unwrap());
+        db.clear(wtxn).unwrap();
+        assert!(db.is_empty(wtxn).unwrap());
+        assert_eq!(db.stat(wtxn).unwrap().entries, 0);
+    }
+
+    #[test]
+    fn database() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().max_dbs(1).open(dir.path()).unwrap() };
+        let mut wtxn = env.write_txn().unwrap();
+        let db: Database<Str, U32<BigEndian>> =
+            env.create_database(&mut wtxn, Some("counters")).unwrap();
+        exercise(&db, &db.remap_types::<Bytes, Bytes>(), &mut wtxn);
+
+        db.put(&mut wtxn, "kiwi", &5).unwrap();
+        wtxn.commit().unwrap();
+
+        let mut rtxn = env.read_txn().unwrap();
+        let txn = <Database<Str, U32<BigEndian>> as KvDatabase<_, _>>::read_txn(&mut rtxn);
+        assert_eq!(KvDatabase::get(&db, txn, "kiwi").unwrap(), Some(5));
+    }
+
+    #[test]
+    #[cfg(master3)]
+    fn encrypted_database() {
+        use aead::Key;
+        use chacha20poly1305::ChaCha20Poly1305;
+
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe {
+            EnvOpenOptions::new()
+                .max_dbs(1)
+                .open_encrypted::<ChaCha20Poly1305, _>(
+                    Key::<ChaCha20Poly1305>::default(),
+                    dir.path(),
+                )
+                .unwrap()
+        };
+        let mut wtxn = env.write_txn().unwrap();
+        let db: EncryptedDatabase<Str, U32<BigEndian>> =
+            env.create_database(&mut wtxn, Some("counters")).unwrap();
+        exercise(&db, &db.remap_types::<Bytes, Bytes>(), &mut wtxn);
+
+        db.put(&mut wtxn, "kiwi", &5).unwrap();
+        wtxn.commit().unwrap();
+
+        let mut rtxn = env.read_txn().unwrap();
+        let txn = <EncryptedDatabase<Str, U32<BigEndian>> as KvDatabase<_, _>>::read_txn(&mut rtxn);
+        assert_eq!(KvDatabase::get(&db, txn, "kiwi").unwrap(), Some(5));
+    }
+}
@@ -1,10 +1,22 @@
 pub use database::{Database, DatabaseOpenOptions};
 #[cfg(master3)]
 pub use encrypted_database::{EncryptedDatabase, EncryptedDatabaseOpenOptions};
+pub use fingerprint::{DatabaseFingerprint, FingerprintMismatch};
+pub use indexed::{IndexKey, IndexedDatabase, IndexedEntries};
+#[cfg(feature = "serde-json")]
+pub use jsonl::{JsonlDecode, JsonlEncode, JsonlSerde};
+pub use kv_database::KvDatabase;
+pub use validate::{ValidationFailure, ValidationReport};
 
 mod database;
 #[cfg(master3)]
 mod encrypted_database;
+mod fingerprint;
+mod indexed;
+#[cfg(feature = "serde-json")]
+mod jsonl;
+mod kv_database;
+mod validate;
 
 /// Statistics for a database in the environment.
 #[derive(Debug, Clone, Copy)]
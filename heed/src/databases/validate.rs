@@ -0,0 +1,154 @@
+use types::Bytes;
+
+use crate::*;
+
+/// The maximum number of failures kept by [`Database::validate`].
+const DEFAULT_MAX_FAILURES: usize = 100;
+
+/// The result of [`Database::validate`].
+#[derive(Debug)]
+pub struct ValidationReport {
+    /// The number of entries checked, duplicate values included.
+    pub entries: usize,
+    /// The number of entries that failed to decode, including the ones not kept in `failures`.
+    pub failure_count: usize,
+    /// The first entries that failed to decode, bounded by the maximum number of failures.
+    pub failures: Vec<ValidationFailure>,
+}
+
+impl ValidationReport {
+    /// Returns `true` if every entry of the database decoded successfully.
+    pub fn is_ok(&self) -> bool {
+        self.failure_count == 0
+    }
+}
+
+/// An entry that failed to decode, see [`Database::validate`].
+#[derive(Debug)]
+pub struct ValidationFailure {
+    /// The raw bytes of the key.
+    pub key: Vec<u8>,
+    /// The error returned by the key codec, if it failed.
+    pub key_error: Option<BoxedError>,
+    /// The error returned by the data codec, if it failed.
+    pub data_error: Option<BoxedError>,
+}
+
+impl<KC, DC, C> Database<KC, DC, C> {
+    /// Decodes every entry of this database with the `KC` and `DC` codecs and
+    /// reports the ones that fail instead of stopping at the first error.
+    ///
+    /// At most 100 failures are kept in the report, use [`Database::validate_with_max_failures`]
+    /// to change that. It is useful after a change of the codecs, to find the entries written
+    /// in an old format before they are read in production.
+    ///
+    /// ```
+    /// # use heed::EnvOpenOptions;
+    /// use heed::Database;
+    /// use heed::types::*;
+    /// use heed::byteorder::BigEndian;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let dir = tempfile::tempdir()?;
+    /// # let env = unsafe { EnvOpenOptions::new()
+    /// #     .map_size(10 * 1024 * 1024) // 10MB
+    /// #     .max_dbs(3000)
+    /// #     .open(dir.path())?
+    /// # };
+    /// type BEU32 = U32<BigEndian>;
+    ///
+    /// let mut wtxn = env.write_txn()?;
+    /// let db: Database<Str, Bytes> = env.create_database(&mut wtxn, Some("validate"))?;
+    /// db.put(&mut wtxn, "valid", &[0, 0, 0, 42])?;
+    /// db.put(&mut wtxn, "too-short", &[42])?;
+    ///
+    /// let report = db.remap_data_type::<BEU32>().validate(&wtxn)?;
+    /// assert_eq!(report.entries, 2);
+    /// assert_eq!(report.failure_count, 1);
+    /// assert_eq!(report.failures[0].key, b"too-short");
+    /// assert!(report.failures[0].key_error.is_none());
+    /// assert!(report.failures[0].data_error.is_some());
+    ///
+    /// wtxn.commit()?;
+    /// # Ok(()) }
+    /// ```
+    pub fn validate<'txn, T>(&self, txn: &'txn RoTxn<T>) -> Result<ValidationReport>
+    where
+        KC: BytesDecode<'txn>,
+        DC: BytesDecode<'txn>,
+    {
+        self.validate_with_max_failures(txn, DEFAULT_MAX_FAILURES)
+    }
+
+    /// Decodes every entry of this database with the `KC` and `DC` codecs and reports
+    /// the ones that fail, keeping at most `max_failures` of them in the report.
+    ///
+    /// See [`Database::validate`] for more details.
+    pub fn validate_with_max_failures<'txn, T>(
+        &self,
+        txn: &'txn RoTxn<T>,
+        max_failures: usize,
+    ) -> Result<ValidationReport>
+    where
+        KC: BytesDecode<'txn>,
+        DC: BytesDecode<'txn>,
+    {
+        let mut report = ValidationReport { entries: 0, failure_count: 0, failures: Vec::new() };
+
+        for result in self.remap_types::<Bytes, Bytes>().iter(txn)? {
+            let (key, data) = result?;
+            report.entries += 1;
+
+            let key_error = KC::bytes_decode(key).err();
+            let data_error = DC::bytes_decode(data).err();
+            if key_error.is_some() || data_error.is_some() {
+                report.failure_count += 1;
+                if report.failures.len() < max_failures {
+                    report.failures.push(ValidationFailure {
+                        key: key.to_vec(),
+                        key_error,
+                        data_error,
+                    });
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use types::{Bytes, Str, U32};
+
+    use crate::*;
+
+    type BEU32 = U32<byteorder::BigEndian>;
+
+    #[test]
+    fn failures_are_bounded_but_counted() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().open(dir.path()).unwrap() };
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<Bytes, Bytes>(&mut wtxn, None).unwrap();
+        db.put(&mut wtxn, b"valid", &[0, 0, 0, 1]).unwrap();
+        db.put(&mut wtxn, &[0xff], &[0, 0, 0, 2]).unwrap();
+        db.put(&mut wtxn, b"short", &[3]).unwrap();
+        db.put(&mut wtxn, &[0xfe], &[4]).unwrap();
+
+        let report = db.remap_types::<Str, BEU32>().validate_with_max_failures(&wtxn, 2).unwrap();
+        assert_eq!(report.entries, 4);
+        assert_eq!(report.failure_count, 3);
+        assert!(!report.is_ok());
+
+        // The failures are kept in the order of the keys.
+        let keys: Vec<_> = report.failures.iter().map(|f| f.key.as_slice()).collect();
+        assert_eq!(keys, [&b"short"[..], &[0xfe]]);
+        assert!(report.failures[0].key_error.is_none() && report.failures[0].data_error.is_some());
+        assert!(report.failures[1].key_error.is_some() && report.failures[1].data_error.is_some());
+
+        let report = db.remap_types::<Bytes, Bytes>().validate(&wtxn).unwrap();
+        assert!(report.is_ok());
+        assert_eq!(report.entries, 4);
+    }
+}
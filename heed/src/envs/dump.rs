@@ -0,0 +1,305 @@
+use std::io::{self, BufRead};
+
+use types::Bytes;
+
+use super::{DumpFormat, Env};
+use crate::mdb::error::mdb_result;
+use crate::mdb::ffi;
+use crate::*;
+
+/// The version of the format written by `mdb_dump`.
+const DUMP_VERSION: u32 = 3;
+
+/// The database flags and their names in the `mdb_dump` headers.
+#[allow(deprecated)] // ok because the dumps must keep the INTEGER_KEY flag
+const DUMP_FLAGS: [(DatabaseFlags, &str); 6] = [
+    (DatabaseFlags::REVERSE_KEY, "reversekey"),
+    (DatabaseFlags::DUP_SORT, "dupsort"),
+    (DatabaseFlags::INTEGER_KEY, "integerkey"),
+    (DatabaseFlags::DUP_FIXED, "dupfixed"),
+    (DatabaseFlags::INTEGER_DUP, "integerdup"),
+    (DatabaseFlags::REVERSE_DUP, "reversedup"),
+];
+
+impl<T> Env<T> {
+    /// Writes the content of the given databases in the text format of the `mdb_dump` tool,
+    /// one after the other, `None` being the unnamed database.
+    ///
+    /// The output can be loaded back with [`Env::load`] or with the `mdb_load` tool. Unlike
+    /// [`Env::copy_to_file`], it doesn't depend on the page size nor on the architecture.
+    ///
+    /// ```
+    /// use heed::{DumpFormat, EnvOpenOptions};
+    /// use heed::types::*;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir = tempfile::tempdir()?;
+    /// let env = unsafe { EnvOpenOptions::new().max_dbs(1).open(dir.path())? };
+    ///
+    /// let mut wtxn = env.write_txn()?;
+    /// let db = env.create_database::<Str, Str>(&mut wtxn, Some("words"))?;
+    /// db.put(&mut wtxn, "hello", "world")?;
+    /// wtxn.commit()?;
+    ///
+    /// let mut dump = Vec::new();
+    /// let rtxn = env.read_txn()?;
+    /// env.dump(&rtxn, &[Some("words")], DumpFormat::Print, &mut dump)?;
+    /// drop(rtxn);
+    ///
+    /// let other_dir = tempfile::tempdir()?;
+    /// let other = unsafe { EnvOpenOptions::new().max_dbs(1).open(other_dir.path())? };
+    /// let mut wtxn = other.write_txn()?;
+    /// assert_eq!(other.load(&mut wtxn, &dump[..])?, 1);
+    /// wtxn.commit()?;
+    ///
+    /// let rtxn = other.read_txn()?;
+    /// let db = other.open_database::<Str, Str>(&rtxn, Some("words"))?.unwrap();
+    /// assert_eq!(db.get(&rtxn, "hello")?, Some("world"));
+    /// # Ok(()) }
+    /// ```
+    pub fn dump<W: io::Write>(
+        &self,
+        rtxn: &RoTxn<T>,
+        databases: &[Option<&str>],
+        format: DumpFormat,
+        mut writer: W,
+    ) -> Result<()> {
+        let info = self.info();
+
+        for &name in databases {
+            let db = match self.open_database::<Bytes, Bytes>(rtxn, name)? {
+                Some(db) => db,
+                None => return Err(Error::Mdb(MdbError::NotFound)),
+            };
+
+            let mut flags = 0;
+            unsafe {
+                mdb_result(ffi::mdb_dbi_flags(rtxn.txn.unwrap().as_ptr(), db.dbi, &mut flags))?
+            };
+            let flags = DatabaseFlags::from_bits_truncate(flags);
+            let stat = db.stat(rtxn)?;
+
+            writeln!(writer, "VERSION={DUMP_VERSION}")?;
+            match format {
+                DumpFormat::Print => writeln!(writer, "format=print")?,
+                DumpFormat::Hex => writeln!(writer, "format=bytevalue")?,
+            }
+            if let Some(name) = name {
+                writeln!(writer, "database={name}")?;
+            }
+            writeln!(writer, "type=btree")?;
+            writeln!(writer, "mapsize={}", info.map_size)?;
+            writeln!(writer, "maxreaders={}", info.maximum_number_of_readers)?;
+            if flags.contains(DatabaseFlags::DUP_SORT) {
+                writeln!(writer, "duplicates=1")?;
+            }
+            for (flag, flag_name) in DUMP_FLAGS {
+                if flags.contains(flag) {
+                    writeln!(writer, "{flag_name}=1")?;
+                }
+            }
+            writeln!(writer, "db_pagesize={}", stat.page_size)?;
+            writeln!(writer, "HEADER=END")?;
+
+            for result in db.iter(rtxn)? {
+                let (key, data) = result?;
+                write_dump_line(&mut writer, format, key)?;
+                write_dump_line(&mut writer, format, data)?;
+            }
+            writeln!(writer, "DATA=END")?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Loads the databases written in the text format of the `mdb_dump` tool,
+    /// either by [`Env::dump`] or by the `mdb_dump` tool itself.
+    ///
+    /// The databases are created with the flags found in the headers and the entries are
+    /// written into them, replacing existing ones. The `mapsize` and `maxreaders` headers
+    /// are ignored as the environment is already opened.
+    ///
+    /// Returns the number of entries loaded.
+    pub fn load<R: io::BufRead>(&self, wtxn: &mut RwTxn, mut reader: R) -> Result<usize> {
+        let mut count = 0;
+        let mut line = Vec::new();
+
+        loop {
+            // The headers of the next database, if any.
+            let mut format = None;
+            let mut name = None;
+            let mut flags = DatabaseFlags::empty();
+            let mut started = false;
+            loop {
+                if !read_dump_line(&mut reader, &mut line)? {
+                    if started {
+                        return Err(invalid_dump("unexpected end of the headers").into());
+                    }
+                    return Ok(count);
+                }
+                started = true;
+
+                let Some((key, value)) = split_header(&line) else {
+                    return Err(invalid_dump("malformed header line").into());
+                };
+                match key {
+                    b"VERSION" if value == DUMP_VERSION.to_string().as_bytes() => (),
+                    b"VERSION" => return Err(invalid_dump("unsupported version").into()),
+                    b"format" if value == b"print" => format = Some(DumpFormat::Print),
+                    b"format" if value == b"bytevalue" => format = Some(DumpFormat::Hex),
+                    b"format" => return Err(invalid_dump("unsupported format").into()),
+                    b"type" if value == b"btree" => (),
+                    b"type" => return Err(invalid_dump("unsupported database type").into()),
+                    b"database" => {
+                        let value = std::str::from_utf8(value)
+                            .map_err(|_| invalid_dump("invalid database name"))?;
+                        name = Some(value.to_owned());
+                    }
+                    b"mapsize" | b"mapaddr" | b"maxreaders" | b"db_pagesize" | b"duplicates" => (),
+                    b"HEADER" if value == b"END" => break,
+                    _ => match DUMP_FLAGS.iter().find(|(_, n)| n.as_bytes() == key) {
+                        Some((flag, _)) if value == b"1" => flags |= *flag,
+                        Some(_) => (),
+                        None => return Err(invalid_dump("unrecognized header keyword").into()),
+                    },
+                }
+            }
+
+            let format = format.ok_or_else(|| invalid_dump("missing format header"))?;
+            let mut options = self.database_options().types::<Bytes, Bytes>();
+            if let Some(name) = name.as_deref() {
+                options.name(name);
+            }
+            options.flags(flags);
+            let db = options.create(wtxn)?;
+
+            loop {
+                if !read_dump_line(&mut reader, &mut line)? {
+                    return Err(invalid_dump("unexpected end of the data").into());
+                }
+                if line == b"DATA=END" {
+                    break;
+                }
+                let key = parse_dump_line(format, &line)?;
+                if !read_dump_line(&mut reader, &mut line)? {
+                    return Err(invalid_dump("missing the data of a key").into());
+                }
+                let data = parse_dump_line(format, &line)?;
+                db.put(wtxn, &key, &data)?;
+                count += 1;
+            }
+        }
+    }
+}
+
+fn write_dump_line<W: io::Write>(
+    writer: &mut W,
+    format: DumpFormat,
+    bytes: &[u8],
+) -> io::Result<()> {
+    writer.write_all(b" ")?;
+    match format {
+        DumpFormat::Print => {
+            for &byte in bytes {
+                match byte {
+                    b'\\' => writer.write_all(b"\\\\")?,
+                    b' '..=b'~' => writer.write_all(&[byte])?,
+                    _ => write!(writer, "\\{byte:02x}")?,
+                }
+            }
+        }
+        DumpFormat::Hex => {
+            for &byte in bytes {
+                write!(writer, "{byte:02x}")?;
+            }
+        }
+    }
+    writer.write_all(b"\n")
+}
+
+/// Reads a line without its line feed, returns `false` at the end of the reader.
+fn read_dump_line<R: BufRead>(reader: &mut R, line: &mut Vec<u8>) -> io::Result<bool> {
+    line.clear();
+    if reader.read_until(b'\n', line)? == 0 {
+        return Ok(false);
+    }
+    if line.last() == Some(&b'\n') {
+        line.pop();
+    }
+    Ok(true)
+}
+
+fn parse_dump_line(format: DumpFormat, line: &[u8]) -> io::Result<Vec<u8>> {
+    let Some(line) = line.strip_prefix(b" ") else {
+        return Err(invalid_dump("data lines must start with a space"));
+    };
+
+    let mut bytes = Vec::with_capacity(line.len());
+    match format {
+        DumpFormat::Print => {
+            let mut iter = line.iter().copied();
+            while let Some(byte) = iter.next() {
+                if byte != b'\\' {
+                    bytes.push(byte);
+                    continue;
+                }
+                match iter.next() {
+                    Some(b'\\') => bytes.push(b'\\'),
+                    Some(high) => {
+                        let low = iter.next().ok_or_else(|| invalid_dump("truncated escape"))?;
+                        bytes.push(hex_byte(high, low)?);
+                    }
+                    None => return Err(invalid_dump("truncated escape")),
+                }
+            }
+        }
+        DumpFormat::Hex => {
+            if line.len() % 2 != 0 {
+                return Err(invalid_dump("odd number of hexadecimal digits"));
+            }
+            for pair in line.chunks_exact(2) {
+                bytes.push(hex_byte(pair[0], pair[1])?);
+            }
+        }
+    }
+
+    Ok(bytes)
+}
+
+fn hex_byte(high: u8, low: u8) -> io::Result<u8> {
+    let digit = |c: u8| {
+        (c as char).to_digit(16).map(|d| d as u8).ok_or_else(|| invalid_dump("invalid hex digit"))
+    };
+    Ok(digit(high)? << 4 | digit(low)?)
+}
+
+fn invalid_dump(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid mdb_dump input: {msg}"))
+}
+
+/// Splits a `keyword=value` header line.
+fn split_header(line: &[u8]) -> Option<(&[u8], &[u8])> {
+    let index = line.iter().position(|&b| b == b'=')?;
+    Some((&line[..index], &line[index + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dump_lines_roundtrip() {
+        let bytes = b"hello \\ world\x00\xff\n";
+        for format in [DumpFormat::Print, DumpFormat::Hex] {
+            let mut line = Vec::new();
+            write_dump_line(&mut line, format, bytes).unwrap();
+            assert_eq!(line.pop(), Some(b'\n'));
+            assert_eq!(parse_dump_line(format, &line).unwrap(), bytes);
+        }
+
+        let mut line = Vec::new();
+        write_dump_line(&mut line, DumpFormat::Print, bytes).unwrap();
+        assert_eq!(line, b" hello \\\\ world\\00\\ff\\0a\n");
+    }
+}
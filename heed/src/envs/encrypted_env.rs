@@ -1,17 +1,24 @@
-use std::fmt;
-use std::fs::File;
-use std::panic::catch_unwind;
+use std::cmp::Ordering;
+use std::fs::{self, File};
+use std::io::Write;
 use std::path::Path;
+use std::{fmt, io};
 
-use aead::generic_array::typenum::Unsigned;
-use aead::{AeadMutInPlace, Key, KeyInit, Nonce, Tag};
+use aead::{AeadMutInPlace, Key, KeyInit};
 
-use super::{Env, EnvClosingEvent, EnvInfo, FlagSetMode};
+use super::env::{path_with_suffix, remove_file_if_exists, replace_file};
+use super::page_cipher::{key_check_block, verify_key_check_block, PageCipher};
+use super::{Env, EnvClosingEvent, EnvInfo, FlagSetMode, ReaderInfo};
 use crate::databases::{EncryptedDatabase, EncryptedDatabaseOpenOptions};
-use crate::mdb::ffi::{self};
-use crate::{CompactionOption, EnvFlags, Result, RoTxn, RwTxn, Unspecified};
+use crate::mdb::ffi;
+use crate::mdb::lmdb_error::mdb_result;
+use crate::types::{Bytes, DecodeIgnore};
 #[allow(unused)] // fro cargo auto doc links
-use crate::{Database, EnvOpenOptions};
+use crate::Database;
+use crate::{
+    ChangeBatch, CompactionOption, DatabaseFlags, EnvFlags, EnvOpenOptions, Error, MdbError,
+    PinnedRoTxn, Result, RoTxn, RwTxn, TlsUsage, Unspecified, WithoutTls,
+};
 
 /// An environment handle constructed by using [`EnvOpenOptions::open_encrypted`].
 #[derive(Clone)]
@@ -141,6 +148,14 @@ impl<T> EncryptedEnv<T> {
         options.open(rtxn)
     }
 
+    /// Returns the names of the named databases of this environment.
+    ///
+    /// The named databases are stored as keys of the unnamed one,
+    /// but not every key of the unnamed database is a database.
+    pub fn database_names(&self, rtxn: &RoTxn<T>) -> Result<Vec<String>> {
+        self.inner.database_names(rtxn)
+    }
+
     /// Creates a typed database that can already exist in this environment.
     ///
     /// If the database was previously opened during this program run, types will be checked.
@@ -249,6 +264,43 @@ impl<T> EncryptedEnv<T> {
         self.inner.static_read_txn()
     }
 
+    /// Create a read-only transaction that pins the entries read through it,
+    /// for the reads that do not require a mutable transaction.
+    ///
+    /// See [`PinnedRoTxn`] and the `_pinned` methods of [`EncryptedDatabase`]. Every entry read
+    /// through it is copied and kept in memory until the transaction is dropped.
+    ///
+    /// ```
+    /// use chacha20poly1305::{ChaCha20Poly1305, Key};
+    /// use heed3::types::*;
+    /// use heed3::EnvOpenOptions;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir = tempfile::tempdir()?;
+    /// let key = Key::from([1; 32]);
+    /// let env = unsafe { EnvOpenOptions::new().open_encrypted::<ChaCha20Poly1305, _>(key, dir.path())? };
+    ///
+    /// let mut wtxn = env.write_txn()?;
+    /// let db = env.create_database::<Str, Str>(&mut wtxn, None)?;
+    /// db.put(&mut wtxn, "hello", "world")?;
+    /// db.put(&mut wtxn, "bonjour", "monde")?;
+    /// wtxn.commit()?;
+    ///
+    /// let rtxn = env.pinned_read_txn()?;
+    /// let hello = db.get_pinned(&rtxn, "hello")?;
+    /// let bonjour = db.get_pinned(&rtxn, "bonjour")?;
+    /// // Both values can be kept at the same time.
+    /// assert_eq!((hello, bonjour), (Some("world"), Some("monde")));
+    ///
+    /// let entries: Vec<_> = db.iter_pinned(&rtxn)?.collect::<heed3::Result<_>>()?;
+    /// assert_eq!(entries, [("bonjour", "monde"), ("hello", "world")]);
+    /// assert_eq!(hello, Some("world"));
+    /// # Ok(()) }
+    /// ```
+    pub fn pinned_read_txn(&self) -> Result<PinnedRoTxn<T>> {
+        self.inner.read_txn().map(PinnedRoTxn::new)
+    }
+
     /// Copy an LMDB environment to the specified path, with options.
     ///
     /// This function may be used to make a backup of an existing environment.
@@ -273,6 +325,120 @@ impl<T> EncryptedEnv<T> {
         self.inner.copy_to_fd(fd, option)
     }
 
+    /// Stream a consistent copy of this LMDB environment into the given writer, with options.
+    ///
+    /// See [`Env::copy_to_writer`] for more details.
+    pub fn copy_to_writer<W: io::Write>(&self, writer: W, option: CompactionOption) -> Result<u64> {
+        self.inner.copy_to_writer(writer, option)
+    }
+
+    /// Stream a consistent copy of this LMDB environment into the given writer, with options,
+    /// calling `progress` with the total number of bytes written so far after every write.
+    ///
+    /// See [`Env::copy_to_writer`] for more details.
+    pub fn copy_to_writer_with_progress<W, F>(
+        &self,
+        writer: W,
+        option: CompactionOption,
+        progress: F,
+    ) -> Result<u64>
+    where
+        W: io::Write,
+        F: FnMut(u64),
+    {
+        self.inner.copy_to_writer_with_progress(writer, option, progress)
+    }
+
+    /// Copy this environment to the specified path, re-encrypted under a new key.
+    ///
+    /// LMDB can't re-encrypt pages, so every entry of every database is decrypted and written
+    /// into a staging environment encrypted with the new key, next to the destination, which
+    /// is then copied to the destination with [`EncryptedEnv::copy_to_file`] and removed.
+    /// It is a logical export: it needs free disk space for twice the size of the entries
+    /// and is slower than a raw copy. The `E` algorithm can differ from the one used by this
+    /// environment.
+    ///
+    /// The databases are recreated with the same flags but with the default comparators,
+    /// the databases using a custom [`Comparator`](crate::Comparator) are not supported.
+    /// An [`io::ErrorKind::InvalidInput`] error is returned, and nothing is written at the
+    /// destination, when a database is opened with a custom comparator in this program or
+    /// when its entries aren't ordered under the default comparator of its flags.
+    /// The copy must be opened with the new key and a [`EnvFlags::NO_SUB_DIR`] flag,
+    /// or be moved into a directory as its `data.mdb` file.
+    ///
+    /// ```
+    /// use heed3::types::*;
+    /// use heed3::{CompactionOption, EnvFlags, EnvOpenOptions};
+    /// use chacha20poly1305::{ChaCha20Poly1305, Key};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir = tempfile::tempdir()?;
+    /// let old_key = Key::from([1; 32]);
+    /// let env = unsafe {
+    ///     EnvOpenOptions::new().max_dbs(1).open_encrypted::<ChaCha20Poly1305, _>(old_key, dir.path())?
+    /// };
+    ///
+    /// let mut wtxn = env.write_txn()?;
+    /// let db = env.create_database::<Str, Str>(&mut wtxn, Some("secrets"))?;
+    /// db.put(&mut wtxn, "password", "hunter2")?;
+    /// wtxn.commit()?;
+    ///
+    /// let new_key = Key::from([2; 32]);
+    /// let copy_path = dir.path().join("copy.mdb");
+    /// env.copy_to_file_reencrypted::<ChaCha20Poly1305, _>(&copy_path, new_key, CompactionOption::Disabled)?;
+    ///
+    /// let copy = unsafe {
+    ///     EnvOpenOptions::new()
+    ///         .max_dbs(1)
+    ///         .flags(EnvFlags::NO_SUB_DIR)
+    ///         .open_encrypted::<ChaCha20Poly1305, _>(new_key, &copy_path)?
+    /// };
+    /// let mut rtxn = copy.read_txn()?;
+    /// let db = copy.open_database::<Str, Str>(&rtxn, Some("secrets"))?.unwrap();
+    /// assert_eq!(db.get(&mut rtxn, "password")?, Some("hunter2"));
+    /// # Ok(()) }
+    /// ```
+    pub fn copy_to_file_reencrypted<E, P>(
+        &self,
+        path: P,
+        new_key: Key<E>,
+        option: CompactionOption,
+    ) -> Result<File>
+    where
+        E: AeadMutInPlace + KeyInit,
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        if path.exists() {
+            return Err(io::Error::from(io::ErrorKind::AlreadyExists).into());
+        }
+
+        let staging_path = path_with_suffix(path, ".reencrypt");
+        let staging_lock_path = path_with_suffix(&staging_path, "-lock");
+        remove_file_if_exists(&staging_path)?;
+        remove_file_if_exists(&staging_lock_path)?;
+
+        let rtxn = self.inner.read_txn()?;
+        let names = self.inner.database_names(&rtxn)?;
+        let staging = unsafe {
+            let mut options = EnvOpenOptions::new().read_txn_without_tls();
+            options.map_size(self.info().map_size).max_dbs(names.len() as u32);
+            options.encryption(self.inner.encryption_options());
+            // Safety: the staging environment is only used by this function.
+            options.flags(EnvFlags::NO_SUB_DIR);
+            options.open_encrypted::<E, _>(new_key, &staging_path)?
+        };
+
+        let result = copy_entries(&self.inner, &rtxn, &staging.inner, &names)
+            .and_then(|()| staging.copy_to_file(path, option));
+        drop(rtxn);
+
+        staging.prepare_for_closing().wait();
+        remove_file_if_exists(&staging_path)?;
+        remove_file_if_exists(&staging_lock_path)?;
+        result
+    }
+
     /// Flush the data buffers to disk.
     pub fn force_sync(&self) -> Result<()> {
         self.inner.force_sync()
@@ -283,6 +449,13 @@ impl<T> EncryptedEnv<T> {
         self.inner.path()
     }
 
+    /// Returns a receiver of the [`ChangeBatch`]es committed after this call.
+    ///
+    /// See [`Env::subscribe`] for more details.
+    pub fn subscribe(&self) -> std::sync::mpsc::Receiver<ChangeBatch> {
+        self.inner.subscribe()
+    }
+
     /// Returns an `EnvClosingEvent` that can be used to wait for the closing event,
     /// multiple threads can wait on this event.
     ///
@@ -299,6 +472,11 @@ impl<T> EncryptedEnv<T> {
         self.inner.clear_stale_readers()
     }
 
+    /// Returns the entries of the reader lock table, one per reader slot in use.
+    pub fn readers(&self) -> Result<Vec<ReaderInfo>> {
+        self.inner.readers()
+    }
+
     /// Resize the memory map to a new size.
     ///
     /// # Safety
@@ -318,6 +496,220 @@ impl<T> EncryptedEnv<T> {
     }
 }
 
+impl<T: TlsUsage> EncryptedEnv<T> {
+    /// Re-encrypts the environment in place under a new key and reopens it with the same
+    /// [`EnvOpenOptions`].
+    ///
+    /// A copy re-encrypted with [`EncryptedEnv::copy_to_file_reencrypted`] is written next to
+    /// the data file, then the environment is closed and the copy atomically renamed over the
+    /// original data file before reopening it with the new key. A copy left by an interrupted
+    /// rotation is removed first.
+    ///
+    /// This method waits for the environment to be closed, i.e. for every other clone of
+    /// this `EncryptedEnv` and every transaction to be dropped. It will never return if the
+    /// calling thread still holds one of them.
+    ///
+    /// ```
+    /// use heed3::types::*;
+    /// use heed3::EnvOpenOptions;
+    /// use chacha20poly1305::{ChaCha20Poly1305, Key};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir = tempfile::tempdir()?;
+    /// let old_key = Key::from([1; 32]);
+    /// let env = unsafe { EnvOpenOptions::new().open_encrypted::<ChaCha20Poly1305, _>(old_key, dir.path())? };
+    ///
+    /// let mut wtxn = env.write_txn()?;
+    /// let db = env.create_database::<Str, Str>(&mut wtxn, None)?;
+    /// db.put(&mut wtxn, "password", "hunter2")?;
+    /// wtxn.commit()?;
+    ///
+    /// let new_key = Key::from([2; 32]);
+    /// let env = unsafe { env.rotate_key::<ChaCha20Poly1305>(new_key)? };
+    ///
+    /// let mut rtxn = env.read_txn()?;
+    /// let db = env.open_database::<Str, Str>(&rtxn, None)?.unwrap();
+    /// assert_eq!(db.get(&mut rtxn, "password")?, Some("hunter2"));
+    /// # Ok(()) }
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// The environment is reopened and the same precautions as [`EnvOpenOptions::open_encrypted`]
+    /// apply. Moreover, no other process must have this environment opened, as it would keep
+    /// using the replaced data file.
+    pub unsafe fn rotate_key<E>(self, new_key: Key<E>) -> Result<EncryptedEnv<T>>
+    where
+        E: AeadMutInPlace + KeyInit,
+    {
+        let options = self.inner.open_options();
+        let path = self.path().to_path_buf();
+        let data_path = self.inner.data_file_path();
+        let copy_path = path_with_suffix(&data_path, ".rotate");
+
+        remove_file_if_exists(&copy_path)?;
+        let copy = self.copy_to_file_reencrypted::<E, _>(
+            &copy_path,
+            new_key.clone(),
+            CompactionOption::Enabled,
+        )?;
+        copy.sync_all()?;
+        drop(copy);
+
+        self.prepare_for_closing().wait();
+        // The key check block of the previous key is removed first, a crash leaves an
+        // environment encrypted with the previous key that is checked by its first entry.
+        remove_file_if_exists(&path_with_suffix(&data_path, KEY_CHECK_SUFFIX))?;
+        replace_file(&copy_path, &data_path)?;
+
+        unsafe { options.open_encrypted::<E, _>(new_key, path) }
+    }
+}
+
+/// The suffix of the file storing the key check block, next to the data file.
+const KEY_CHECK_SUFFIX: &str = ".key-check";
+
+/// Makes sure that the environment is opened with the key and options it was encrypted with.
+///
+/// The key is verified against the key check block stored next to the data file, a page
+/// that cannot be decrypted is then a corrupted page and is reported as such by LMDB with
+/// an [`MdbError::CryptoFail`]. Environments without a key check block, like the ones
+/// created before it existed or by copying the data file, are checked by reading the first
+/// entry of the unnamed database, where a [`MdbError::CryptoFail`] can't be told apart from
+/// a wrong key and is converted into an [`Error::InvalidEncryptionKey`]. The block is
+/// written once the key is known to be right, an empty environment accepts any key.
+pub(crate) fn check_encryption_key<C: PageCipher, T>(
+    env: &Env<T>,
+    cipher_key: &[u8],
+) -> Result<()> {
+    let path = path_with_suffix(&env.data_file_path(), KEY_CHECK_SUFFIX);
+    match fs::read(&path) {
+        Ok(block) if verify_key_check_block::<C>(cipher_key, &block) => return Ok(()),
+        Ok(_) => return Err(Error::InvalidEncryptionKey),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+        Err(e) => return Err(e.into()),
+    }
+
+    let check = || {
+        let rtxn = env.read_txn()?;
+        if let Some(main) = env.open_database::<DecodeIgnore, DecodeIgnore>(&rtxn, None)? {
+            main.first(&rtxn)?;
+        }
+        Ok(())
+    };
+
+    match check() {
+        Err(Error::Mdb(MdbError::CryptoFail)) => return Err(Error::InvalidEncryptionKey),
+        result => result?,
+    }
+
+    if EnvFlags::from_bits_truncate(env.get_flags()?).contains(EnvFlags::READ_ONLY) {
+        return Ok(());
+    }
+
+    let block = key_check_block::<C>(cipher_key).map_err(|e| io::Error::other(e.to_string()))?;
+    // The block is identical for every program opening the environment with the
+    // same key, it is written aside and renamed to never be seen partially written.
+    let tmp_path = path_with_suffix(&path, &format!(".{}", std::process::id()));
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(&block)?;
+    file.sync_all()?;
+    replace_file(&tmp_path, &path)?;
+    Ok(())
+}
+
+/// Writes the entries of the unnamed and of the named databases into the target environment.
+fn copy_entries<T>(
+    source: &Env<T>,
+    rtxn: &RoTxn<T>,
+    target: &Env<WithoutTls>,
+    names: &[String],
+) -> Result<()> {
+    let mut wtxn = target.write_txn()?;
+
+    // The named databases are created first, their names are keys of the unnamed
+    // database that must not be copied as regular entries.
+    let databases = names.iter().map(|name| Some(name.as_str())).chain([None]);
+    for name in databases {
+        let Some(db) = source.open_database::<Bytes, Bytes>(rtxn, name)? else {
+            continue;
+        };
+
+        if source.change_feed().has_custom_comparator(db.dbi) {
+            return Err(custom_comparator_error(name));
+        }
+
+        let mut flags = 0;
+        unsafe { mdb_result(ffi::mdb_dbi_flags(rtxn.txn.unwrap().as_ptr(), db.dbi, &mut flags))? };
+        let flags = DatabaseFlags::from_bits_truncate(flags);
+        let mut options = target.database_options().types::<Bytes, Bytes>();
+        options.flags(flags);
+        if let Some(name) = name {
+            options.name(name);
+        }
+        let target_db = options.create(&mut wtxn)?;
+
+        // The entries are read in the order of the comparator the database was written with,
+        // it must also be the order of the default comparator of the recreated database.
+        let raw_wtxn = wtxn.txn.txn.unwrap().as_ptr();
+        let compare = |cmp: CompareFn, a: &[u8], b: &[u8]| unsafe {
+            let (a, b) = (ffi::into_val(a), ffi::into_val(b));
+            cmp(raw_wtxn, target_db.dbi, &a, &b).cmp(&0)
+        };
+        let dup_sort = flags.contains(DatabaseFlags::DUP_SORT);
+        let mut previous: Option<(Vec<u8>, Vec<u8>)> = None;
+
+        for result in db.iter(rtxn)? {
+            let (key, data) = result?;
+            if let Some((prev_key, prev_data)) = &previous {
+                let ordered = match compare(ffi::mdb_cmp, prev_key, key) {
+                    Ordering::Less => true,
+                    Ordering::Equal => {
+                        dup_sort && compare(ffi::mdb_dcmp, prev_data, data) == Ordering::Less
+                    }
+                    Ordering::Greater => false,
+                };
+                if !ordered {
+                    return Err(custom_comparator_error(name));
+                }
+            }
+            // The decrypted entries are only valid until the next read.
+            let (prev_key, prev_data) = previous.get_or_insert_with(Default::default);
+            prev_key.clear();
+            prev_key.extend_from_slice(key);
+            prev_data.clear();
+            prev_data.extend_from_slice(data);
+
+            let is_database_name = name.is_none()
+                && std::str::from_utf8(key).is_ok_and(|k| names.iter().any(|n| n == k));
+            if !is_database_name {
+                target_db.put(&mut wtxn, key, data)?;
+            }
+        }
+    }
+
+    wtxn.commit()
+}
+
+fn custom_comparator_error(name: Option<&str>) -> Error {
+    let database = match name {
+        Some(name) => format!("the `{name}` database"),
+        None => "the unnamed database".to_owned(),
+    };
+    let msg = format!(
+        "{database} uses a custom comparator, the databases using a custom comparator \
+        can't be re-encrypted"
+    );
+    io::Error::new(io::ErrorKind::InvalidInput, msg).into()
+}
+
+type CompareFn = unsafe extern "C" fn(
+    *mut ffi::MDB_txn,
+    ffi::MDB_dbi,
+    *const ffi::MDB_val,
+    *const ffi::MDB_val,
+) -> i32;
+
 unsafe impl<T> Send for EncryptedEnv<T> {}
 unsafe impl<T> Sync for EncryptedEnv<T> {}
 
@@ -329,78 +721,194 @@ impl<T> fmt::Debug for EncryptedEnv<T> {
     }
 }
 
-fn encrypt<A: AeadMutInPlace + KeyInit>(
-    key: &[u8],
-    nonce: &[u8],
-    aad: &[u8],
-    plaintext: &[u8],
-    chipertext_out: &mut [u8],
-    auth_out: &mut [u8],
-) -> aead::Result<()> {
-    chipertext_out.copy_from_slice(plaintext);
-    let key: &Key<A> = key.into();
-    let nonce: &Nonce<A> = if nonce.len() >= A::NonceSize::USIZE {
-        nonce[..A::NonceSize::USIZE].into()
-    } else {
-        return Err(aead::Error);
-    };
-    let mut aead = A::new(key);
-    let tag = aead.encrypt_in_place_detached(nonce, aad, chipertext_out)?;
-    auth_out.copy_from_slice(&tag);
-    Ok(())
-}
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+    use std::path::Path;
 
-fn decrypt<A: AeadMutInPlace + KeyInit>(
-    key: &[u8],
-    nonce: &[u8],
-    aad: &[u8],
-    chipher_text: &[u8],
-    output: &mut [u8],
-    auth_in: &[u8],
-) -> aead::Result<()> {
-    output.copy_from_slice(chipher_text);
-    let key: &Key<A> = key.into();
-    let nonce: &Nonce<A> = if nonce.len() >= A::NonceSize::USIZE {
-        nonce[..A::NonceSize::USIZE].into()
-    } else {
-        return Err(aead::Error);
-    };
-    let tag: &Tag<A> = auth_in.into();
-    let mut aead = A::new(key);
-    aead.decrypt_in_place_detached(nonce, aad, output, tag)
-}
+    use aead::Key;
+    use byteorder::NativeEndian;
+    use chacha20poly1305::ChaCha20Poly1305;
 
-/// The wrapper function that is called by LMDB that directly calls
-/// the Rust idiomatic function internally.
-pub(crate) unsafe extern "C" fn encrypt_func_wrapper<E: AeadMutInPlace + KeyInit>(
-    src: *const ffi::MDB_val,
-    dst: *mut ffi::MDB_val,
-    key_ptr: *const ffi::MDB_val,
-    encdec: i32,
-) -> i32 {
-    let result = catch_unwind(|| {
-        let input = std::slice::from_raw_parts((*src).mv_data as *const u8, (*src).mv_size);
-        let output = std::slice::from_raw_parts_mut((*dst).mv_data as *mut u8, (*dst).mv_size);
-        let key = std::slice::from_raw_parts((*key_ptr).mv_data as *const u8, (*key_ptr).mv_size);
-        let iv = std::slice::from_raw_parts(
-            (*key_ptr.offset(1)).mv_data as *const u8,
-            (*key_ptr.offset(1)).mv_size,
-        );
-        let auth = std::slice::from_raw_parts_mut(
-            (*key_ptr.offset(2)).mv_data as *mut u8,
-            (*key_ptr.offset(2)).mv_size,
-        );
-
-        let aad = [];
-        let nonce = iv;
-        let result = if encdec == 1 {
-            encrypt::<E>(key, nonce, &aad, input, output, auth)
-        } else {
-            decrypt::<E>(key, nonce, &aad, input, output, auth)
-        };
+    use crate::types::*;
+    use crate::*;
+
+    enum Reverse {}
+
+    impl Comparator for Reverse {
+        fn compare(a: &[u8], b: &[u8]) -> Ordering {
+            b.cmp(a)
+        }
+    }
+
+    fn open(path: &Path, key: u8) -> EncryptedEnv<WithTls> {
+        let key = Key::<ChaCha20Poly1305>::from([key; 32]);
+        unsafe { EnvOpenOptions::new().max_dbs(4).open_encrypted::<ChaCha20Poly1305, _>(key, path) }
+            .unwrap()
+    }
+
+    fn is_invalid_input(result: Result<std::fs::File>) -> bool {
+        matches!(result, Err(Error::Io(e)) if e.kind() == io::ErrorKind::InvalidInput)
+    }
+
+    fn reencrypt(env: &EncryptedEnv<WithTls>, path: &Path) -> Result<std::fs::File> {
+        let key = Key::<ChaCha20Poly1305>::from([2; 32]);
+        env.copy_to_file_reencrypted::<ChaCha20Poly1305, _>(path, key, CompactionOption::Disabled)
+    }
+
+    #[test]
+    fn reencrypted_copy_keeps_the_flags() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = open(dir.path(), 1);
+        let mut wtxn = env.write_txn().unwrap();
+        let integers = env
+            .database_options()
+            .types::<U32<NativeEndian>, Str>()
+            .key_comparator::<IntegerComparator>()
+            .name("integers")
+            .create(&mut wtxn)
+            .unwrap();
+        for (key, data) in [(1, "one"), (256, "two hundred fifty-six"), (2, "two")] {
+            integers.put(&mut wtxn, &key, data).unwrap();
+        }
+        let duplicates = env
+            .database_options()
+            .types::<Str, Str>()
+            .name("duplicates")
+            .flags(DatabaseFlags::DUP_SORT)
+            .create(&mut wtxn)
+            .unwrap();
+        for data in ["b", "a", "c"] {
+            duplicates.put(&mut wtxn, "key", data).unwrap();
+        }
+        wtxn.commit().unwrap();
+
+        let copy_dir = tempfile::tempdir().unwrap();
+        reencrypt(&env, &copy_dir.path().join("data.mdb")).unwrap();
+        assert!(matches!(
+            reencrypt(&env, &copy_dir.path().join("data.mdb")),
+            Err(Error::Io(e)) if e.kind() == io::ErrorKind::AlreadyExists
+        ));
+
+        let copy = open(copy_dir.path(), 2);
+        let mut rtxn = copy.read_txn().unwrap();
+        let integers = copy
+            .database_options()
+            .types::<U32<NativeEndian>, Str>()
+            .key_comparator::<IntegerComparator>()
+            .name("integers")
+            .open(&rtxn)
+            .unwrap()
+            .unwrap();
+        let keys: Vec<_> = integers.iter(&mut rtxn).unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, [1, 2, 256]);
+        let duplicates =
+            copy.open_database::<Str, Str>(&rtxn, Some("duplicates")).unwrap().unwrap();
+        assert_eq!(duplicates.len(&rtxn).unwrap(), 3);
+    }
+
+    #[test]
+    fn custom_comparators_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = open(dir.path(), 1);
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env
+            .database_options()
+            .types::<Str, Unit>()
+            .key_comparator::<Reverse>()
+            .name("reversed")
+            .create(&mut wtxn)
+            .unwrap();
+        for key in ["a", "b", "c"] {
+            db.put(&mut wtxn, key, &()).unwrap();
+        }
+        wtxn.commit().unwrap();
+
+        // The comparator is known when the database is opened in this program.
+        let copy_path = dir.path().join("copy.mdb");
+        assert!(is_invalid_input(reencrypt(&env, &copy_path)));
+        assert!(!copy_path.exists());
+        assert!(!copy_path.with_extension("mdb.reencrypt").exists());
+        env.prepare_for_closing().wait();
+
+        // Otherwise the entries aren't in the order of the default comparator.
+        let env = open(dir.path(), 1);
+        assert!(is_invalid_input(reencrypt(&env, &copy_path)));
+        assert!(!copy_path.exists());
+    }
+
+    fn put_entries(env: &EncryptedEnv<WithTls>) {
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<U32<NativeEndian>, Str>(&mut wtxn, Some("numbers")).unwrap();
+        for i in 0..100 {
+            db.put(&mut wtxn, &i, "a number").unwrap();
+        }
+        wtxn.commit().unwrap();
+    }
 
-        result.is_err() as i32
-    });
+    fn is_invalid_key(result: Result<EncryptedEnv<WithTls>>) -> bool {
+        matches!(result, Err(Error::InvalidEncryptionKey))
+    }
+
+    fn try_open(path: &Path, key: u8) -> Result<EncryptedEnv<WithTls>> {
+        let key = Key::<ChaCha20Poly1305>::from([key; 32]);
+        unsafe { EnvOpenOptions::new().max_dbs(4).open_encrypted::<ChaCha20Poly1305, _>(key, path) }
+    }
+
+    #[test]
+    fn corrupted_pages_are_not_reported_as_a_wrong_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = open(dir.path(), 1);
+        put_entries(&env);
+        let page_size = env.info().page_size as usize;
+        env.prepare_for_closing().wait();
+        assert!(dir.path().join("data.mdb.key-check").exists());
+
+        assert!(is_invalid_key(try_open(dir.path(), 2)));
+
+        // The two meta pages are kept, every other page is corrupted.
+        let data_path = dir.path().join("data.mdb");
+        let mut data = std::fs::read(&data_path).unwrap();
+        data[2 * page_size..].iter_mut().for_each(|byte| *byte ^= 0xff);
+        std::fs::write(&data_path, data).unwrap();
+
+        let env = open(dir.path(), 1);
+        let rtxn = env.read_txn().unwrap();
+        let result = env.open_database::<Str, Str>(&rtxn, Some("numbers"));
+        assert!(matches!(result, Err(Error::Mdb(MdbError::CryptoFail))));
+    }
+
+    #[test]
+    fn key_check_block_is_written_for_existing_environments() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = open(dir.path(), 1);
+        put_entries(&env);
+        env.prepare_for_closing().wait();
+
+        let key_check_path = dir.path().join("data.mdb.key-check");
+        std::fs::remove_file(&key_check_path).unwrap();
+        assert!(is_invalid_key(try_open(dir.path(), 2)));
+        assert!(!key_check_path.exists());
+
+        open(dir.path(), 1).prepare_for_closing().wait();
+        assert!(key_check_path.exists());
+        assert!(is_invalid_key(try_open(dir.path(), 2)));
+    }
 
-    result.unwrap_or(1)
+    #[test]
+    fn rotated_keys_replace_the_key_check_block() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = open(dir.path(), 1);
+        put_entries(&env);
+
+        let new_key = Key::<ChaCha20Poly1305>::from([2; 32]);
+        let env = unsafe { env.rotate_key::<ChaCha20Poly1305>(new_key) }.unwrap();
+        env.prepare_for_closing().wait();
+
+        assert!(is_invalid_key(try_open(dir.path(), 1)));
+        let env = open(dir.path(), 2);
+        let rtxn = env.read_txn().unwrap();
+        let db = env.open_database::<U32<NativeEndian>, Str>(&rtxn, Some("numbers")).unwrap();
+        assert_eq!(db.unwrap().len(&rtxn).unwrap(), 100);
+    }
 }
@@ -1,28 +1,31 @@
 use std::any::TypeId;
 use std::ffi::CString;
-use std::fs::File;
+use std::fs::{self, File};
+use std::io::Read;
 use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
 use std::ptr::{self, NonNull};
 use std::sync::Arc;
-use std::{fmt, io, mem};
+use std::{fmt, io, mem, panic, result, thread};
 
 use heed_traits::Comparator;
 use synchronoise::SignalEvent;
 
 use super::{
-    custom_key_cmp_wrapper, get_file_fd, metadata_from_fd, DefaultComparator, EnvClosingEvent,
-    EnvInfo, FlagSetMode, IntegerComparator, OPENED_ENV,
+    custom_key_cmp_wrapper, get_file_fd, metadata_from_fd, pipe_writer_into_file,
+    reader_list_wrapper, DefaultComparator, EnvClosingEvent, EnvInfo, FlagSetMode,
+    IntegerComparator, ReaderInfo, OPENED_ENV,
 };
+use crate::change_feed::{ChangeBatch, ChangeFeed};
 use crate::cursor::{MoveOperation, RoCursor};
 use crate::mdb::ffi::{self, MDB_env};
 use crate::mdb::lmdb_error::mdb_result;
 use crate::mdb::lmdb_flags::AllDatabaseFlags;
-#[allow(unused)] // for cargo auto doc links
+use crate::types::{Bytes, DecodeIgnore};
 use crate::EnvOpenOptions;
 use crate::{
-    CompactionOption, Database, DatabaseOpenOptions, EnvFlags, Error, Result, RoTxn, RwTxn,
-    Unspecified, WithoutTls,
+    CompactionOption, Database, DatabaseOpenOptions, EnvFlags, Error, MdbError, Result, RoTxn,
+    RwTxn, TlsUsage, Unspecified, WithoutTls,
 };
 
 /// An environment handle constructed by using [`EnvOpenOptions::open`].
@@ -35,14 +38,29 @@ impl<T> Env<T> {
         env_ptr: NonNull<MDB_env>,
         path: PathBuf,
         signal_event: Arc<SignalEvent>,
+        change_feed: ChangeFeed,
+        options: EnvOpenOptions<WithoutTls>,
     ) -> Self {
-        Env { inner: Arc::new(EnvInner { env_ptr, path, signal_event, _tls_marker: PhantomData }) }
+        Env {
+            inner: Arc::new(EnvInner {
+                env_ptr,
+                path,
+                signal_event,
+                change_feed,
+                options,
+                _tls_marker: PhantomData,
+            }),
+        }
     }
 
     pub(crate) fn env_mut_ptr(&self) -> NonNull<ffi::MDB_env> {
         self.inner.env_ptr
     }
 
+    pub(crate) fn change_feed(&self) -> &ChangeFeed {
+        &self.inner.change_feed
+    }
+
     /// Converts any `Env` into `Env<WithoutTls>`, useful for wrapping
     /// into a `RwTxn` due to the latter always being `WithoutTls`.
     ///
@@ -144,6 +162,9 @@ impl<T> Env<T> {
         let mut raw_info = mem::MaybeUninit::uninit();
         unsafe { ffi::mdb_env_info(self.inner.env_ptr.as_ptr(), raw_info.as_mut_ptr()) };
         let raw_info = unsafe { raw_info.assume_init() };
+        let mut raw_stat = mem::MaybeUninit::uninit();
+        unsafe { ffi::mdb_env_stat(self.inner.env_ptr.as_ptr(), raw_stat.as_mut_ptr()) };
+        let raw_stat = unsafe { raw_stat.assume_init() };
 
         EnvInfo {
             map_addr: raw_info.me_mapaddr,
@@ -152,6 +173,7 @@ impl<T> Env<T> {
             last_txn_id: raw_info.me_last_txnid,
             maximum_number_of_readers: raw_info.me_maxreaders,
             number_of_readers: raw_info.me_numreaders,
+            page_size: raw_stat.ms_psize,
         }
     }
 
@@ -178,7 +200,7 @@ impl<T> Env<T> {
         let dbi = self.raw_open_dbi::<DefaultComparator>(rtxn.txn.unwrap(), None, 0)?;
 
         // We're going to iterate on the unnamed database
-        let mut cursor = RoCursor::new(&rtxn, dbi)?;
+        let mut cursor = RoCursor::new(&rtxn, dbi, None)?;
 
         while let Some((key, _value)) = cursor.move_on_next(MoveOperation::NoDup)? {
             if key.contains(&0) {
@@ -267,9 +289,9 @@ impl<T> Env<T> {
         options.create(wtxn)
     }
 
-    pub(crate) fn raw_init_database<C: Comparator + 'static>(
+    pub(crate) fn raw_init_database<C: Comparator + 'static, U>(
         &self,
-        raw_txn: NonNull<ffi::MDB_txn>,
+        txn: &RoTxn<U>,
         name: Option<&str>,
         mut flags: AllDatabaseFlags,
     ) -> Result<u32> {
@@ -277,8 +299,18 @@ impl<T> Env<T> {
             flags.insert(AllDatabaseFlags::INTEGER_KEY);
         }
 
-        match self.raw_open_dbi::<C>(raw_txn, name, flags.bits()) {
-            Ok(dbi) => Ok(dbi),
+        match self.raw_open_dbi::<C>(txn.txn.unwrap(), name, flags.bits()) {
+            Ok(dbi) => {
+                let custom_comparator = TypeId::of::<C>() != TypeId::of::<DefaultComparator>()
+                    && TypeId::of::<C>() != TypeId::of::<IntegerComparator>();
+                let first_opening =
+                    self.inner.change_feed.register_database(dbi, name, custom_comparator);
+                // The handle of the unnamed database is never freed.
+                if first_opening && name.is_some() {
+                    txn.database_opened(dbi);
+                }
+                Ok(dbi)
+            }
             Err(e) => Err(e.into()),
         }
     }
@@ -434,6 +466,89 @@ impl<T> Env<T> {
         Ok(())
     }
 
+    /// Stream a consistent copy of this LMDB environment into the given writer, with options.
+    ///
+    /// LMDB writes the copy into a pipe from a helper thread while the calling thread
+    /// forwards it to the writer. This way the copy can be sent through a socket,
+    /// a compressor or a hasher without being staged on disk first.
+    ///
+    /// Returns the number of bytes written into the writer.
+    ///
+    /// ```
+    /// use heed::{CompactionOption, EnvOpenOptions};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir = tempfile::tempdir()?;
+    /// let env = unsafe { EnvOpenOptions::new().open(dir.path())? };
+    ///
+    /// let mut backup = Vec::new();
+    /// let written = env.copy_to_writer(&mut backup, CompactionOption::Enabled)?;
+    /// assert_eq!(written, backup.len() as u64);
+    /// # Ok(()) }
+    /// ```
+    pub fn copy_to_writer<W: io::Write>(&self, writer: W, option: CompactionOption) -> Result<u64> {
+        self.copy_to_writer_with_progress(writer, option, |_| ())
+    }
+
+    /// Stream a consistent copy of this LMDB environment into the given writer, with options,
+    /// calling `progress` with the total number of bytes written so far after every write.
+    ///
+    /// See [`Self::copy_to_writer`] for more details.
+    pub fn copy_to_writer_with_progress<W, F>(
+        &self,
+        mut writer: W,
+        option: CompactionOption,
+        mut progress: F,
+    ) -> Result<u64>
+    where
+        W: io::Write,
+        F: FnMut(u64),
+    {
+        let (mut reader, pipe_writer) = io::pipe()?;
+        let pipe_writer = pipe_writer_into_file(pipe_writer);
+
+        thread::scope(|s| {
+            let copy = s.spawn(move || {
+                // The write end of the pipe is closed when the copy ends,
+                // this is how the reading side knows it reached the end.
+                let fd = get_file_fd(&pipe_writer);
+                unsafe { self.copy_to_fd(fd, option) }
+            });
+
+            let mut buffer = vec![0; 64 * 1024];
+            let mut written = 0;
+            let forwarded = loop {
+                let count = match reader.read(&mut buffer) {
+                    Ok(0) => break Ok(()),
+                    Ok(count) => count,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => break Err(e),
+                };
+                if let Err(e) = writer.write_all(&buffer[..count]) {
+                    break Err(e);
+                }
+                written += count as u64;
+                progress(written);
+            };
+
+            // Writing into a pipe whose read end is closed raises a SIGPIPE that kills the
+            // processes not ignoring it, the rest of the copy is drained when forwarding failed.
+            if forwarded.is_err() {
+                let _ = io::copy(&mut reader, &mut io::sink());
+            }
+            drop(reader);
+            let copied = match copy.join() {
+                Ok(result) => result,
+                Err(payload) => panic::resume_unwind(payload),
+            };
+
+            forwarded?;
+            copied?;
+            writer.flush()?;
+            Ok(written)
+        })
+    }
+
     /// Flush the data buffers to disk.
     pub fn force_sync(&self) -> Result<()> {
         unsafe { mdb_result(ffi::mdb_env_sync(self.inner.env_ptr.as_ptr(), 1))? }
@@ -445,6 +560,53 @@ impl<T> Env<T> {
         &self.inner.path
     }
 
+    /// The path of the data file, the environment path itself when opened with
+    /// [`EnvFlags::NO_SUB_DIR`].
+    pub(crate) fn data_file_path(&self) -> PathBuf {
+        data_file_path(&self.inner.path, self.inner.options.env_flags())
+    }
+
+    /// The options binding context into the pages, if the environment is encrypted.
+    #[cfg(master3)]
+    pub(crate) fn encryption_options(&self) -> crate::EncryptionOptions {
+        self.inner.options.encryption_options()
+    }
+
+    /// Returns the names of the named databases of this environment.
+    ///
+    /// The named databases are stored as keys of the unnamed one,
+    /// but not every key of the unnamed database is a database.
+    pub fn database_names(&self, rtxn: &RoTxn<T>) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        if let Some(main) = self.open_database::<Bytes, DecodeIgnore>(rtxn, None)? {
+            for result in main.iter(rtxn)? {
+                let (key, ()) = result?;
+                let Some(name) = std::str::from_utf8(key).ok().filter(|n| !n.contains('\0')) else {
+                    continue;
+                };
+                // The key is copied before opening the database, as the pages
+                // read by the iterator may be overwritten in encrypted environments.
+                let name = name.to_owned();
+                match self.open_database::<Bytes, Bytes>(rtxn, Some(&name)) {
+                    Ok(Some(_)) => names.push(name),
+                    Ok(None) | Err(Error::Mdb(MdbError::Incompatible)) => (),
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Ok(names)
+    }
+
+    /// Returns a receiver of the [`ChangeBatch`]es committed after this call.
+    ///
+    /// Nothing is ever sent unless the environment has been opened with
+    /// [`EnvOpenOptions::change_capture`] enabled. A batch is sent for every
+    /// top-level write transaction that has been successfully committed and
+    /// that performed at least one write. Dropping the receiver unsubscribes it.
+    pub fn subscribe(&self) -> std::sync::mpsc::Receiver<ChangeBatch> {
+        self.inner.change_feed.subscribe()
+    }
+
     /// Returns an `EnvClosingEvent` that can be used to wait for the closing event,
     /// multiple threads can wait on this event.
     ///
@@ -465,6 +627,20 @@ impl<T> Env<T> {
         Ok(dead as usize)
     }
 
+    /// Returns the entries of the reader lock table, one per reader slot in use.
+    pub fn readers(&self) -> Result<Vec<ReaderInfo>> {
+        let mut readers = Vec::new();
+        let ctx = &mut readers as *mut Vec<ReaderInfo> as *mut libc::c_void;
+        let rc = unsafe {
+            ffi::mdb_reader_list(self.inner.env_ptr.as_ptr(), Some(reader_list_wrapper), ctx)
+        };
+        // The function returns a negative value when the message function fails.
+        if rc < 0 {
+            return Err(io::Error::other("could not list the readers").into());
+        }
+        Ok(readers)
+    }
+
     /// Resize the memory map to a new size.
     ///
     /// # Safety
@@ -494,6 +670,128 @@ impl<T> Env<T> {
     }
 }
 
+impl<T: TlsUsage> Env<T> {
+    /// Compacts the environment in place and reopens it with the same [`EnvOpenOptions`].
+    ///
+    /// A compacted copy of the data file is written next to it, then the environment is
+    /// closed and the copy atomically renamed over the original data file before reopening it.
+    /// A copy left by an interrupted compaction is removed first.
+    ///
+    /// This method waits for the environment to be closed, i.e. for every other clone of
+    /// this `Env` and every transaction to be dropped. It will never return if the calling
+    /// thread still holds one of them.
+    ///
+    /// When the compaction fails, the copy is removed and the environment is given back by
+    /// [`CompactionError::into_env`], reopened with the same options if it was already closed.
+    ///
+    /// LMDB restarts the transaction IDs after a compacting copy, the `txn_id` of the
+    /// [`ChangeBatch`]es is therefore not monotonic across a compaction. The entries of the
+    /// [`replication`](crate::replication) log are numbered independently and are not affected.
+    ///
+    /// ```
+    /// use heed::EnvOpenOptions;
+    /// use heed::types::*;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir = tempfile::tempdir()?;
+    /// let env = unsafe { EnvOpenOptions::new().map_size(10 * 1024 * 1024).open(dir.path())? };
+    ///
+    /// let mut wtxn = env.write_txn()?;
+    /// let db = env.create_database::<U32<byteorder::BigEndian>, Bytes>(&mut wtxn, None)?;
+    /// for i in 0..1000 {
+    ///     db.put(&mut wtxn, &i, &[0; 512])?;
+    /// }
+    /// db.delete_range(&mut wtxn, &(0..990))?;
+    /// wtxn.commit()?;
+    ///
+    /// let before = env.real_disk_size()?;
+    /// let env = unsafe { env.compact()? };
+    /// assert!(env.real_disk_size()? < before);
+    ///
+    /// let rtxn = env.read_txn()?;
+    /// let db = env.open_database::<U32<byteorder::BigEndian>, Bytes>(&rtxn, None)?.unwrap();
+    /// assert_eq!(db.len(&rtxn)?, 10);
+    /// # Ok(()) }
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// The environment is reopened and the same precautions as [`EnvOpenOptions::open`] apply.
+    /// Moreover, no other process must have this environment opened, as it would keep using
+    /// the replaced data file.
+    pub unsafe fn compact(self) -> result::Result<Env<T>, CompactionError<T>> {
+        let options = self.open_options();
+        let path = self.inner.path.clone();
+        let data_path = self.data_file_path();
+        let copy_path = path_with_suffix(&data_path, ".compact");
+
+        let copied = remove_file_if_exists(&copy_path).map_err(Error::from).and_then(|()| {
+            let copy = self.copy_to_file(&copy_path, CompactionOption::Enabled)?;
+            copy.sync_all().map_err(Error::from)
+        });
+        if let Err(error) = copied {
+            let _ = remove_file_if_exists(&copy_path);
+            return Err(CompactionError { env: Some(self), error });
+        }
+
+        self.prepare_for_closing().wait();
+        if let Err(error) = replace_file(&copy_path, &data_path) {
+            // The original data file is left untouched when the rename fails.
+            let _ = remove_file_if_exists(&copy_path);
+            let env = unsafe { options.open(&path) }.ok();
+            return Err(CompactionError { env, error: error.into() });
+        }
+
+        unsafe { options.open(path) }.map_err(|error| CompactionError { env: None, error })
+    }
+
+    /// The options this environment was opened with.
+    pub(crate) fn open_options(&self) -> EnvOpenOptions<T> {
+        self.inner.options.clone().with_tls_usage()
+    }
+}
+
+/// The error returned by [`Env::compact`], giving the environment back when possible.
+pub struct CompactionError<T> {
+    env: Option<Env<T>>,
+    error: Error,
+}
+
+impl<T> CompactionError<T> {
+    /// Returns the error that made the compaction fail.
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+
+    /// Returns the environment, not compacted, or `None` if it could not be reopened.
+    pub fn into_env(self) -> Option<Env<T>> {
+        self.env
+    }
+}
+
+impl<T> fmt::Debug for CompactionError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CompactionError")
+            .field("env", &self.env)
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+impl<T> fmt::Display for CompactionError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "failed to compact the environment: {}", self.error)
+    }
+}
+
+impl<T> std::error::Error for CompactionError<T> {}
+
+impl<T> From<CompactionError<T>> for Error {
+    fn from(error: CompactionError<T>) -> Error {
+        error.error
+    }
+}
+
 impl<T> Clone for Env<T> {
     fn clone(&self) -> Self {
         Env { inner: self.inner.clone() }
@@ -512,6 +810,9 @@ impl<T> fmt::Debug for Env<T> {
 pub(crate) struct EnvInner<T> {
     env_ptr: NonNull<MDB_env>,
     signal_event: Arc<SignalEvent>,
+    change_feed: ChangeFeed,
+    /// The options used to open the environment, to be able to reopen it.
+    options: EnvOpenOptions<WithoutTls>,
     pub(crate) path: PathBuf,
     _tls_marker: PhantomData<T>,
 }
@@ -529,14 +830,49 @@ impl<T> Drop for EnvInner<T> {
     }
 }
 
+/// The path of the data file of an environment opened at this path with these flags.
+pub(crate) fn data_file_path(path: &Path, flags: EnvFlags) -> PathBuf {
+    if flags.contains(EnvFlags::NO_SUB_DIR) {
+        path.to_path_buf()
+    } else {
+        path.join("data.mdb")
+    }
+}
+
+/// Returns the path with the suffix appended to its file name.
+pub(crate) fn path_with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// Removes a file, ignoring it if it doesn't exist.
+pub(crate) fn remove_file_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Atomically renames the file over the destination and makes the rename durable.
+pub(crate) fn replace_file(from: &Path, to: &Path) -> io::Result<()> {
+    fs::rename(from, to)?;
+    #[cfg(unix)]
+    if let Some(parent) = to.parent() {
+        // Makes sure the rename itself is durable.
+        File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::ErrorKind;
     use std::time::Duration;
-    use std::{fs, thread};
+    use std::{fs, io, thread};
 
     use crate::types::*;
-    use crate::{env_closing_event, EnvOpenOptions, Error};
+    use crate::{env_closing_event, CompactionOption, EnvOpenOptions, Error};
 
     #[test]
     fn close_env() {
@@ -849,4 +1185,160 @@ mod tests {
             assert_eq!(maxkeysize, 511);
         }
     }
+
+    #[test]
+    fn copy_to_writer_can_be_reopened() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().map_size(10 * 1024 * 1024).open(dir.path()) };
+        let env = env.unwrap();
+        put_numbers(&env, 0..100);
+
+        let mut progress = Vec::new();
+        let mut backup = Vec::new();
+        let written = env
+            .copy_to_writer_with_progress(&mut backup, CompactionOption::Enabled, |n| {
+                progress.push(n)
+            })
+            .unwrap();
+        assert_eq!(written, backup.len() as u64);
+        assert!(progress.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(progress.last(), Some(&written));
+
+        let copy_dir = tempfile::tempdir().unwrap();
+        fs::write(copy_dir.path().join("data.mdb"), &backup).unwrap();
+        let copy = unsafe { EnvOpenOptions::new().open(copy_dir.path()).unwrap() };
+        let rtxn = copy.read_txn().unwrap();
+        let db = copy.open_database::<U32<byteorder::BigEndian>, Bytes>(&rtxn, None).unwrap();
+        assert_eq!(db.unwrap().len(&rtxn).unwrap(), 100);
+    }
+
+    #[test]
+    fn copy_to_writer_reports_writer_errors() {
+        /// A writer that fails once it has been given more than `capacity` bytes.
+        struct Limited {
+            capacity: usize,
+        }
+
+        impl io::Write for Limited {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                match self.capacity.checked_sub(buf.len()) {
+                    Some(capacity) => {
+                        self.capacity = capacity;
+                        Ok(buf.len())
+                    }
+                    None => Err(io::Error::new(ErrorKind::StorageFull, "writer is full")),
+                }
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().map_size(10 * 1024 * 1024).open(dir.path()) };
+        let env = env.unwrap();
+        put_numbers(&env, 0..1000);
+
+        let result = env.copy_to_writer(Limited { capacity: 4096 }, CompactionOption::Disabled);
+        assert!(matches!(result, Err(Error::Io(e)) if e.kind() == ErrorKind::StorageFull));
+
+        // The copy is drained instead of failing on the closed pipe, the environment is usable.
+        let mut backup = Vec::new();
+        env.copy_to_writer(&mut backup, CompactionOption::Disabled).unwrap();
+        assert!(!backup.is_empty());
+    }
+
+    fn put_numbers(env: &crate::Env<crate::WithTls>, numbers: std::ops::Range<u32>) {
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<U32<byteorder::BigEndian>, Bytes>(&mut wtxn, None).unwrap();
+        for i in numbers {
+            db.put(&mut wtxn, &i, &[0; 512]).unwrap();
+        }
+        wtxn.commit().unwrap();
+    }
+
+    #[test]
+    fn compact_removes_a_leftover_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().map_size(10 * 1024 * 1024).open(dir.path()) };
+        let env = env.unwrap();
+        put_numbers(&env, 0..100);
+
+        let copy_path = dir.path().join("data.mdb.compact");
+        fs::write(&copy_path, b"interrupted compaction").unwrap();
+
+        let env = unsafe { env.compact() }.unwrap();
+        assert!(!copy_path.exists());
+        let rtxn = env.read_txn().unwrap();
+        let db = env.open_database::<U32<byteorder::BigEndian>, Bytes>(&rtxn, None).unwrap();
+        assert_eq!(db.unwrap().len(&rtxn).unwrap(), 100);
+    }
+
+    #[test]
+    fn failed_compaction_gives_the_env_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().map_size(10 * 1024 * 1024).open(dir.path()) };
+        let env = env.unwrap();
+        put_numbers(&env, 0..100);
+
+        // A directory can't be removed like a leftover copy, the compaction fails.
+        let copy_path = dir.path().join("data.mdb.compact");
+        fs::create_dir(&copy_path).unwrap();
+        fs::write(copy_path.join("file"), b"not empty").unwrap();
+
+        let error = unsafe { env.compact() }.unwrap_err();
+        assert!(matches!(error.error(), Error::Io(_)));
+        let env = error.into_env().unwrap();
+        let rtxn = env.read_txn().unwrap();
+        let db = env.open_database::<U32<byteorder::BigEndian>, Bytes>(&rtxn, None).unwrap();
+        assert_eq!(db.unwrap().len(&rtxn).unwrap(), 100);
+    }
+
+    #[test]
+    fn compaction_keeps_the_replication_log_sequence() {
+        use crate::replication::read_log;
+
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe {
+            EnvOpenOptions::new().max_dbs(10).replication_log(true).open(dir.path()).unwrap()
+        };
+        put_numbers(&env, 0..10);
+        put_numbers(&env, 10..20);
+
+        let env = unsafe { env.compact() }.unwrap();
+        put_numbers(&env, 20..30);
+
+        let rtxn = env.read_txn().unwrap();
+        let entries = read_log(&env, &rtxn, 0, 100).unwrap();
+        let sequences: Vec<_> = entries.iter().map(|entry| entry.sequence).collect();
+        assert_eq!(sequences, [1, 2, 3]);
+    }
+
+    #[test]
+    fn readers_list_the_reader_slots() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().open(dir.path()).unwrap() };
+        put_numbers(&env, 0..10);
+
+        let rtxn = env.read_txn().unwrap();
+        let readers = env.readers().unwrap();
+        assert_eq!(readers.len(), 1);
+        assert_eq!(readers[0].pid, std::process::id() as i32);
+        assert_eq!(readers[0].txn_id, Some(env.info().last_txn_id as u64));
+        drop(rtxn);
+    }
+
+    #[test]
+    fn database_names_list_the_named_databases() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().max_dbs(2).open(dir.path()).unwrap() };
+        let mut wtxn = env.write_txn().unwrap();
+        env.create_database::<Str, Str>(&mut wtxn, Some("first")).unwrap();
+        env.create_database::<Str, Str>(&mut wtxn, Some("second")).unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = env.read_txn().unwrap();
+        assert_eq!(env.database_names(&rtxn).unwrap(), ["first", "second"]);
+    }
 }
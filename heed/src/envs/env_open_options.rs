@@ -11,13 +11,24 @@ use std::sync::Arc;
 use std::{io, ptr};
 
 #[cfg(master3)]
-use aead::{generic_array::typenum::Unsigned, AeadCore, AeadMutInPlace, Key, KeyInit};
+use aead::{AeadMutInPlace, Key, KeyInit};
 use synchronoise::SignalEvent;
 
 #[cfg(master3)]
-use super::encrypted_env::{encrypt_func_wrapper, EncryptedEnv};
+use super::encrypted_env::{check_encryption_key, EncryptedEnv};
+#[cfg(master3)]
+#[cfg(feature = "password")]
+use super::env::data_file_path;
 use super::env::Env;
+#[cfg(master3)]
+#[cfg(feature = "password")]
+use super::kdf::{KdfHeader, KdfParams};
+#[cfg(master3)]
+use super::page_cipher::{cipher_key, encrypt_func_wrapper, EncryptionOptions, PageCipher};
 use super::{canonicalize_path, OPENED_ENV};
+use crate::change_feed::ChangeFeed;
+#[cfg(master3)]
+use crate::checksum::{checksum_func_wrapper, PageChecksum};
 #[cfg(windows)]
 use crate::envs::OsStrExtLmdb as _;
 use crate::mdb::error::mdb_result;
@@ -25,6 +36,13 @@ use crate::mdb::ffi;
 use crate::txn::{TlsUsage, WithTls, WithoutTls};
 use crate::{EnvFlags, Error, Result};
 
+/// The smallest page size accepted by LMDB.
+#[cfg(master3)]
+const MIN_PAGE_SIZE: u32 = 512;
+/// The largest page size accepted by LMDB.
+#[cfg(master3)]
+const MAX_PAGE_SIZE: u32 = 64 * 1024;
+
 /// Options and flags which can be used to configure how an environment is opened.
 #[derive(Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -33,6 +51,21 @@ pub struct EnvOpenOptions<T: TlsUsage> {
     max_readers: Option<u32>,
     max_dbs: Option<u32>,
     flags: EnvFlags,
+    #[cfg_attr(feature = "serde", serde(default))]
+    change_capture: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    replication_log: bool,
+    #[cfg(master3)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    page_size: Option<u32>,
+    #[cfg(master3)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    encryption: EncryptionOptions,
+    /// The page checksum, only set in the options kept by an environment opened
+    /// with [`Self::open_checksummed`] to be reopened the same way.
+    #[cfg(master3)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    checksum: Option<(ffi::MDB_sum_func, u32)>,
     _tls_marker: PhantomData<T>,
 }
 
@@ -44,6 +77,14 @@ impl EnvOpenOptions<WithTls> {
             max_readers: None,
             max_dbs: None,
             flags: EnvFlags::empty(),
+            change_capture: false,
+            replication_log: false,
+            #[cfg(master3)]
+            page_size: None,
+            #[cfg(master3)]
+            encryption: EncryptionOptions::default(),
+            #[cfg(master3)]
+            checksum: None,
             _tls_marker: PhantomData,
         }
     }
@@ -82,8 +123,7 @@ impl<T: TlsUsage> EnvOpenOptions<T> {
     /// # Ok(()) }
     /// ```
     pub fn read_txn_with_tls(self) -> EnvOpenOptions<WithTls> {
-        let Self { map_size, max_readers, max_dbs, flags, _tls_marker: _ } = self;
-        EnvOpenOptions { map_size, max_readers, max_dbs, flags, _tls_marker: PhantomData }
+        self.with_tls_usage()
     }
 
     /// Make the read transactions `Send` by specifying they will
@@ -127,8 +167,52 @@ impl<T: TlsUsage> EnvOpenOptions<T> {
     /// # Ok(()) }
     /// ```
     pub fn read_txn_without_tls(self) -> EnvOpenOptions<WithoutTls> {
-        let Self { map_size, max_readers, max_dbs, flags, _tls_marker: _ } = self;
-        EnvOpenOptions { map_size, max_readers, max_dbs, flags, _tls_marker: PhantomData }
+        self.with_tls_usage()
+    }
+
+    /// Changes the TLS usage marker, keeping all the other options.
+    pub(crate) fn with_tls_usage<U: TlsUsage>(self) -> EnvOpenOptions<U> {
+        let Self {
+            map_size,
+            max_readers,
+            max_dbs,
+            flags,
+            change_capture,
+            replication_log,
+            #[cfg(master3)]
+            page_size,
+            #[cfg(master3)]
+            encryption,
+            #[cfg(master3)]
+            checksum,
+            _tls_marker: _,
+        } = self;
+        EnvOpenOptions {
+            map_size,
+            max_readers,
+            max_dbs,
+            flags,
+            change_capture,
+            replication_log,
+            #[cfg(master3)]
+            page_size,
+            #[cfg(master3)]
+            encryption,
+            #[cfg(master3)]
+            checksum,
+            _tls_marker: PhantomData,
+        }
+    }
+
+    /// Returns the flags the environment is opened with.
+    pub(crate) fn env_flags(&self) -> EnvFlags {
+        self.flags
+    }
+
+    /// The options binding context into the encrypted pages.
+    #[cfg(master3)]
+    pub(crate) fn encryption_options(&self) -> EncryptionOptions {
+        self.encryption
     }
 
     /// Set the size of the memory map to use for this environment.
@@ -139,6 +223,39 @@ impl<T: TlsUsage> EnvOpenOptions<T> {
         self
     }
 
+    /// Set the size of the pages of the environment, in bytes.
+    ///
+    /// It defaults to the OS page size and must be a power of two between 512 bytes and
+    /// 64 KiB. Larger pages store large values in fewer overflow pages. The page size is
+    /// only used when the environment is created, an existing environment keeps its own.
+    ///
+    /// ```
+    /// use heed3::EnvOpenOptions;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir = tempfile::tempdir()?;
+    /// let env = unsafe { EnvOpenOptions::new().page_size(16 * 1024).open(dir.path())? };
+    /// assert_eq!(env.info().page_size, 16 * 1024);
+    /// # Ok(()) }
+    /// ```
+    #[cfg(master3)]
+    pub fn page_size(&mut self, size: u32) -> &mut Self {
+        self.page_size = Some(size);
+        self
+    }
+
+    /// Map the data file in chunks, on demand, rather than as a single memory map.
+    ///
+    /// It avoids reserving the whole [`Self::map_size`] of address space up front, which is
+    /// useful on platforms with a limited address space. The encrypted environments always
+    /// use it. Unlike the other [`EnvFlags`], it is safe to set as it only changes how the
+    /// file is mapped in this process.
+    #[cfg(master3)]
+    pub fn remap_chunks(&mut self, enable: bool) -> &mut Self {
+        self.flags.set(EnvFlags::REMAP_CHUNKS, enable);
+        self
+    }
+
     /// Set the maximum number of threads/reader slots for the environment.
     pub fn max_readers(&mut self, readers: u32) -> &mut Self {
         self.max_readers = Some(readers);
@@ -151,6 +268,94 @@ impl<T: TlsUsage> EnvOpenOptions<T> {
         self
     }
 
+    /// Record the raw operations performed by the write transactions and send
+    /// them, once committed, to the receivers returned by [`Env::subscribe`].
+    ///
+    /// The writes done with the cursor methods (`put_current`, `del_current`...) are captured
+    /// as the [`ChangeOp`](crate::ChangeOp)s they are equivalent to.
+    ///
+    /// ```
+    /// use std::fs;
+    /// use std::path::Path;
+    /// use heed::{ChangeOp, EnvOpenOptions, Database};
+    /// use heed::types::*;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir = tempfile::tempdir()?;
+    /// let env = unsafe {
+    ///     EnvOpenOptions::new()
+    ///         .map_size(10 * 1024 * 1024) // 10MB
+    ///         .max_dbs(3)
+    ///         .change_capture(true)
+    ///         .open(dir.path())?
+    /// };
+    /// let changes = env.subscribe();
+    ///
+    /// let mut wtxn = env.write_txn()?;
+    /// let db: Database<Str, U32<byteorder::NativeEndian>> = env.create_database(&mut wtxn, Some("counts"))?;
+    /// db.put(&mut wtxn, "hello", &42)?;
+    /// db.delete(&mut wtxn, "hello")?;
+    /// wtxn.commit()?;
+    ///
+    /// let batch = changes.try_recv()?;
+    /// assert_eq!(batch.ops, vec![
+    ///     ChangeOp::Put {
+    ///         database: Some("counts".to_string()),
+    ///         key: b"hello".to_vec(),
+    ///         data: 42u32.to_ne_bytes().to_vec(),
+    ///     },
+    ///     ChangeOp::Delete { database: Some("counts".to_string()), key: b"hello".to_vec(), data: None },
+    /// ]);
+    /// # Ok(()) }
+    /// ```
+    pub fn change_capture(&mut self, enable: bool) -> &mut Self {
+        self.change_capture = enable;
+        self
+    }
+
+    /// Store the raw operations of every committed write transaction in an internal
+    /// log database, to replay them in other environments.
+    ///
+    /// The log is stored in a named database, make sure to count it in the [`Self::max_dbs`].
+    /// See the [`replication`](crate::replication) module for more details.
+    pub fn replication_log(&mut self, enable: bool) -> &mut Self {
+        self.replication_log = enable;
+        self
+    }
+
+    /// Bind some context into the associated data of the encrypted pages,
+    /// see [`EncryptionOptions`]. It is only used by the encrypted environments.
+    ///
+    /// ```
+    /// use chacha20poly1305::{ChaCha20Poly1305, Key};
+    /// use heed3::{EncryptionOptions, EnvOpenOptions, Error};
+    /// use heed3::types::*;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir = tempfile::tempdir()?;
+    /// let key = Key::from([1; 32]);
+    ///
+    /// let mut options = EnvOpenOptions::new();
+    /// options.encryption(EncryptionOptions { environment_id: Some([7; 16]) });
+    /// let env = unsafe { options.open_encrypted::<ChaCha20Poly1305, _>(key, dir.path())? };
+    /// let mut wtxn = env.write_txn()?;
+    /// let db = env.create_database::<Str, Str>(&mut wtxn, None)?;
+    /// db.put(&mut wtxn, "hello", "world")?;
+    /// wtxn.commit()?;
+    /// env.prepare_for_closing().wait();
+    ///
+    /// // The pages cannot be authenticated as part of another environment.
+    /// options.encryption(EncryptionOptions { environment_id: Some([8; 16]) });
+    /// let result = unsafe { options.open_encrypted::<ChaCha20Poly1305, _>(key, dir.path()) };
+    /// assert!(matches!(result, Err(Error::InvalidEncryptionKey)));
+    /// # Ok(()) }
+    /// ```
+    #[cfg(master3)]
+    pub fn encryption(&mut self, options: EncryptionOptions) -> &mut Self {
+        self.encryption = options;
+        self
+    }
+
     /// Set one or [more LMDB flags](http://www.lmdb.tech/doc/group__mdb__env.html).
     ///
     /// ```
@@ -237,9 +442,66 @@ impl<T: TlsUsage> EnvOpenOptions<T> {
             path.as_ref(),
             #[cfg(master3)]
             None,
+            #[cfg(master3)]
+            self.checksum,
         )
     }
 
+    /// Opens an existing environment, read-only, at the snapshot preceding the last committed
+    /// write transaction.
+    ///
+    /// LMDB keeps the two last snapshots of an environment. This can be used to recover the
+    /// last known good state after a bad commit or a torn write, by copying it to a new file
+    /// with [`Env::copy_to_file`]. The environment is opened read-only to never overwrite the
+    /// latest snapshot, it must not be opened elsewhere in this program.
+    ///
+    /// ```
+    /// use heed::types::*;
+    /// use heed::{CompactionOption, EnvFlags, EnvOpenOptions};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir = tempfile::tempdir()?;
+    /// let env = unsafe { EnvOpenOptions::new().open(dir.path())? };
+    /// let db = {
+    ///     let mut wtxn = env.write_txn()?;
+    ///     let db = env.create_database::<Str, Str>(&mut wtxn, None)?;
+    ///     db.put(&mut wtxn, "state", "good")?;
+    ///     wtxn.commit()?;
+    ///     db
+    /// };
+    ///
+    /// let mut wtxn = env.write_txn()?;
+    /// db.put(&mut wtxn, "state", "bad")?;
+    /// wtxn.commit()?;
+    /// env.prepare_for_closing().wait();
+    ///
+    /// let previous = unsafe { EnvOpenOptions::new().open_previous_snapshot(dir.path())? };
+    /// let rtxn = previous.read_txn()?;
+    /// let db = previous.open_database::<Str, Str>(&rtxn, None)?.unwrap();
+    /// assert_eq!(db.get(&rtxn, "state")?, Some("good"));
+    /// drop(rtxn);
+    ///
+    /// let recovered_path = dir.path().join("recovered.mdb");
+    /// previous.copy_to_file(&recovered_path, CompactionOption::Enabled)?;
+    /// previous.prepare_for_closing().wait();
+    ///
+    /// let mut options = EnvOpenOptions::new();
+    /// let recovered = unsafe { options.flags(EnvFlags::NO_SUB_DIR).open(&recovered_path)? };
+    /// let rtxn = recovered.read_txn()?;
+    /// let db = recovered.open_database::<Str, Str>(&rtxn, None)?.unwrap();
+    /// assert_eq!(db.get(&rtxn, "state")?, Some("good"));
+    /// # Ok(()) }
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// The same as [`Self::open`].
+    pub unsafe fn open_previous_snapshot<P: AsRef<Path>>(&self, path: P) -> Result<Env<T>> {
+        let mut options = self.clone();
+        options.flags |= EnvFlags::PREV_SNAPSHOT | EnvFlags::READ_ONLY;
+        options.open(path)
+    }
+
     /// Open an encrypted-at-rest environment that will be located at the specified path.
     ///
     /// # Safety
@@ -320,6 +582,41 @@ impl<T: TlsUsage> EnvOpenOptions<T> {
     /// # Ok(()) }
     /// ```
     ///
+    /// # Example With the Wrong Key
+    ///
+    /// The key is checked when the environment is opened against a small block encrypted with
+    /// it, stored in a `.key-check` file next to the data file, opening it with another key
+    /// returns an [`Error::InvalidEncryptionKey`]. Once the key is verified, a page that cannot
+    /// be decrypted is corrupted and reported as an [`MdbError::CryptoFail`](crate::MdbError::CryptoFail).
+    /// An empty environment has nothing to decrypt and accepts any key.
+    ///
+    /// ```
+    /// use chacha20poly1305::{ChaCha20Poly1305, Key};
+    /// use heed3::types::*;
+    /// use heed3::{EnvOpenOptions, Error};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir = tempfile::tempdir()?;
+    /// let mut options = EnvOpenOptions::new();
+    /// options.max_dbs(1);
+    ///
+    /// let key = Key::from([1; 32]);
+    /// let env = unsafe { options.open_encrypted::<ChaCha20Poly1305, _>(key, dir.path())? };
+    /// let mut wtxn = env.write_txn()?;
+    /// let db = env.create_database::<Str, Str>(&mut wtxn, Some("secrets"))?;
+    /// db.put(&mut wtxn, "hello", "world")?;
+    /// wtxn.commit()?;
+    /// env.prepare_for_closing().wait();
+    ///
+    /// let wrong_key = Key::from([2; 32]);
+    /// let result = unsafe { options.open_encrypted::<ChaCha20Poly1305, _>(wrong_key, dir.path()) };
+    /// assert!(matches!(result, Err(Error::InvalidEncryptionKey)));
+    ///
+    /// // The environment can be opened again with the right key.
+    /// let _env = unsafe { options.open_encrypted::<ChaCha20Poly1305, _>(key, dir.path())? };
+    /// # Ok(()) }
+    /// ```
+    ///
     /// # Example Showing limitations
     ///
     /// At the end of this example file you can see that we can not longer use the `val1`
@@ -389,19 +686,181 @@ impl<T: TlsUsage> EnvOpenOptions<T> {
     where
         E: AeadMutInPlace + KeyInit,
         P: AsRef<Path>,
+    {
+        self.open_with_page_cipher::<E, _>(&key, path)
+    }
+
+    /// Opens an encrypted-at-rest environment using a custom [`PageCipher`].
+    ///
+    /// This is what [`Self::open_encrypted`] does with a RustCrypto AEAD algorithm, the `key`
+    /// is copied by LMDB and given to every call of the cipher. The key is checked the same way.
+    ///
+    /// ## Safety
+    ///
+    /// The same as [`Self::open_encrypted`].
+    #[cfg(master3)]
+    pub unsafe fn open_with_page_cipher<C, P>(&self, key: &[u8], path: P) -> Result<EncryptedEnv<T>>
+    where
+        C: PageCipher,
+        P: AsRef<Path>,
+    {
+        let key = cipher_key(key, &self.encryption);
+        let inner = self.raw_open_with_encryption(
+            path.as_ref(),
+            Some((Some(encrypt_func_wrapper::<C>), &key, C::TAG_SIZE)),
+            None,
+        )?;
+        // The environment is closed when dropped on error and can be opened again.
+        check_encryption_key::<C, _>(&inner, &key)?;
+        Ok(EncryptedEnv { inner })
+    }
+
+    /// Opens an unencrypted environment that stores a [`PageChecksum`] with every page.
+    ///
+    /// The checksum of a page is verified when it is read and a corrupted page is reported
+    /// as an [`MdbError::BadChecksum`](crate::MdbError::BadChecksum), see the
+    /// [`checksum`](crate::checksum) module. An environment must always be opened with
+    /// the same checksum, and an environment created without checksums cannot be opened
+    /// with one. The checksum is kept by the environment, [`Env::compact`] reopens it with
+    /// the same one.
+    ///
+    /// ## Safety
+    ///
+    /// The same as [`Self::open`].
+    #[cfg(master3)]
+    pub unsafe fn open_checksummed<H, P>(&self, path: P) -> Result<Env<T>>
+    where
+        H: PageChecksum,
+        P: AsRef<Path>,
     {
         self.raw_open_with_encryption(
             path.as_ref(),
-            Some((Some(encrypt_func_wrapper::<E>), &key, <E as AeadCore>::TagSize::U32)),
+            None,
+            Some((Some(checksum_func_wrapper::<H>), H::SIZE)),
         )
-        .map(|inner| EncryptedEnv { inner })
+    }
+
+    /// Opens an encrypted environment with a key derived from a password.
+    ///
+    /// This method requires the `password` feature.
+    ///
+    /// The key is derived with Argon2id from the password and a random salt. When the
+    /// environment is created, a new salt is generated and stored with the `params` in a
+    /// plaintext `.kdf` header file next to the data file. The next openings read the salt
+    /// and the parameters from this header, the `params` argument is then ignored.
+    ///
+    /// The header doesn't contain any secret but is required to open the environment,
+    /// it must be backed up and moved along with the data file.
+    ///
+    /// ## Safety
+    ///
+    /// The same as [`Self::open_encrypted`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns an [`io::ErrorKind::InvalidData`] error if the data file exists but not the header,
+    /// or if the header is invalid, and an [`io::ErrorKind::InvalidInput`] error if the key
+    /// derivation parameters are invalid.
+    ///
+    /// ```
+    /// use chacha20poly1305::ChaCha20Poly1305;
+    /// use heed3::types::*;
+    /// use heed3::{EnvOpenOptions, KdfParams};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir = tempfile::tempdir()?;
+    /// let password = b"correct horse battery staple";
+    ///
+    /// let env = unsafe {
+    ///     EnvOpenOptions::new().max_dbs(1).open_encrypted_with_password::<ChaCha20Poly1305, _>(
+    ///         password,
+    ///         KdfParams::default(),
+    ///         dir.path(),
+    ///     )?
+    /// };
+    ///
+    /// let mut wtxn = env.write_txn()?;
+    /// let db = env.create_database::<Str, Str>(&mut wtxn, Some("secrets"))?;
+    /// db.put(&mut wtxn, "hello", "world")?;
+    /// wtxn.commit()?;
+    /// env.prepare_for_closing().wait();
+    ///
+    /// // The salt is read back from the header, the parameters are ignored.
+    /// let env = unsafe {
+    ///     EnvOpenOptions::new().max_dbs(1).open_encrypted_with_password::<ChaCha20Poly1305, _>(
+    ///         password,
+    ///         KdfParams::default(),
+    ///         dir.path(),
+    ///     )?
+    /// };
+    /// let mut rtxn = env.read_txn()?;
+    /// let db = env.open_database::<Str, Str>(&rtxn, Some("secrets"))?.unwrap();
+    /// assert_eq!(db.get(&mut rtxn, "hello")?, Some("world"));
+    /// # Ok(()) }
+    /// ```
+    #[cfg(master3)]
+    #[cfg(feature = "password")]
+    pub unsafe fn open_encrypted_with_password<E, P>(
+        &self,
+        password: &[u8],
+        params: KdfParams,
+        path: P,
+    ) -> Result<EncryptedEnv<T>>
+    where
+        E: AeadMutInPlace + KeyInit,
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let data_file_path = data_file_path(path, self.flags);
+
+        let header_path = KdfHeader::path(&data_file_path);
+        let header = match KdfHeader::read(&header_path)? {
+            Some(header) => header,
+            None if data_file_path.exists() => {
+                let msg = format!(
+                    "missing key derivation header `{}` for an existing environment",
+                    header_path.display()
+                );
+                return Err(Error::Io(io::Error::new(io::ErrorKind::InvalidData, msg)));
+            }
+            // Another process can be creating the environment concurrently,
+            // the header written first is the one used by both.
+            None => KdfHeader::generate(params)?.write_new(&header_path)?,
+        };
+
+        let key = header.derive_key::<E>(password)?;
+        self.open_encrypted::<E, _>(key, path)
     }
 
     fn raw_open_with_encryption(
         &self,
         path: &Path,
         #[cfg(master3)] enc: Option<(ffi::MDB_enc_func, &[u8], u32)>,
+        #[cfg(master3)] checksum: Option<(ffi::MDB_sum_func, u32)>,
     ) -> Result<Env<T>> {
+        // The options are validated before creating the environment.
+        #[cfg(master3)]
+        if let Some(size) = self.page_size {
+            if !(MIN_PAGE_SIZE..=MAX_PAGE_SIZE).contains(&size) || !size.is_power_of_two() {
+                let msg = format!(
+                    "page size ({size}) must be a power of two between \
+                    {MIN_PAGE_SIZE} and {MAX_PAGE_SIZE}"
+                );
+                return Err(Error::Io(io::Error::new(io::ErrorKind::InvalidInput, msg)));
+            }
+        }
+
+        if let Some(size) = self.map_size {
+            if size % page_size::get() != 0 {
+                let msg = format!(
+                    "map size ({}) must be a multiple of the system page size ({})",
+                    size,
+                    page_size::get()
+                );
+                return Err(Error::Io(io::Error::new(io::ErrorKind::InvalidInput, msg)));
+            }
+        }
+
         let mut lock = OPENED_ENV.write().unwrap();
 
         let path = match canonicalize_path(path) {
@@ -427,54 +886,68 @@ impl<T: TlsUsage> EnvOpenOptions<T> {
                 let mut env: *mut ffi::MDB_env = ptr::null_mut();
                 mdb_result(ffi::mdb_env_create(&mut env))?;
 
-                #[cfg(master3)]
-                if let Some((encrypt_func, key, tag_size)) = enc {
-                    mdb_result(ffi::mdb_env_set_encrypt(
-                        env,
-                        encrypt_func,
-                        &crate::into_val(key),
-                        tag_size,
-                    ))?;
-                }
+                // The environment must be closed if any of the following steps fails.
+                let configure_and_open = || -> Result<()> {
+                    #[cfg(master3)]
+                    if let Some((encrypt_func, key, tag_size)) = enc {
+                        mdb_result(ffi::mdb_env_set_encrypt(
+                            env,
+                            encrypt_func,
+                            &crate::into_val(key),
+                            tag_size,
+                        ))?;
+                    }
 
-                if let Some(size) = self.map_size {
-                    if size % page_size::get() != 0 {
-                        let msg = format!(
-                            "map size ({}) must be a multiple of the system page size ({})",
-                            size,
-                            page_size::get()
-                        );
-                        return Err(Error::Io(io::Error::new(io::ErrorKind::InvalidInput, msg)));
+                    #[cfg(master3)]
+                    if let Some((checksum_func, size)) = checksum {
+                        mdb_result(ffi::mdb_env_set_checksum(env, checksum_func, size))?;
                     }
-                    mdb_result(ffi::mdb_env_set_mapsize(env, size))?;
-                }
 
-                if let Some(readers) = self.max_readers {
-                    mdb_result(ffi::mdb_env_set_maxreaders(env, readers))?;
-                }
+                    #[cfg(master3)]
+                    if let Some(size) = self.page_size {
+                        mdb_result(ffi::mdb_env_set_pagesize(env, size as i32))?;
+                    }
 
-                if let Some(dbs) = self.max_dbs {
-                    mdb_result(ffi::mdb_env_set_maxdbs(env, dbs))?;
-                }
+                    if let Some(size) = self.map_size {
+                        mdb_result(ffi::mdb_env_set_mapsize(env, size))?;
+                    }
+
+                    if let Some(readers) = self.max_readers {
+                        mdb_result(ffi::mdb_env_set_maxreaders(env, readers))?;
+                    }
 
-                // When the `<T as TlsUsage>::ENABLED` is true, we must tell
-                // LMDB to avoid using the thread local storage, this way we
-                // allow users to move RoTxn between threads safely.
-                #[allow(deprecated)] // ok because NO_TLS is inside of the crate
-                let flags = if T::ENABLED { self.flags } else { self.flags | EnvFlags::NO_TLS };
+                    if let Some(dbs) = self.max_dbs {
+                        mdb_result(ffi::mdb_env_set_maxdbs(env, dbs))?;
+                    }
 
-                let result = ffi::mdb_env_open(env, path_str.as_ptr(), flags.bits(), 0o600);
-                match mdb_result(result) {
+                    // When the `<T as TlsUsage>::ENABLED` is true, we must tell
+                    // LMDB to avoid using the thread local storage, this way we
+                    // allow users to move RoTxn between threads safely.
+                    #[allow(deprecated)] // ok because NO_TLS is inside of the crate
+                    let flags = if T::ENABLED { self.flags } else { self.flags | EnvFlags::NO_TLS };
+
+                    let result = ffi::mdb_env_open(env, path_str.as_ptr(), flags.bits(), 0o600);
+                    mdb_result(result).map_err(Into::into)
+                };
+
+                match configure_and_open() {
                     Ok(()) => {
                         let env_ptr = NonNull::new(env).unwrap();
                         let signal_event = Arc::new(SignalEvent::manual(false));
                         let inserted = lock.insert(path.clone(), signal_event.clone());
                         debug_assert!(inserted.is_none());
-                        Ok(Env::new(env_ptr, path, signal_event))
+                        let change_feed =
+                            ChangeFeed::new(self.change_capture, self.replication_log);
+                        let options = EnvOpenOptions {
+                            #[cfg(master3)]
+                            checksum,
+                            ..self.clone().with_tls_usage()
+                        };
+                        Ok(Env::new(env_ptr, path, signal_event, change_feed, options))
                     }
                     Err(e) => {
                         ffi::mdb_env_close(env);
-                        Err(e.into())
+                        Err(e)
                     }
                 }
             }
@@ -490,7 +963,113 @@ impl Default for EnvOpenOptions<WithTls> {
 
 impl<T: TlsUsage> Clone for EnvOpenOptions<T> {
     fn clone(&self) -> Self {
-        let Self { map_size, max_readers, max_dbs, flags, _tls_marker } = *self;
-        EnvOpenOptions { map_size, max_readers, max_dbs, flags, _tls_marker }
+        let Self {
+            map_size,
+            max_readers,
+            max_dbs,
+            flags,
+            change_capture,
+            replication_log,
+            #[cfg(master3)]
+            page_size,
+            #[cfg(master3)]
+            encryption,
+            #[cfg(master3)]
+            checksum,
+            _tls_marker,
+        } = *self;
+        EnvOpenOptions {
+            map_size,
+            max_readers,
+            max_dbs,
+            flags,
+            change_capture,
+            replication_log,
+            #[cfg(master3)]
+            page_size,
+            #[cfg(master3)]
+            encryption,
+            #[cfg(master3)]
+            checksum,
+            _tls_marker,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use crate::types::*;
+    use crate::{EnvOpenOptions, Error};
+
+    fn is_invalid_input<T>(result: crate::Result<T>) -> bool {
+        matches!(result, Err(Error::Io(e)) if e.kind() == io::ErrorKind::InvalidInput)
+    }
+
+    #[test]
+    #[cfg(master3)]
+    fn invalid_page_sizes() {
+        let dir = tempfile::tempdir().unwrap();
+        for size in [0, 256, 3000, 128 * 1024] {
+            let result = unsafe { EnvOpenOptions::new().page_size(size).open(dir.path()) };
+            assert!(is_invalid_input(result), "page size {size}");
+        }
+        // Nothing is created by an invalid configuration.
+        assert!(!dir.path().join("data.mdb").exists());
+
+        let env = unsafe { EnvOpenOptions::new().page_size(16 * 1024).open(dir.path()).unwrap() };
+        assert_eq!(env.info().page_size, 16 * 1024);
+        env.prepare_for_closing().wait();
+
+        // An existing environment keeps its own page size.
+        let env = unsafe { EnvOpenOptions::new().page_size(1024).open(dir.path()).unwrap() };
+        assert_eq!(env.info().page_size, 16 * 1024);
+    }
+
+    #[test]
+    fn invalid_map_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let result =
+            unsafe { EnvOpenOptions::new().map_size(page_size::get() + 1).open(dir.path()) };
+        assert!(is_invalid_input(result));
+        assert!(!dir.path().join("data.mdb").exists());
+
+        // The environment can be opened once the options are fixed.
+        let _env =
+            unsafe { EnvOpenOptions::new().map_size(page_size::get()).open(dir.path()).unwrap() };
+    }
+
+    #[test]
+    fn reopen_previous_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = unsafe { EnvOpenOptions::new().open_previous_snapshot(dir.path()) };
+        assert!(result.is_err(), "there is no environment to open");
+
+        let env = unsafe { EnvOpenOptions::new().open(dir.path()).unwrap() };
+        for state in ["first", "second", "third"] {
+            let mut wtxn = env.write_txn().unwrap();
+            let db = env.create_database::<Str, Str>(&mut wtxn, None).unwrap();
+            db.put(&mut wtxn, "state", state).unwrap();
+            wtxn.commit().unwrap();
+        }
+
+        let result = unsafe { EnvOpenOptions::new().open_previous_snapshot(dir.path()) };
+        assert!(matches!(result, Err(Error::EnvAlreadyOpened)));
+        env.prepare_for_closing().wait();
+
+        let previous = unsafe { EnvOpenOptions::new().open_previous_snapshot(dir.path()).unwrap() };
+        let rtxn = previous.read_txn().unwrap();
+        let db = previous.open_database::<Str, Str>(&rtxn, None).unwrap().unwrap();
+        assert_eq!(db.get(&rtxn, "state").unwrap(), Some("second"));
+        drop(rtxn);
+        assert!(previous.write_txn().is_err(), "the previous snapshot is read-only");
+        previous.prepare_for_closing().wait();
+
+        // Opening the previous snapshot leaves the latest one untouched.
+        let env = unsafe { EnvOpenOptions::new().open(dir.path()).unwrap() };
+        let rtxn = env.read_txn().unwrap();
+        let db = env.open_database::<Str, Str>(&rtxn, None).unwrap().unwrap();
+        assert_eq!(db.get(&rtxn, "state").unwrap(), Some("third"));
     }
 }
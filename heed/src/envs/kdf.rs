@@ -0,0 +1,249 @@
+use std::fmt::Write as _;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use aead::{Key, KeySizeUser};
+use argon2::{Algorithm, Argon2, Params, Version};
+
+use super::env::path_with_suffix;
+
+/// The first line of the key derivation header files.
+const HEADER_MAGIC: &str = "heed3-kdf v1";
+
+/// The number of random bytes of the salt generated for a new environment.
+const SALT_LEN: usize = 16;
+
+/// How many times a header being written by another process is read again.
+const INCOMPLETE_HEADER_RETRIES: u32 = 50;
+
+/// The Argon2id parameters used to derive the encryption key of an environment from a password,
+/// see [`EnvOpenOptions::open_encrypted_with_password`](crate::EnvOpenOptions::open_encrypted_with_password).
+///
+/// The defaults are the ones recommended by OWASP: 19 MiB of memory, 2 iterations and
+/// 1 degree of parallelism.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    /// The memory size in KiB.
+    pub memory_kib: u32,
+    /// The number of iterations.
+    pub iterations: u32,
+    /// The degree of parallelism.
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> KdfParams {
+        KdfParams {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+/// The plaintext header stored next to the data file of an environment
+/// encrypted with a key derived from a password.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct KdfHeader {
+    pub params: KdfParams,
+    pub salt: Vec<u8>,
+}
+
+impl KdfHeader {
+    /// Creates a header with a new random salt.
+    pub fn generate(params: KdfParams) -> io::Result<KdfHeader> {
+        let mut salt = vec![0; SALT_LEN];
+        getrandom::getrandom(&mut salt).map_err(io::Error::from)?;
+        Ok(KdfHeader { params, salt })
+    }
+
+    /// The path of the header of the environment, next to its data file.
+    pub fn path(data_file_path: &Path) -> PathBuf {
+        path_with_suffix(data_file_path, ".kdf")
+    }
+
+    /// Reads the header, returns `None` if it doesn't exist.
+    pub fn read(path: &Path) -> io::Result<Option<KdfHeader>> {
+        match fs::read_to_string(path) {
+            Ok(content) => KdfHeader::decode(&content).map(Some).ok_or_else(|| {
+                let msg = format!("invalid key derivation header `{}`", path.display());
+                io::Error::new(io::ErrorKind::InvalidData, msg)
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Writes the header if none exists and returns the header stored at the path:
+    /// this one or the one already written, possibly concurrently, by another process.
+    pub fn write_new(self, path: &Path) -> io::Result<KdfHeader> {
+        match OpenOptions::new().write(true).create_new(true).open(path) {
+            Ok(mut file) => {
+                file.write_all(self.encode().as_bytes())?;
+                file.sync_all()?;
+                #[cfg(unix)]
+                if let Some(parent) = path.parent() {
+                    // Makes sure the creation of the file itself is durable.
+                    File::open(parent)?.sync_all()?;
+                }
+                Ok(self)
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => KdfHeader::read_complete(path),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Reads a header that exists, waiting for the process creating it to finish writing it.
+    fn read_complete(path: &Path) -> io::Result<KdfHeader> {
+        let mut retries = 0;
+        loop {
+            match fs::read_to_string(path) {
+                Ok(content) => match KdfHeader::decode(&content) {
+                    Some(header) => return Ok(header),
+                    // The header is written in a single write that ends with a newline.
+                    None if !content.ends_with('\n') && retries < INCOMPLETE_HEADER_RETRIES => {
+                        retries += 1;
+                        thread::sleep(Duration::from_millis(10));
+                    }
+                    None => {
+                        let msg = format!("invalid key derivation header `{}`", path.display());
+                        return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+                    }
+                },
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Derives a key for the `E` algorithm from the password.
+    pub fn derive_key<E: KeySizeUser>(&self, password: &[u8]) -> io::Result<Key<E>> {
+        let invalid = |e: argon2::Error| io::Error::new(io::ErrorKind::InvalidInput, e.to_string());
+
+        let mut key = Key::<E>::default();
+        let KdfParams { memory_kib, iterations, parallelism } = self.params;
+        let params =
+            Params::new(memory_kib, iterations, parallelism, Some(key.len())).map_err(invalid)?;
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(password, &self.salt, &mut key)
+            .map_err(invalid)?;
+        Ok(key)
+    }
+
+    fn encode(&self) -> String {
+        let mut salt = String::with_capacity(self.salt.len() * 2);
+        for byte in &self.salt {
+            write!(salt, "{byte:02x}").unwrap();
+        }
+        let KdfParams { memory_kib, iterations, parallelism } = self.params;
+        format!(
+            "{HEADER_MAGIC}\nalgorithm=argon2id\nmemory_kib={memory_kib}\n\
+            iterations={iterations}\nparallelism={parallelism}\nsalt={salt}\n"
+        )
+    }
+
+    fn decode(content: &str) -> Option<KdfHeader> {
+        let mut lines = content.lines();
+        if lines.next()? != HEADER_MAGIC {
+            return None;
+        }
+
+        let mut field = |name: &str| lines.next()?.strip_prefix(name)?.strip_prefix('=');
+        if field("algorithm")? != "argon2id" {
+            return None;
+        }
+        let params = KdfParams {
+            memory_kib: field("memory_kib")?.parse().ok()?,
+            iterations: field("iterations")?.parse().ok()?,
+            parallelism: field("parallelism")?.parse().ok()?,
+        };
+        let salt = field("salt")?
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| {
+                let pair = std::str::from_utf8(pair).ok().filter(|p| p.len() == 2)?;
+                u8::from_str_radix(pair, 16).ok()
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(KdfHeader { params, salt })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chacha20poly1305::ChaCha20Poly1305;
+
+    use super::*;
+    use crate::{EnvOpenOptions, Error};
+
+    /// Cheap parameters to keep the tests fast.
+    const PARAMS: KdfParams = KdfParams { memory_kib: 64, iterations: 1, parallelism: 1 };
+
+    #[test]
+    fn header_roundtrip() {
+        let header = KdfHeader::generate(KdfParams::default()).unwrap();
+        assert_eq!(header.salt.len(), SALT_LEN);
+        assert_eq!(KdfHeader::decode(&header.encode()), Some(header));
+        assert_eq!(KdfHeader::decode("heed3-kdf v1\nalgorithm=scrypt\n"), None);
+    }
+
+    #[test]
+    fn the_first_written_header_wins() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = KdfHeader::path(&dir.path().join("data.mdb"));
+        assert_eq!(KdfHeader::read(&path).unwrap(), None);
+
+        let first = KdfHeader::generate(PARAMS).unwrap();
+        let second = KdfHeader::generate(KdfParams::default()).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(first.clone().write_new(&path).unwrap(), first);
+        assert_eq!(second.write_new(&path).unwrap(), first);
+        assert_eq!(KdfHeader::read(&path).unwrap(), Some(first));
+    }
+
+    #[test]
+    fn invalid_headers_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = KdfHeader::path(&dir.path().join("data.mdb"));
+        fs::write(&path, "heed3-kdf v1\nalgorithm=argon2id\nmemory_kib=lots\n").unwrap();
+
+        let invalid_data = |e: io::Error| e.kind() == io::ErrorKind::InvalidData;
+        assert!(KdfHeader::read(&path).is_err_and(invalid_data));
+        let header = KdfHeader::generate(PARAMS).unwrap();
+        assert!(header.write_new(&path).is_err_and(invalid_data));
+
+        let params = KdfParams { memory_kib: 0, ..PARAMS };
+        let header = KdfHeader::generate(params).unwrap();
+        let result = header.derive_key::<ChaCha20Poly1305>(b"password");
+        assert!(result.is_err_and(|e| e.kind() == io::ErrorKind::InvalidInput));
+    }
+
+    #[test]
+    fn an_existing_environment_requires_its_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let open = || unsafe {
+            EnvOpenOptions::new().open_encrypted_with_password::<ChaCha20Poly1305, _>(
+                b"password",
+                PARAMS,
+                dir.path(),
+            )
+        };
+
+        let env = open().unwrap();
+        env.prepare_for_closing().wait();
+        let header_path = KdfHeader::path(&dir.path().join("data.mdb"));
+        let header = KdfHeader::read(&header_path).unwrap().unwrap();
+        assert_eq!(header.params, PARAMS);
+
+        fs::remove_file(&header_path).unwrap();
+        match open() {
+            Err(Error::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::InvalidData),
+            Err(e) => panic!("expected an invalid data error, got {e}"),
+            Ok(_) => panic!("expected an invalid data error"),
+        }
+        assert!(!header_path.exists());
+    }
+}
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use std::ffi::c_void;
 use std::fs::{File, Metadata};
 #[cfg(unix)]
-use std::os::unix::io::{AsRawFd, BorrowedFd, RawFd};
+use std::os::unix::io::{AsRawFd, BorrowedFd, OwnedFd, RawFd};
 use std::panic::catch_unwind;
 use std::path::{Path, PathBuf};
 use std::process::abort;
@@ -12,7 +12,7 @@ use std::time::Duration;
 #[cfg(windows)]
 use std::{
     ffi::OsStr,
-    os::windows::io::{AsRawHandle as _, BorrowedHandle, RawHandle},
+    os::windows::io::{AsRawHandle as _, BorrowedHandle, OwnedHandle, RawHandle},
 };
 use std::{fmt, io};
 
@@ -23,15 +23,28 @@ use crate::mdb::ffi;
 #[allow(unused)] // for cargo auto doc links
 use crate::{Database, DatabaseFlags};
 
+mod dump;
 #[cfg(master3)]
 mod encrypted_env;
 mod env;
 mod env_open_options;
+#[cfg(master3)]
+#[cfg(feature = "password")]
+mod kdf;
+#[cfg(master3)]
+mod page_cipher;
+mod verify;
 
 #[cfg(master3)]
 pub use encrypted_env::EncryptedEnv;
-pub use env::Env;
+pub use env::{CompactionError, Env};
 pub use env_open_options::EnvOpenOptions;
+#[cfg(master3)]
+#[cfg(feature = "password")]
+pub use kdf::KdfParams;
+#[cfg(master3)]
+pub use page_cipher::{EncryptionOptions, PageCipher};
+pub use verify::{DatabaseVerifyReport, VerifyProblem, VerifyReport};
 
 /// Records the current list of opened environments for tracking purposes. The canonical
 /// path of an environment is removed when either an `Env` or `EncryptedEnv` is closed.
@@ -59,6 +72,50 @@ pub struct EnvInfo {
     pub maximum_number_of_readers: u32,
     /// Number of reader slots used in the environment.
     pub number_of_readers: u32,
+    /// Size of the pages of the environment, in bytes.
+    pub page_size: u32,
+}
+
+/// An entry of the reader lock table, see [`Env::readers`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReaderInfo {
+    /// The id of the process owning the reader slot.
+    pub pid: i32,
+    /// The id of the thread owning the reader slot.
+    pub thread: u64,
+    /// The id of the snapshot read by the transaction,
+    /// `None` if the slot isn't used by a transaction.
+    pub txn_id: Option<u64>,
+}
+
+impl ReaderInfo {
+    /// Parses a line of the table printed by `mdb_reader_list`,
+    /// the header and the empty table message are ignored.
+    fn from_line(line: &str) -> Option<ReaderInfo> {
+        let mut fields = line.split_whitespace();
+        let pid = fields.next()?.parse().ok()?;
+        let thread = u64::from_str_radix(fields.next()?, 16).ok()?;
+        let txn_id = match fields.next()? {
+            "-" => None,
+            txn_id => Some(txn_id.parse().ok()?),
+        };
+        Some(ReaderInfo { pid, thread, txn_id })
+    }
+}
+
+/// The message function given to `mdb_reader_list`, called once per line of the table.
+///
+/// # Safety
+///
+/// `ctx` must point to a valid `Vec<ReaderInfo>` and `msg` to a nul-terminated string.
+unsafe extern "C" fn reader_list_wrapper(
+    msg: *const libc::c_char,
+    ctx: *mut libc::c_void,
+) -> libc::c_int {
+    let readers = unsafe { &mut *(ctx as *mut Vec<ReaderInfo>) };
+    let msg = unsafe { std::ffi::CStr::from_ptr(msg) };
+    readers.extend(msg.to_str().ok().and_then(ReaderInfo::from_line));
+    0
 }
 
 /// A structure that can be used to wait for the closing event.
@@ -130,6 +187,16 @@ fn get_file_fd(file: &File) -> RawHandle {
     file.as_raw_handle()
 }
 
+#[cfg(unix)]
+fn pipe_writer_into_file(writer: io::PipeWriter) -> File {
+    File::from(OwnedFd::from(writer))
+}
+
+#[cfg(windows)]
+fn pipe_writer_into_file(writer: io::PipeWriter) -> File {
+    File::from(OwnedHandle::from(writer))
+}
+
 #[cfg(unix)]
 /// Get metadata from a file descriptor.
 unsafe fn metadata_from_fd(raw_fd: RawFd) -> io::Result<Metadata> {
@@ -268,6 +335,17 @@ pub enum CompactionOption {
     Disabled,
 }
 
+/// The text format used by [`Env::dump`] to write the keys and values,
+/// the same as the `mdb_dump` tool.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DumpFormat {
+    /// Printable characters are written as is, the others as backslash-escaped
+    /// hexadecimal pairs, like `mdb_dump -p`.
+    Print,
+    /// Every byte is written as an hexadecimal pair, the default of `mdb_dump`.
+    Hex,
+}
+
 /// Whether to enable or disable flags in [`Env::set_flags`].
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum FlagSetMode {
@@ -0,0 +1,434 @@
+use std::panic::catch_unwind;
+
+use aead::generic_array::typenum::Unsigned;
+use aead::{AeadCore, AeadMutInPlace, Key, KeyInit, Nonce, Tag};
+
+use crate::mdb::ffi;
+use crate::BoxedError;
+
+/// A cipher used by LMDB to encrypt and decrypt the pages of an environment,
+/// see [`EnvOpenOptions::open_with_page_cipher`](crate::EnvOpenOptions::open_with_page_cipher).
+///
+/// It is implemented for every [RustCrypto AEAD](https://github.com/RustCrypto/AEADs#crates)
+/// algorithm and can be implemented to plug in other libraries, hardware-backed
+/// ciphers or non-authenticated modes like XTS.
+///
+/// LMDB gives the same key to every call along with a 16 bytes initialization vector that is
+/// unique for every page write. The associated data is built from the [`EncryptionOptions`]
+/// and is empty by default. The `tag` is stored next to the encrypted page and given back
+/// to [`PageCipher::decrypt`], it is empty when [`PageCipher::TAG_SIZE`] is zero.
+///
+/// The ciphertext must have the same length as the plaintext. An error returned
+/// by one of the methods is reported by LMDB as an [`MdbError::CryptoFail`](crate::MdbError::CryptoFail).
+///
+/// ```
+/// use heed3::types::*;
+/// use heed3::{BoxedError, EnvOpenOptions, PageCipher};
+///
+/// /// A toy cipher, do not use it to protect anything.
+/// enum Xor {}
+///
+/// impl PageCipher for Xor {
+///     const TAG_SIZE: u32 = 0;
+///
+///     fn encrypt(
+///         key: &[u8],
+///         iv: &[u8],
+///         _aad: &[u8],
+///         plaintext: &[u8],
+///         ciphertext: &mut [u8],
+///         _tag: &mut [u8],
+///     ) -> Result<(), BoxedError> {
+///         let mask = key.iter().zip(iv.iter().cycle()).map(|(k, i)| k ^ i).cycle();
+///         plaintext.iter().zip(mask).zip(ciphertext).for_each(|((p, m), c)| *c = p ^ m);
+///         Ok(())
+///     }
+///
+///     fn decrypt(
+///         key: &[u8],
+///         iv: &[u8],
+///         aad: &[u8],
+///         ciphertext: &[u8],
+///         plaintext: &mut [u8],
+///         _tag: &[u8],
+///     ) -> Result<(), BoxedError> {
+///         Xor::encrypt(key, iv, aad, ciphertext, plaintext, &mut [])
+///     }
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let dir = tempfile::tempdir()?;
+/// let options = EnvOpenOptions::new();
+/// let env = unsafe { options.open_with_page_cipher::<Xor, _>(b"secret", dir.path())? };
+///
+/// let mut wtxn = env.write_txn()?;
+/// let db = env.create_database::<Str, Str>(&mut wtxn, None)?;
+/// db.put(&mut wtxn, "hello", "world")?;
+/// wtxn.commit()?;
+///
+/// let mut rtxn = env.read_txn()?;
+/// assert_eq!(db.get(&mut rtxn, "hello")?, Some("world"));
+/// # Ok(()) }
+/// ```
+pub trait PageCipher {
+    /// The size in bytes of the authentication tag stored with every page.
+    const TAG_SIZE: u32;
+
+    /// Encrypts the `plaintext` page into the `ciphertext` buffer and writes the
+    /// authentication tag of the page and of the `aad` into `tag`.
+    fn encrypt(
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+        plaintext: &[u8],
+        ciphertext: &mut [u8],
+        tag: &mut [u8],
+    ) -> Result<(), BoxedError>;
+
+    /// Verifies the authentication `tag` of the page and of the `aad` and decrypts
+    /// the `ciphertext` page into the `plaintext` buffer.
+    fn decrypt(
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+        ciphertext: &[u8],
+        plaintext: &mut [u8],
+        tag: &[u8],
+    ) -> Result<(), BoxedError>;
+}
+
+impl<A: AeadMutInPlace + KeyInit> PageCipher for A {
+    const TAG_SIZE: u32 = <A as AeadCore>::TagSize::U32;
+
+    fn encrypt(
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+        plaintext: &[u8],
+        ciphertext: &mut [u8],
+        tag: &mut [u8],
+    ) -> Result<(), BoxedError> {
+        ciphertext.copy_from_slice(plaintext);
+        let key: &Key<A> = key.into();
+        let nonce = truncate_nonce::<A>(iv)?;
+        let mut aead = A::new(key);
+        let computed = aead
+            .encrypt_in_place_detached(nonce, aad, ciphertext)
+            .map_err(|aead::Error| "AEAD encryption failed")?;
+        tag.copy_from_slice(&computed);
+        Ok(())
+    }
+
+    fn decrypt(
+        key: &[u8],
+        iv: &[u8],
+        aad: &[u8],
+        ciphertext: &[u8],
+        plaintext: &mut [u8],
+        tag: &[u8],
+    ) -> Result<(), BoxedError> {
+        plaintext.copy_from_slice(ciphertext);
+        let key: &Key<A> = key.into();
+        let nonce = truncate_nonce::<A>(iv)?;
+        let tag: &Tag<A> = tag.into();
+        let mut aead = A::new(key);
+        aead.decrypt_in_place_detached(nonce, aad, plaintext, tag)
+            .map_err(|aead::Error| "AEAD decryption failed")?;
+        Ok(())
+    }
+}
+
+/// The context bound into the associated data of every encrypted page,
+/// see [`EnvOpenOptions::encryption`](crate::EnvOpenOptions::encryption).
+///
+/// When binding the environment identifier, pages copied from another environment, even one
+/// encrypted with the same key, fail authentication. The position of a page needs no binding,
+/// LMDB derives the initialization vector, used as the nonce, from the page number and the
+/// transaction id. These options must be the same every time the environment is opened, the
+/// environment fails to open with an [`Error::InvalidEncryptionKey`](crate::Error::InvalidEncryptionKey)
+/// otherwise. The defaults bind nothing, they are compatible with the environments
+/// encrypted without these options.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EncryptionOptions {
+    /// A stable identifier of the environment, like a UUID, stored nowhere by heed.
+    pub environment_id: Option<[u8; 16]>,
+}
+
+/// The flag of the cipher key telling that it contains an environment identifier.
+const ENVIRONMENT_ID_FLAG: u8 = 0b01;
+
+/// Prepends the encryption options to the key given to LMDB, as it is the only
+/// data available to [`encrypt_func_wrapper`]: a flags byte, then the environment
+/// identifier if any, then the key itself.
+pub(crate) fn cipher_key(key: &[u8], options: &EncryptionOptions) -> Vec<u8> {
+    let EncryptionOptions { environment_id } = *options;
+    let flags = if environment_id.is_some() { ENVIRONMENT_ID_FLAG } else { 0 };
+
+    let mut bytes = Vec::with_capacity(1 + 16 + key.len());
+    bytes.push(flags);
+    bytes.extend(environment_id.iter().flatten());
+    bytes.extend_from_slice(key);
+    bytes
+}
+
+/// Splits the key given by LMDB into the user key and the associated data of the pages,
+/// both borrowed from the cipher key to not allocate for every page.
+fn split_cipher_key(cipher_key: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (&flags, key) = cipher_key.split_first()?;
+    if flags & ENVIRONMENT_ID_FLAG != 0 {
+        let (environment_id, key) = key.split_at_checked(16)?;
+        Some((key, environment_id))
+    } else {
+        Some((key, &[]))
+    }
+}
+
+/// The constant encrypted into the key check block of an environment.
+const KEY_CHECK_PLAINTEXT: &[u8; 32] = b"heed3 encryption key check v1\0\0\0";
+
+/// The initialization vector of the key check block. LMDB derives the initialization
+/// vectors of the pages from their page number and transaction id, none of them can be
+/// all ones, the key check block never shares a nonce with a page.
+const KEY_CHECK_IV: [u8; 16] = [0xff; 16];
+
+/// Encrypts a constant with the key and the associated data of the environment, the block
+/// is stored next to the data file and tells apart a wrong key from a corrupted page.
+pub(crate) fn key_check_block<C: PageCipher>(cipher_key: &[u8]) -> Result<Vec<u8>, BoxedError> {
+    let (key, aad) = split_cipher_key(cipher_key).ok_or("invalid cipher key")?;
+    let mut block = vec![0; KEY_CHECK_PLAINTEXT.len() + C::TAG_SIZE as usize];
+    let (ciphertext, tag) = block.split_at_mut(KEY_CHECK_PLAINTEXT.len());
+    C::encrypt(key, &KEY_CHECK_IV, aad, KEY_CHECK_PLAINTEXT, ciphertext, tag)?;
+    Ok(block)
+}
+
+/// Whether the key check block was written with this key and associated data.
+pub(crate) fn verify_key_check_block<C: PageCipher>(cipher_key: &[u8], block: &[u8]) -> bool {
+    let Some((key, aad)) = split_cipher_key(cipher_key) else {
+        return false;
+    };
+    if block.len() != KEY_CHECK_PLAINTEXT.len() + C::TAG_SIZE as usize {
+        return false;
+    }
+
+    let (ciphertext, tag) = block.split_at(KEY_CHECK_PLAINTEXT.len());
+    let mut plaintext = [0; KEY_CHECK_PLAINTEXT.len()];
+    C::decrypt(key, &KEY_CHECK_IV, aad, ciphertext, &mut plaintext, tag).is_ok()
+        && &plaintext == KEY_CHECK_PLAINTEXT
+}
+
+/// LMDB exposes a nonce of 16 bytes, it is truncated to the size used by the algorithm.
+fn truncate_nonce<A: AeadCore>(iv: &[u8]) -> Result<&Nonce<A>, BoxedError> {
+    iv.get(..A::NonceSize::USIZE).map(Into::into).ok_or_else(|| "the nonce is too short".into())
+}
+
+/// The wrapper function that is called by LMDB that directly calls
+/// the Rust idiomatic function internally.
+pub(crate) unsafe extern "C" fn encrypt_func_wrapper<C: PageCipher>(
+    src: *const ffi::MDB_val,
+    dst: *mut ffi::MDB_val,
+    key_ptr: *const ffi::MDB_val,
+    encdec: i32,
+) -> i32 {
+    let result = catch_unwind(|| {
+        let input = std::slice::from_raw_parts((*src).mv_data as *const u8, (*src).mv_size);
+        let output = std::slice::from_raw_parts_mut((*dst).mv_data as *mut u8, (*dst).mv_size);
+        let key = std::slice::from_raw_parts((*key_ptr).mv_data as *const u8, (*key_ptr).mv_size);
+        let iv = std::slice::from_raw_parts(
+            (*key_ptr.offset(1)).mv_data as *const u8,
+            (*key_ptr.offset(1)).mv_size,
+        );
+        let tag = match (*key_ptr.offset(2)).mv_size {
+            0 => &mut [],
+            size => std::slice::from_raw_parts_mut((*key_ptr.offset(2)).mv_data as *mut u8, size),
+        };
+
+        let Some((key, aad)) = split_cipher_key(key) else {
+            return 1;
+        };
+        let result = if encdec == 1 {
+            C::encrypt(key, iv, aad, input, output, tag)
+        } else {
+            C::decrypt(key, iv, aad, input, output, tag)
+        };
+
+        result.is_err() as i32
+    });
+
+    result.unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::*;
+    use crate::{EnvOpenOptions, Error, MdbError};
+
+    /// A toy cipher, whose tag is the sum of the bytes of the plaintext.
+    enum Xor {}
+
+    impl PageCipher for Xor {
+        const TAG_SIZE: u32 = 1;
+
+        fn encrypt(
+            key: &[u8],
+            iv: &[u8],
+            _aad: &[u8],
+            plaintext: &[u8],
+            ciphertext: &mut [u8],
+            tag: &mut [u8],
+        ) -> Result<(), BoxedError> {
+            let mask = key.iter().zip(iv.iter().cycle()).map(|(k, i)| k ^ i).cycle();
+            plaintext.iter().zip(mask).zip(&mut *ciphertext).for_each(|((p, m), c)| *c = p ^ m);
+            tag[0] = plaintext.iter().fold(0u8, |sum, p| sum.wrapping_add(*p));
+            Ok(())
+        }
+
+        fn decrypt(
+            key: &[u8],
+            iv: &[u8],
+            aad: &[u8],
+            ciphertext: &[u8],
+            plaintext: &mut [u8],
+            tag: &[u8],
+        ) -> Result<(), BoxedError> {
+            let mut computed = [0];
+            Xor::encrypt(key, iv, aad, ciphertext, plaintext, &mut computed)?;
+            let sum = plaintext.iter().fold(0u8, |sum, p| sum.wrapping_add(*p));
+            if sum == tag[0] {
+                Ok(())
+            } else {
+                Err("invalid tag".into())
+            }
+        }
+    }
+
+    /// A cipher that fails or panics when encrypting.
+    enum Broken<const PANIC: bool> {}
+
+    impl<const PANIC: bool> PageCipher for Broken<PANIC> {
+        const TAG_SIZE: u32 = 0;
+
+        fn encrypt(
+            _key: &[u8],
+            _iv: &[u8],
+            _aad: &[u8],
+            _plaintext: &[u8],
+            _ciphertext: &mut [u8],
+            _tag: &mut [u8],
+        ) -> Result<(), BoxedError> {
+            if PANIC {
+                panic!("broken cipher");
+            }
+            Err("broken cipher".into())
+        }
+
+        fn decrypt(
+            _key: &[u8],
+            _iv: &[u8],
+            _aad: &[u8],
+            _ciphertext: &[u8],
+            _plaintext: &mut [u8],
+            _tag: &[u8],
+        ) -> Result<(), BoxedError> {
+            Err("broken cipher".into())
+        }
+    }
+
+    #[test]
+    fn custom_page_cipher() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = EnvOpenOptions::new();
+        let env =
+            unsafe { options.open_with_page_cipher::<Xor, _>(b"secret", dir.path()) }.unwrap();
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<Str, Str>(&mut wtxn, None).unwrap();
+        db.put(&mut wtxn, "hello", "world").unwrap();
+        wtxn.commit().unwrap();
+        env.prepare_for_closing().wait();
+
+        let data = std::fs::read(dir.path().join("data.mdb")).unwrap();
+        assert!(!data.windows(5).any(|window| window == b"world"));
+
+        let result = unsafe { options.open_with_page_cipher::<Xor, _>(b"public", dir.path()) };
+        assert!(matches!(result, Err(Error::InvalidEncryptionKey)));
+
+        let env =
+            unsafe { options.open_with_page_cipher::<Xor, _>(b"secret", dir.path()) }.unwrap();
+        let mut rtxn = env.read_txn().unwrap();
+        let db = env.open_database::<Str, Str>(&rtxn, None).unwrap().unwrap();
+        assert_eq!(db.get(&mut rtxn, "hello").unwrap(), Some("world"));
+    }
+
+    #[test]
+    fn page_cipher_errors_are_crypto_failures() {
+        fn write<C: PageCipher>() -> crate::Result<()> {
+            let dir = tempfile::tempdir().unwrap();
+            let env = unsafe {
+                EnvOpenOptions::new().open_with_page_cipher::<C, _>(b"secret", dir.path())?
+            };
+            let mut wtxn = env.write_txn()?;
+            let db = env.create_database::<Str, Str>(&mut wtxn, None)?;
+            db.put(&mut wtxn, "hello", "world")?;
+            wtxn.commit()
+        }
+
+        assert!(matches!(write::<Broken<false>>(), Err(Error::Mdb(MdbError::CryptoFail))));
+        assert!(matches!(write::<Broken<true>>(), Err(Error::Mdb(MdbError::CryptoFail))));
+    }
+
+    #[test]
+    fn aead_page_cipher_authenticates_the_associated_data() {
+        type Cipher = chacha20poly1305::ChaCha20Poly1305;
+
+        let key = [1; 32];
+        let iv = [2; 16];
+        let plaintext = [3; 64];
+        let mut ciphertext = [0; 64];
+        let mut tag = [0; <Cipher as PageCipher>::TAG_SIZE as usize];
+        Cipher::encrypt(&key, &iv, b"aad", &plaintext, &mut ciphertext, &mut tag).unwrap();
+        assert_ne!(ciphertext, plaintext);
+
+        let mut decrypted = [0; 64];
+        Cipher::decrypt(&key, &iv, b"aad", &ciphertext, &mut decrypted, &tag).unwrap();
+        assert_eq!(decrypted, plaintext);
+
+        assert!(Cipher::decrypt(&key, &iv, b"other", &ciphertext, &mut decrypted, &tag).is_err());
+        assert!(Cipher::decrypt(&key, &[4; 16], b"aad", &ciphertext, &mut decrypted, &tag).is_err());
+        assert!(Cipher::decrypt(&key, &iv[..4], b"aad", &ciphertext, &mut decrypted, &tag).is_err());
+    }
+
+    #[test]
+    fn cipher_key_roundtrip() {
+        let key = cipher_key(b"secret", &EncryptionOptions::default());
+        assert_eq!(split_cipher_key(&key), Some((&b"secret"[..], &[][..])));
+
+        let options = EncryptionOptions { environment_id: Some([7; 16]) };
+        let key = cipher_key(b"secret", &options);
+        assert_eq!(split_cipher_key(&key), Some((&b"secret"[..], &[7; 16][..])));
+
+        assert_eq!(split_cipher_key(&[ENVIRONMENT_ID_FLAG, 1, 2]), None);
+        assert_eq!(split_cipher_key(&[]), None);
+    }
+
+    #[test]
+    fn key_check_block_depends_on_the_key_and_options() {
+        type Cipher = chacha20poly1305::ChaCha20Poly1305;
+
+        let key = cipher_key(&[1; 32], &EncryptionOptions::default());
+        let block = key_check_block::<Cipher>(&key).unwrap();
+        assert!(verify_key_check_block::<Cipher>(&key, &block));
+
+        let wrong_key = cipher_key(&[2; 32], &EncryptionOptions::default());
+        assert!(!verify_key_check_block::<Cipher>(&wrong_key, &block));
+
+        let options = EncryptionOptions { environment_id: Some([7; 16]) };
+        let other_id = cipher_key(&[1; 32], &options);
+        assert!(!verify_key_check_block::<Cipher>(&other_id, &block));
+
+        let mut tampered = block.clone();
+        tampered[0] ^= 1;
+        assert!(!verify_key_check_block::<Cipher>(&key, &tampered));
+        assert!(!verify_key_check_block::<Cipher>(&key, &block[..block.len() - 1]));
+    }
+}
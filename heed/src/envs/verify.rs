@@ -0,0 +1,319 @@
+use std::cmp::Ordering;
+
+use types::Bytes;
+
+use super::Env;
+use crate::mdb::error::mdb_result;
+use crate::mdb::ffi;
+use crate::*;
+
+/// The result of [`Env::verify`], one report for each database of the environment.
+#[derive(Debug, Clone)]
+pub struct VerifyReport {
+    /// The reports of the unnamed database followed by the named ones.
+    pub databases: Vec<DatabaseVerifyReport>,
+}
+
+impl VerifyReport {
+    /// Returns `true` if no problem was found in any of the databases.
+    pub fn is_ok(&self) -> bool {
+        self.databases.iter().all(|db| db.problems.is_empty())
+    }
+
+    /// Returns an iterator over the problems found and the name of their database.
+    pub fn problems(&self) -> impl Iterator<Item = (Option<&str>, &VerifyProblem)> {
+        self.databases.iter().flat_map(|db| db.problems.iter().map(|p| (db.name.as_deref(), p)))
+    }
+}
+
+/// The verification report of a single database.
+#[derive(Debug, Clone)]
+pub struct DatabaseVerifyReport {
+    /// The name of the database, `None` for the unnamed one.
+    pub name: Option<String>,
+    /// The flags the database was created with.
+    pub flags: DatabaseFlags,
+    /// The number of entries the cursor went through, duplicate values included.
+    pub entries: usize,
+    /// The problems found in this database.
+    pub problems: Vec<VerifyProblem>,
+}
+
+/// A problem found by [`Env::verify`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyProblem {
+    /// A key is not strictly greater than the previous one under the comparator of the database.
+    UnorderedKeys {
+        /// The previous key.
+        previous: Vec<u8>,
+        /// The offending key.
+        key: Vec<u8>,
+    },
+    /// A duplicate value is not strictly greater than the previous one of the same key.
+    UnorderedDuplicates {
+        /// The key of the values.
+        key: Vec<u8>,
+        /// The previous value.
+        previous: Vec<u8>,
+        /// The offending value.
+        data: Vec<u8>,
+    },
+    /// The number of entries walked doesn't match [`DatabaseStat::entries`].
+    EntriesMismatch {
+        /// The number of entries the cursor went through.
+        counted: usize,
+        /// The number of entries reported by LMDB.
+        expected: usize,
+    },
+    /// LMDB returned an error, like [`MdbError::Corrupted`] or [`MdbError::PageNotFound`],
+    /// and the walk of the database was stopped.
+    Mdb {
+        /// The last key successfully read before the error, if any.
+        after_key: Option<Vec<u8>>,
+        /// The error returned by LMDB.
+        error: MdbError,
+    },
+}
+
+impl<T> Env<T> {
+    /// Walks every database of the environment with a cursor and reports the problems found.
+    ///
+    /// For every database it checks that the keys are strictly ordered under the comparator
+    /// of the database, that the duplicate values of [`DatabaseFlags::DUP_SORT`] databases are
+    /// strictly ordered too, and that the number of entries matches [`DatabaseStat::entries`].
+    /// The LMDB errors, like [`MdbError::Corrupted`], are reported with the database and the
+    /// last valid key instead of being returned. Only the errors that prevent listing the
+    /// databases are returned.
+    ///
+    /// The comparators are the ones LMDB knows about: the databases using a custom
+    /// [`Comparator`] must have been opened with it in this environment beforehand,
+    /// otherwise they are verified with the default lexicographic one.
+    ///
+    /// It is useful after a crash or before promoting a backup.
+    ///
+    /// ```
+    /// use heed::EnvOpenOptions;
+    /// use heed::types::*;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir = tempfile::tempdir()?;
+    /// let env = unsafe { EnvOpenOptions::new().max_dbs(2).open(dir.path())? };
+    ///
+    /// let mut wtxn = env.write_txn()?;
+    /// let db = env.create_database::<Str, Str>(&mut wtxn, Some("words"))?;
+    /// db.put(&mut wtxn, "hello", "world")?;
+    /// db.put(&mut wtxn, "bonjour", "monde")?;
+    /// wtxn.commit()?;
+    ///
+    /// let rtxn = env.read_txn()?;
+    /// let report = env.verify(&rtxn)?;
+    /// assert!(report.is_ok());
+    /// assert_eq!(report.databases.len(), 2);
+    /// assert_eq!(report.databases[1].name.as_deref(), Some("words"));
+    /// assert_eq!(report.databases[1].entries, 2);
+    /// # Ok(()) }
+    /// ```
+    pub fn verify(&self, rtxn: &RoTxn<T>) -> Result<VerifyReport> {
+        let mut names = vec![None];
+        names.extend(self.database_names(rtxn)?.into_iter().map(Some));
+
+        let mut databases = Vec::with_capacity(names.len());
+        for name in names {
+            let db = match self.open_database::<Bytes, Bytes>(rtxn, name.as_deref())? {
+                Some(db) => db,
+                None => continue,
+            };
+            databases.push(verify_database(rtxn, db, name)?);
+        }
+
+        Ok(VerifyReport { databases })
+    }
+}
+
+fn verify_database<T>(
+    rtxn: &RoTxn<T>,
+    db: Database<Bytes, Bytes>,
+    name: Option<String>,
+) -> Result<DatabaseVerifyReport> {
+    let raw_txn = rtxn.txn.unwrap().as_ptr();
+    let mut flags = 0;
+    unsafe { mdb_result(ffi::mdb_dbi_flags(raw_txn, db.dbi, &mut flags))? };
+    let flags = DatabaseFlags::from_bits_truncate(flags);
+    let dup_sort = flags.contains(DatabaseFlags::DUP_SORT);
+
+    let compare = |cmp: CompareFn, a: &[u8], b: &[u8]| unsafe {
+        let (a, b) = (ffi::into_val(a), ffi::into_val(b));
+        cmp(raw_txn, db.dbi, &a, &b).cmp(&0)
+    };
+
+    let mut problems = Vec::new();
+    let mut entries = 0;
+    let mut previous: Option<(&[u8], &[u8])> = None;
+    let mut iter = match db.iter(rtxn) {
+        Ok(iter) => iter,
+        Err(Error::Mdb(error)) => {
+            problems.push(VerifyProblem::Mdb { after_key: None, error });
+            return Ok(DatabaseVerifyReport { name, flags, entries, problems });
+        }
+        Err(e) => return Err(e),
+    };
+
+    loop {
+        let (key, data) = match iter.next() {
+            Some(Ok(entry)) => entry,
+            Some(Err(Error::Mdb(error))) => {
+                let after_key = previous.map(|(key, _)| key.to_vec());
+                problems.push(VerifyProblem::Mdb { after_key, error });
+                break;
+            }
+            Some(Err(e)) => return Err(e),
+            None => break,
+        };
+
+        if let Some((prev_key, prev_data)) = previous {
+            match compare(ffi::mdb_cmp, prev_key, key) {
+                Ordering::Less => (),
+                Ordering::Equal if dup_sort => {
+                    if compare(ffi::mdb_dcmp, prev_data, data) != Ordering::Less {
+                        problems.push(VerifyProblem::UnorderedDuplicates {
+                            key: key.to_vec(),
+                            previous: prev_data.to_vec(),
+                            data: data.to_vec(),
+                        });
+                    }
+                }
+                Ordering::Equal | Ordering::Greater => {
+                    problems.push(VerifyProblem::UnorderedKeys {
+                        previous: prev_key.to_vec(),
+                        key: key.to_vec(),
+                    });
+                }
+            }
+        }
+
+        entries += 1;
+        previous = Some((key, data));
+    }
+
+    // The entries can't be compared when the walk was stopped by an error.
+    if !problems.iter().any(|p| matches!(p, VerifyProblem::Mdb { .. })) {
+        let expected = db.stat(rtxn)?.entries;
+        if entries != expected {
+            problems.push(VerifyProblem::EntriesMismatch { counted: entries, expected });
+        }
+    }
+
+    Ok(DatabaseVerifyReport { name, flags, entries, problems })
+}
+
+type CompareFn = unsafe extern "C" fn(
+    *mut ffi::MDB_txn,
+    ffi::MDB_dbi,
+    *const ffi::MDB_val,
+    *const ffi::MDB_val,
+) -> i32;
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use crate::types::*;
+    use crate::*;
+
+    enum Reverse {}
+
+    impl Comparator for Reverse {
+        fn compare(a: &[u8], b: &[u8]) -> Ordering {
+            b.cmp(a)
+        }
+    }
+
+    #[test]
+    fn detects_keys_unordered_under_the_comparator() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().max_dbs(2).open(dir.path()).unwrap() };
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env
+            .database_options()
+            .types::<Str, Unit>()
+            .key_comparator::<Reverse>()
+            .name("reversed")
+            .create(&mut wtxn)
+            .unwrap();
+        for key in ["a", "b", "c"] {
+            db.put(&mut wtxn, key, &()).unwrap();
+        }
+        wtxn.commit().unwrap();
+
+        let rtxn = env.read_txn().unwrap();
+        assert!(env.verify(&rtxn).unwrap().is_ok());
+        drop(rtxn);
+        env.prepare_for_closing().wait();
+
+        // Without its comparator the database is verified in the lexicographic order.
+        let env = unsafe { EnvOpenOptions::new().max_dbs(2).open(dir.path()).unwrap() };
+        let rtxn = env.read_txn().unwrap();
+        let report = env.verify(&rtxn).unwrap();
+        assert!(!report.is_ok());
+        let problems: Vec<_> = report.problems().collect();
+        assert_eq!(
+            problems,
+            [
+                (
+                    Some("reversed"),
+                    &VerifyProblem::UnorderedKeys { previous: b"c".to_vec(), key: b"b".to_vec() }
+                ),
+                (
+                    Some("reversed"),
+                    &VerifyProblem::UnorderedKeys { previous: b"b".to_vec(), key: b"a".to_vec() }
+                ),
+            ]
+        );
+    }
+
+    #[cfg(master3)]
+    #[test]
+    fn reports_corrupted_pages() {
+        use std::fs;
+
+        use crate::checksum::PageChecksum;
+
+        enum Sum {}
+
+        impl PageChecksum for Sum {
+            const SIZE: u32 = 8;
+
+            fn checksum(page: &[u8], output: &mut [u8]) {
+                let sum = page.iter().fold(0u64, |s, &b| s.wrapping_mul(31).wrapping_add(b as u64));
+                output.copy_from_slice(&sum.to_le_bytes());
+            }
+        }
+
+        let marker = b"corrupt-me-please";
+        let dir = tempfile::tempdir().unwrap();
+        let mut options = EnvOpenOptions::new();
+        options.max_dbs(2);
+        let env = unsafe { options.open_checksummed::<Sum, _>(dir.path()).unwrap() };
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<Str, Bytes>(&mut wtxn, Some("values")).unwrap();
+        db.put(&mut wtxn, "key", marker).unwrap();
+        wtxn.commit().unwrap();
+        env.prepare_for_closing().wait();
+
+        // Flip a byte of the value directly in the data file.
+        let path = dir.path().join("data.mdb");
+        let mut bytes = fs::read(&path).unwrap();
+        let offset = bytes.windows(marker.len()).position(|w| w == marker).unwrap();
+        bytes[offset] ^= 0xff;
+        fs::write(&path, bytes).unwrap();
+
+        let env = unsafe { options.open_checksummed::<Sum, _>(dir.path()).unwrap() };
+        let rtxn = env.read_txn().unwrap();
+        let report = env.verify(&rtxn).unwrap();
+        let problems: Vec<_> = report.problems().collect();
+        assert!(matches!(
+            problems[..],
+            [(Some("values"), VerifyProblem::Mdb { error: MdbError::BadChecksum, .. })]
+        ));
+    }
+}
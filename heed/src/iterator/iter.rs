@@ -173,7 +173,10 @@ where
         match result {
             Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                 (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                (Err(e), _) => Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e))),
+                (_, Err(e)) => {
+                    Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                }
             },
             Ok(None) => None,
             Err(e) => Some(Err(e)),
@@ -196,7 +199,10 @@ where
         match result {
             Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                 (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                (Err(e), _) => Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e))),
+                (_, Err(e)) => {
+                    Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                }
             },
             Ok(None) => None,
             Err(e) => Some(Err(e)),
@@ -274,8 +280,10 @@ impl<'txn, KC, DC, IM> RwIter<'txn, KC, DC, IM> {
         KC: BytesEncode<'a>,
         DC: BytesEncode<'a>,
     {
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let data_bytes: Cow<[u8]> = DC::bytes_encode(data).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key)
+            .map_err(|e| self.cursor.encoding_error::<KC>(EntrySide::Key, e))?;
+        let data_bytes: Cow<[u8]> = DC::bytes_encode(data)
+            .map_err(|e| self.cursor.encoding_error::<DC>(EntrySide::Data, e))?;
         self.cursor.put_current(&key_bytes, &data_bytes)
     }
 
@@ -303,7 +311,8 @@ impl<'txn, KC, DC, IM> RwIter<'txn, KC, DC, IM> {
         KC: BytesEncode<'a>,
         F: FnOnce(&mut ReservedSpace) -> io::Result<()>,
     {
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key)
+            .map_err(|e| self.cursor.encoding_error::<KC>(EntrySide::Key, e))?;
         self.cursor.put_current_reserved_with_flags(flags, &key_bytes, data_size, write_func)
     }
 
@@ -334,8 +343,10 @@ impl<'txn, KC, DC, IM> RwIter<'txn, KC, DC, IM> {
         KC: BytesEncode<'a>,
         NDC: BytesEncode<'a>,
     {
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let data_bytes: Cow<[u8]> = NDC::bytes_encode(data).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key)
+            .map_err(|e| self.cursor.encoding_error::<KC>(EntrySide::Key, e))?;
+        let data_bytes: Cow<[u8]> = NDC::bytes_encode(data)
+            .map_err(|e| self.cursor.encoding_error::<NDC>(EntrySide::Data, e))?;
         self.cursor.put_current_with_flags(flags, &key_bytes, &data_bytes)
     }
 
@@ -405,7 +416,10 @@ where
         match result {
             Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                 (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                (Err(e), _) => Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e))),
+                (_, Err(e)) => {
+                    Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                }
             },
             Ok(None) => None,
             Err(e) => Some(Err(e)),
@@ -428,7 +442,10 @@ where
         match result {
             Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                 (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                (Err(e), _) => Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e))),
+                (_, Err(e)) => {
+                    Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                }
             },
             Ok(None) => None,
             Err(e) => Some(Err(e)),
@@ -522,7 +539,10 @@ where
         match result {
             Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                 (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                (Err(e), _) => Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e))),
+                (_, Err(e)) => {
+                    Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                }
             },
             Ok(None) => None,
             Err(e) => Some(Err(e)),
@@ -545,7 +565,10 @@ where
         match result {
             Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                 (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                (Err(e), _) => Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e))),
+                (_, Err(e)) => {
+                    Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                }
             },
             Ok(None) => None,
             Err(e) => Some(Err(e)),
@@ -622,8 +645,10 @@ impl<'txn, KC, DC, IM> RwRevIter<'txn, KC, DC, IM> {
         KC: BytesEncode<'a>,
         DC: BytesEncode<'a>,
     {
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let data_bytes: Cow<[u8]> = DC::bytes_encode(data).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key)
+            .map_err(|e| self.cursor.encoding_error::<KC>(EntrySide::Key, e))?;
+        let data_bytes: Cow<[u8]> = DC::bytes_encode(data)
+            .map_err(|e| self.cursor.encoding_error::<DC>(EntrySide::Data, e))?;
         self.cursor.put_current(&key_bytes, &data_bytes)
     }
 
@@ -651,7 +676,8 @@ impl<'txn, KC, DC, IM> RwRevIter<'txn, KC, DC, IM> {
         KC: BytesEncode<'a>,
         F: FnOnce(&mut ReservedSpace) -> io::Result<()>,
     {
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key)
+            .map_err(|e| self.cursor.encoding_error::<KC>(EntrySide::Key, e))?;
         self.cursor.put_current_reserved_with_flags(flags, &key_bytes, data_size, write_func)
     }
 
@@ -682,8 +708,10 @@ impl<'txn, KC, DC, IM> RwRevIter<'txn, KC, DC, IM> {
         KC: BytesEncode<'a>,
         NDC: BytesEncode<'a>,
     {
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let data_bytes: Cow<[u8]> = NDC::bytes_encode(data).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key)
+            .map_err(|e| self.cursor.encoding_error::<KC>(EntrySide::Key, e))?;
+        let data_bytes: Cow<[u8]> = NDC::bytes_encode(data)
+            .map_err(|e| self.cursor.encoding_error::<NDC>(EntrySide::Data, e))?;
         self.cursor.put_current_with_flags(flags, &key_bytes, &data_bytes)
     }
 
@@ -755,7 +783,10 @@ where
         match result {
             Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                 (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                (Err(e), _) => Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e))),
+                (_, Err(e)) => {
+                    Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                }
             },
             Ok(None) => None,
             Err(e) => Some(Err(e)),
@@ -778,7 +809,10 @@ where
         match result {
             Ok(Some((key, data))) => match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                 (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                (Err(e), _) => Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e))),
+                (_, Err(e)) => {
+                    Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                }
             },
             Ok(None) => None,
             Err(e) => Some(Err(e)),
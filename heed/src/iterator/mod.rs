@@ -1,8 +1,14 @@
 mod iter;
+#[cfg(master3)]
+mod pinned;
 mod prefix;
 mod range;
 
 pub use self::iter::{RoIter, RoRevIter, RwIter, RwRevIter};
+#[cfg(master3)]
+pub(crate) use self::pinned::pin_and_decode;
+#[cfg(master3)]
+pub use self::pinned::{PinnedIter, PinnedPrefix, PinnedRange};
 pub use self::prefix::{RoPrefix, RoRevPrefix, RwPrefix, RwRevPrefix};
 pub use self::range::{RoRange, RoRevRange, RwRange, RwRevRange};
 
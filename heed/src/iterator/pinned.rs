@@ -0,0 +1,150 @@
+use std::mem::ManuallyDrop;
+
+use heed_traits::{Comparator, LexicographicComparator};
+
+use crate::envs::DefaultComparator;
+use crate::txn::PinGuard;
+use crate::types::Bytes;
+use crate::*;
+
+/// A read-only iterator over the entries of an encrypted database that pins them
+/// in the transaction, see [`EncryptedDatabase::iter_pinned`].
+pub struct PinnedIter<'p, T, KC, DC, C = DefaultComparator> {
+    iter: ManuallyDrop<RoIter<'p, T, Bytes, Bytes>>,
+    txn: &'p PinnedRoTxn<'p, T>,
+    database: Database<KC, DC, C>,
+}
+
+impl<'p, T, KC, DC, C> PinnedIter<'p, T, KC, DC, C> {
+    pub(crate) fn new(
+        iter: RoIter<'p, T, Bytes, Bytes>,
+        txn: &'p PinnedRoTxn<'p, T>,
+        database: Database<KC, DC, C>,
+    ) -> PinnedIter<'p, T, KC, DC, C> {
+        PinnedIter { iter: ManuallyDrop::new(iter), txn, database }
+    }
+}
+
+impl<'p, T, KC, DC, C> Iterator for PinnedIter<'p, T, KC, DC, C>
+where
+    KC: BytesDecode<'p>,
+    DC: BytesDecode<'p>,
+{
+    type Item = Result<(KC::DItem, DC::DItem)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut pins = self.txn.lock();
+        let entry = self.iter.next()?;
+        Some(entry.and_then(|(key, data)| pin_and_decode(&self.database, &mut pins, key, data)))
+    }
+}
+
+impl<T, KC, DC, C> Drop for PinnedIter<'_, T, KC, DC, C> {
+    fn drop(&mut self) {
+        // Closing the cursor releases the pages it decrypted.
+        let _pins = self.txn.lock();
+        unsafe { ManuallyDrop::drop(&mut self.iter) }
+    }
+}
+
+/// A read-only iterator over a range of the entries of an encrypted database that pins
+/// them in the transaction, see [`EncryptedDatabase::range_pinned`].
+pub struct PinnedRange<'p, T, KC, DC, C = DefaultComparator> {
+    range: ManuallyDrop<RoRange<'p, T, Bytes, Bytes, C>>,
+    txn: &'p PinnedRoTxn<'p, T>,
+    database: Database<KC, DC, C>,
+}
+
+impl<'p, T, KC, DC, C> PinnedRange<'p, T, KC, DC, C> {
+    pub(crate) fn new(
+        range: RoRange<'p, T, Bytes, Bytes, C>,
+        txn: &'p PinnedRoTxn<'p, T>,
+        database: Database<KC, DC, C>,
+    ) -> PinnedRange<'p, T, KC, DC, C> {
+        PinnedRange { range: ManuallyDrop::new(range), txn, database }
+    }
+}
+
+impl<'p, T, KC, DC, C> Iterator for PinnedRange<'p, T, KC, DC, C>
+where
+    KC: BytesDecode<'p>,
+    DC: BytesDecode<'p>,
+    C: Comparator,
+{
+    type Item = Result<(KC::DItem, DC::DItem)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut pins = self.txn.lock();
+        let entry = self.range.next()?;
+        Some(entry.and_then(|(key, data)| pin_and_decode(&self.database, &mut pins, key, data)))
+    }
+}
+
+impl<T, KC, DC, C> Drop for PinnedRange<'_, T, KC, DC, C> {
+    fn drop(&mut self) {
+        // Closing the cursor releases the pages it decrypted.
+        let _pins = self.txn.lock();
+        unsafe { ManuallyDrop::drop(&mut self.range) }
+    }
+}
+
+/// A read-only iterator over the entries of an encrypted database starting with a prefix
+/// that pins them in the transaction, see [`EncryptedDatabase::prefix_iter_pinned`].
+pub struct PinnedPrefix<'p, T, KC, DC, C = DefaultComparator> {
+    prefix: ManuallyDrop<RoPrefix<'p, T, Bytes, Bytes, C>>,
+    txn: &'p PinnedRoTxn<'p, T>,
+    database: Database<KC, DC, C>,
+}
+
+impl<'p, T, KC, DC, C> PinnedPrefix<'p, T, KC, DC, C> {
+    pub(crate) fn new(
+        prefix: RoPrefix<'p, T, Bytes, Bytes, C>,
+        txn: &'p PinnedRoTxn<'p, T>,
+        database: Database<KC, DC, C>,
+    ) -> PinnedPrefix<'p, T, KC, DC, C> {
+        PinnedPrefix { prefix: ManuallyDrop::new(prefix), txn, database }
+    }
+}
+
+impl<'p, T, KC, DC, C> Iterator for PinnedPrefix<'p, T, KC, DC, C>
+where
+    KC: BytesDecode<'p>,
+    DC: BytesDecode<'p>,
+    C: LexicographicComparator,
+{
+    type Item = Result<(KC::DItem, DC::DItem)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut pins = self.txn.lock();
+        let entry = self.prefix.next()?;
+        Some(entry.and_then(|(key, data)| pin_and_decode(&self.database, &mut pins, key, data)))
+    }
+}
+
+impl<T, KC, DC, C> Drop for PinnedPrefix<'_, T, KC, DC, C> {
+    fn drop(&mut self) {
+        // Closing the cursor releases the pages it decrypted.
+        let _pins = self.txn.lock();
+        unsafe { ManuallyDrop::drop(&mut self.prefix) }
+    }
+}
+
+/// Pins the raw entry in the transaction and decodes it,
+/// the decoded entry is valid for the whole transaction.
+pub(crate) fn pin_and_decode<'p, T, KC, DC, C>(
+    database: &Database<KC, DC, C>,
+    pins: &mut PinGuard<'p, '_, T>,
+    key: &[u8],
+    data: &[u8],
+) -> Result<(KC::DItem, DC::DItem)>
+where
+    KC: BytesDecode<'p>,
+    DC: BytesDecode<'p>,
+{
+    let (key, data) = (pins.pin(key), pins.pin(data));
+    match (KC::bytes_decode(key), DC::bytes_decode(data)) {
+        (Ok(key), Ok(data)) => Ok((key, data)),
+        (Err(e), _) => Err(database.key_decoding_error(key, e)),
+        (_, Err(e)) => Err(database.data_decoding_error(data, e)),
+    }
+}
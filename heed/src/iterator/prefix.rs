@@ -153,7 +153,12 @@ where
                 if key.starts_with(&self.prefix) {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                         (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                        (Err(e), _) => {
+                            Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e)))
+                        }
+                        (_, Err(e)) => {
+                            Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                        }
                     }
                 } else {
                     None
@@ -185,7 +190,12 @@ where
                 if key.starts_with(&self.prefix) {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                         (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                        (Err(e), _) => {
+                            Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e)))
+                        }
+                        (_, Err(e)) => {
+                            Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                        }
                     }
                 } else {
                     None
@@ -267,8 +277,10 @@ impl<'txn, KC, DC, C, IM> RwPrefix<'txn, KC, DC, C, IM> {
         KC: BytesEncode<'a>,
         DC: BytesEncode<'a>,
     {
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let data_bytes: Cow<[u8]> = DC::bytes_encode(data).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key)
+            .map_err(|e| self.cursor.encoding_error::<KC>(EntrySide::Key, e))?;
+        let data_bytes: Cow<[u8]> = DC::bytes_encode(data)
+            .map_err(|e| self.cursor.encoding_error::<DC>(EntrySide::Data, e))?;
         self.cursor.put_current(&key_bytes, &data_bytes)
     }
 
@@ -296,7 +308,8 @@ impl<'txn, KC, DC, C, IM> RwPrefix<'txn, KC, DC, C, IM> {
         KC: BytesEncode<'a>,
         F: FnOnce(&mut ReservedSpace) -> io::Result<()>,
     {
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key)
+            .map_err(|e| self.cursor.encoding_error::<KC>(EntrySide::Key, e))?;
         self.cursor.put_current_reserved_with_flags(flags, &key_bytes, data_size, write_func)
     }
 
@@ -327,8 +340,10 @@ impl<'txn, KC, DC, C, IM> RwPrefix<'txn, KC, DC, C, IM> {
         KC: BytesEncode<'a>,
         NDC: BytesEncode<'a>,
     {
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let data_bytes: Cow<[u8]> = NDC::bytes_encode(data).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key)
+            .map_err(|e| self.cursor.encoding_error::<KC>(EntrySide::Key, e))?;
+        let data_bytes: Cow<[u8]> = NDC::bytes_encode(data)
+            .map_err(|e| self.cursor.encoding_error::<NDC>(EntrySide::Data, e))?;
         self.cursor.put_current_with_flags(flags, &key_bytes, &data_bytes)
     }
 
@@ -406,7 +421,12 @@ where
                 if key.starts_with(&self.prefix) {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                         (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                        (Err(e), _) => {
+                            Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e)))
+                        }
+                        (_, Err(e)) => {
+                            Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                        }
                     }
                 } else {
                     None
@@ -438,7 +458,12 @@ where
                 if key.starts_with(&self.prefix) {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                         (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                        (Err(e), _) => {
+                            Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e)))
+                        }
+                        (_, Err(e)) => {
+                            Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                        }
                     }
                 } else {
                     None
@@ -546,7 +571,12 @@ where
                 if key.starts_with(&self.prefix) {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                         (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                        (Err(e), _) => {
+                            Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e)))
+                        }
+                        (_, Err(e)) => {
+                            Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                        }
                     }
                 } else {
                     None
@@ -577,7 +607,12 @@ where
                 if key.starts_with(&self.prefix) {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                         (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                        (Err(e), _) => {
+                            Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e)))
+                        }
+                        (_, Err(e)) => {
+                            Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                        }
                     }
                 } else {
                     None
@@ -659,8 +694,10 @@ impl<'txn, KC, DC, C, IM> RwRevPrefix<'txn, KC, DC, C, IM> {
         KC: BytesEncode<'a>,
         DC: BytesEncode<'a>,
     {
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let data_bytes: Cow<[u8]> = DC::bytes_encode(data).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key)
+            .map_err(|e| self.cursor.encoding_error::<KC>(EntrySide::Key, e))?;
+        let data_bytes: Cow<[u8]> = DC::bytes_encode(data)
+            .map_err(|e| self.cursor.encoding_error::<DC>(EntrySide::Data, e))?;
         self.cursor.put_current(&key_bytes, &data_bytes)
     }
 
@@ -688,7 +725,8 @@ impl<'txn, KC, DC, C, IM> RwRevPrefix<'txn, KC, DC, C, IM> {
         KC: BytesEncode<'a>,
         F: FnOnce(&mut ReservedSpace) -> io::Result<()>,
     {
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key)
+            .map_err(|e| self.cursor.encoding_error::<KC>(EntrySide::Key, e))?;
         self.cursor.put_current_reserved_with_flags(flags, &key_bytes, data_size, write_func)
     }
 
@@ -719,8 +757,10 @@ impl<'txn, KC, DC, C, IM> RwRevPrefix<'txn, KC, DC, C, IM> {
         KC: BytesEncode<'a>,
         NDC: BytesEncode<'a>,
     {
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let data_bytes: Cow<[u8]> = NDC::bytes_encode(data).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key)
+            .map_err(|e| self.cursor.encoding_error::<KC>(EntrySide::Key, e))?;
+        let data_bytes: Cow<[u8]> = NDC::bytes_encode(data)
+            .map_err(|e| self.cursor.encoding_error::<NDC>(EntrySide::Data, e))?;
         self.cursor.put_current_with_flags(flags, &key_bytes, &data_bytes)
     }
 
@@ -798,7 +838,12 @@ where
                 if key.starts_with(&self.prefix) {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                         (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                        (Err(e), _) => {
+                            Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e)))
+                        }
+                        (_, Err(e)) => {
+                            Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                        }
                     }
                 } else {
                     None
@@ -829,7 +874,12 @@ where
                 if key.starts_with(&self.prefix) {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                         (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                        (Err(e), _) => {
+                            Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e)))
+                        }
+                        (_, Err(e)) => {
+                            Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                        }
                     }
                 } else {
                     None
@@ -146,7 +146,12 @@ where
                 if must_be_returned {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                         (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                        (Err(e), _) => {
+                            Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e)))
+                        }
+                        (_, Err(e)) => {
+                            Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                        }
                     }
                 } else {
                     None
@@ -181,7 +186,12 @@ where
                 if must_be_returned {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                         (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                        (Err(e), _) => {
+                            Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e)))
+                        }
+                        (_, Err(e)) => {
+                            Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                        }
                     }
                 } else {
                     None
@@ -274,8 +284,10 @@ impl<'txn, KC, DC, C, IM> RwRange<'txn, KC, DC, C, IM> {
         KC: BytesEncode<'a>,
         DC: BytesEncode<'a>,
     {
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let data_bytes: Cow<[u8]> = DC::bytes_encode(data).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key)
+            .map_err(|e| self.cursor.encoding_error::<KC>(EntrySide::Key, e))?;
+        let data_bytes: Cow<[u8]> = DC::bytes_encode(data)
+            .map_err(|e| self.cursor.encoding_error::<DC>(EntrySide::Data, e))?;
         self.cursor.put_current(&key_bytes, &data_bytes)
     }
 
@@ -303,7 +315,8 @@ impl<'txn, KC, DC, C, IM> RwRange<'txn, KC, DC, C, IM> {
         KC: BytesEncode<'a>,
         F: FnOnce(&mut ReservedSpace) -> io::Result<()>,
     {
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key)
+            .map_err(|e| self.cursor.encoding_error::<KC>(EntrySide::Key, e))?;
         self.cursor.put_current_reserved_with_flags(flags, &key_bytes, data_size, write_func)
     }
 
@@ -334,8 +347,10 @@ impl<'txn, KC, DC, C, IM> RwRange<'txn, KC, DC, C, IM> {
         KC: BytesEncode<'a>,
         NDC: BytesEncode<'a>,
     {
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let data_bytes: Cow<[u8]> = NDC::bytes_encode(data).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key)
+            .map_err(|e| self.cursor.encoding_error::<KC>(EntrySide::Key, e))?;
+        let data_bytes: Cow<[u8]> = NDC::bytes_encode(data)
+            .map_err(|e| self.cursor.encoding_error::<NDC>(EntrySide::Data, e))?;
         self.cursor.put_current_with_flags(flags, &key_bytes, &data_bytes)
     }
 
@@ -422,7 +437,12 @@ where
                 if must_be_returned {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                         (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                        (Err(e), _) => {
+                            Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e)))
+                        }
+                        (_, Err(e)) => {
+                            Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                        }
                     }
                 } else {
                     None
@@ -457,7 +477,12 @@ where
                 if must_be_returned {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                         (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                        (Err(e), _) => {
+                            Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e)))
+                        }
+                        (_, Err(e)) => {
+                            Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                        }
                     }
                 } else {
                     None
@@ -582,7 +607,12 @@ where
                 if must_be_returned {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                         (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                        (Err(e), _) => {
+                            Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e)))
+                        }
+                        (_, Err(e)) => {
+                            Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                        }
                     }
                 } else {
                     None
@@ -619,7 +649,12 @@ where
                 if must_be_returned {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                         (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                        (Err(e), _) => {
+                            Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e)))
+                        }
+                        (_, Err(e)) => {
+                            Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                        }
                     }
                 } else {
                     None
@@ -712,8 +747,10 @@ impl<'txn, KC, DC, C, IM> RwRevRange<'txn, KC, DC, C, IM> {
         KC: BytesEncode<'a>,
         DC: BytesEncode<'a>,
     {
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let data_bytes: Cow<[u8]> = DC::bytes_encode(data).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key)
+            .map_err(|e| self.cursor.encoding_error::<KC>(EntrySide::Key, e))?;
+        let data_bytes: Cow<[u8]> = DC::bytes_encode(data)
+            .map_err(|e| self.cursor.encoding_error::<DC>(EntrySide::Data, e))?;
         self.cursor.put_current(&key_bytes, &data_bytes)
     }
 
@@ -741,7 +778,8 @@ impl<'txn, KC, DC, C, IM> RwRevRange<'txn, KC, DC, C, IM> {
         KC: BytesEncode<'a>,
         F: FnOnce(&mut ReservedSpace) -> io::Result<()>,
     {
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key)
+            .map_err(|e| self.cursor.encoding_error::<KC>(EntrySide::Key, e))?;
         self.cursor.put_current_reserved_with_flags(flags, &key_bytes, data_size, write_func)
     }
 
@@ -772,8 +810,10 @@ impl<'txn, KC, DC, C, IM> RwRevRange<'txn, KC, DC, C, IM> {
         KC: BytesEncode<'a>,
         NDC: BytesEncode<'a>,
     {
-        let key_bytes: Cow<[u8]> = KC::bytes_encode(key).map_err(Error::Encoding)?;
-        let data_bytes: Cow<[u8]> = NDC::bytes_encode(data).map_err(Error::Encoding)?;
+        let key_bytes: Cow<[u8]> = KC::bytes_encode(key)
+            .map_err(|e| self.cursor.encoding_error::<KC>(EntrySide::Key, e))?;
+        let data_bytes: Cow<[u8]> = NDC::bytes_encode(data)
+            .map_err(|e| self.cursor.encoding_error::<NDC>(EntrySide::Data, e))?;
         self.cursor.put_current_with_flags(flags, &key_bytes, &data_bytes)
     }
 
@@ -860,7 +900,12 @@ where
                 if must_be_returned {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                         (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                        (Err(e), _) => {
+                            Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e)))
+                        }
+                        (_, Err(e)) => {
+                            Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                        }
                     }
                 } else {
                     None
@@ -897,7 +942,12 @@ where
                 if must_be_returned {
                     match (KC::bytes_decode(key), DC::bytes_decode(data)) {
                         (Ok(key), Ok(data)) => Some(Ok((key, data))),
-                        (Err(e), _) | (_, Err(e)) => Some(Err(Error::Decoding(e))),
+                        (Err(e), _) => {
+                            Some(Err(self.cursor.decoding_error::<KC>(EntrySide::Key, key, e)))
+                        }
+                        (_, Err(e)) => {
+                            Some(Err(self.cursor.decoding_error::<DC>(EntrySide::Data, data, e)))
+                        }
                     }
                 } else {
                     None
@@ -69,6 +69,10 @@
 //! ```
 #![warn(missing_docs)]
 
+mod change_feed;
+#[cfg(master3)]
+pub mod checksum;
+mod codec_error;
 pub mod cookbook;
 mod cursor;
 mod databases;
@@ -76,25 +80,44 @@ mod envs;
 pub mod iteration_method;
 mod iterator;
 mod mdb;
+pub mod migrate;
+pub mod replication;
 mod reserved_space;
 mod txn;
 
 use std::ffi::CStr;
 use std::{error, fmt, io, mem, result};
 
+#[cfg(feature = "derive")]
+pub use heed_derive::{HeedCodec, HeedKey};
 use heed_traits as traits;
 pub use {byteorder, heed_types as types};
 
+pub use self::change_feed::{ChangeBatch, ChangeOp};
+#[cfg(master3)]
+pub use self::checksum::PageChecksum;
+pub use self::codec_error::{DecodingError, EncodingError, EntrySide};
 use self::cursor::{RoCursor, RwCursor};
-pub use self::databases::{Database, DatabaseOpenOptions, DatabaseStat};
+pub use self::databases::{
+    Database, DatabaseFingerprint, DatabaseOpenOptions, DatabaseStat, FingerprintMismatch,
+    IndexKey, IndexedDatabase, IndexedEntries, KvDatabase, ValidationFailure, ValidationReport,
+};
 #[cfg(master3)]
 pub use self::databases::{EncryptedDatabase, EncryptedDatabaseOpenOptions};
+#[cfg(feature = "serde-json")]
+pub use self::databases::{JsonlDecode, JsonlEncode, JsonlSerde};
 #[cfg(master3)]
-pub use self::envs::EncryptedEnv;
+#[cfg(feature = "password")]
+pub use self::envs::KdfParams;
 pub use self::envs::{
-    env_closing_event, CompactionOption, DefaultComparator, Env, EnvClosingEvent, EnvInfo,
-    EnvOpenOptions, FlagSetMode, IntegerComparator,
+    env_closing_event, CompactionError, CompactionOption, DatabaseVerifyReport, DefaultComparator,
+    DumpFormat, Env, EnvClosingEvent, EnvInfo, EnvOpenOptions, FlagSetMode, IntegerComparator,
+    ReaderInfo, VerifyProblem, VerifyReport,
 };
+#[cfg(master3)]
+pub use self::envs::{EncryptedEnv, EncryptionOptions, PageCipher};
+#[cfg(master3)]
+pub use self::iterator::{PinnedIter, PinnedPrefix, PinnedRange};
 pub use self::iterator::{
     RoIter, RoPrefix, RoRange, RoRevIter, RoRevPrefix, RoRevRange, RwIter, RwPrefix, RwRange,
     RwRevIter, RwRevPrefix, RwRevRange,
@@ -104,6 +127,8 @@ use self::mdb::ffi::{from_val, into_val};
 pub use self::mdb::flags::{DatabaseFlags, EnvFlags, PutFlags};
 pub use self::reserved_space::ReservedSpace;
 pub use self::traits::{BoxedError, BytesDecode, BytesEncode, Comparator, LexicographicComparator};
+#[cfg(master3)]
+pub use self::txn::PinnedRoTxn;
 pub use self::txn::{RoTxn, RwTxn, TlsUsage, WithTls, WithoutTls};
 
 /// The underlying LMDB library version information.
@@ -165,13 +190,22 @@ pub enum Error {
     Io(io::Error),
     /// LMDB error.
     Mdb(MdbError),
-    /// Encoding error.
-    Encoding(BoxedError),
-    /// Decoding error.
-    Decoding(BoxedError),
+    /// Encoding error, with the database and the codec that failed.
+    Encoding(EncodingError),
+    /// Decoding error, with the database, the codec that failed and the bytes.
+    Decoding(DecodingError),
     /// The environment is already open in this program;
     /// close it to be able to open it again with different options.
     EnvAlreadyOpened,
+    /// The database was created with different codecs, comparator or flags,
+    /// see [`DatabaseOpenOptions::fingerprint`].
+    FingerprintMismatch(Box<FingerprintMismatch>),
+    /// The key used to open an encrypted environment is not the one it was encrypted with.
+    ///
+    /// It is checked when the environment is opened, a page that cannot be decrypted with
+    /// the right key is corrupted and reported as an [`MdbError::CryptoFail`].
+    #[cfg(master3)]
+    InvalidEncryptionKey,
 }
 
 impl fmt::Display for Error {
@@ -179,12 +213,17 @@ impl fmt::Display for Error {
         match self {
             Error::Io(error) => write!(f, "{error}"),
             Error::Mdb(error) => write!(f, "{error}"),
-            Error::Encoding(error) => write!(f, "error while encoding: {error}"),
-            Error::Decoding(error) => write!(f, "error while decoding: {error}"),
+            Error::Encoding(error) => write!(f, "{error}"),
+            Error::Decoding(error) => write!(f, "{error}"),
             Error::EnvAlreadyOpened => f.write_str(
                 "environment already open in this program; \
                 close it to be able to open it again with different options",
             ),
+            Error::FingerprintMismatch(mismatch) => write!(f, "{mismatch}"),
+            #[cfg(master3)]
+            Error::InvalidEncryptionKey => f.write_str(
+                "invalid encryption key; the environment was encrypted with another key",
+            ),
         }
     }
 }
@@ -245,7 +284,11 @@ mod tests {
     fn error_is_send_sync() {
         fn give_me_send_sync<T: Send + Sync>(_: T) {}
 
-        let error = Error::Encoding(Box::from("There is an issue, you know?"));
+        let error = codec_error::encoding_error::<types::Str>(
+            None,
+            EntrySide::Key,
+            Box::from("There is an issue, you know?"),
+        );
         give_me_send_sync(error);
     }
 }
@@ -38,6 +38,13 @@ bitflags! {
         const NO_READ_AHEAD = ffi::MDB_NORDAHEAD;
         /// Don't initialize malloc'd memory before writing to datafile.
         const NO_MEM_INIT = ffi::MDB_NOMEMINIT;
+        /// Open the previous snapshot rather than the latest one,
+        /// see [`EnvOpenOptions::open_previous_snapshot`](crate::EnvOpenOptions::open_previous_snapshot).
+        const PREV_SNAPSHOT = ffi::MDB_PREVSNAPSHOT;
+        /// Map the data file in chunks rather than as a whole,
+        /// see [`EnvOpenOptions::remap_chunks`](crate::EnvOpenOptions::remap_chunks).
+        #[cfg(master3)]
+        const REMAP_CHUNKS = ffi::MDB_REMAP_CHUNKS;
     }
 }
 
@@ -0,0 +1,393 @@
+//! Apply ordered schema migrations to an environment and remember the applied ones.
+//!
+//! The migrations are registered, in order, in a [`Migrations`] list and applied with
+//! [`Env::migrate`]. The number of migrations applied, the schema version, is stored under
+//! a reserved key of the unnamed database, a key that can't be the name of a database.
+//! Only the migrations registered after this version are run by [`Env::migrate`].
+//!
+//! ```
+//! use heed::migrate::{reencode, Migrations};
+//! use heed::types::*;
+//! use heed::byteorder::{BigEndian, LittleEndian};
+//! use heed::{Database, EnvOpenOptions};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let dir = tempfile::tempdir()?;
+//! let env = unsafe { EnvOpenOptions::new().max_dbs(10).open(dir.path())? };
+//!
+//! let mut migrations = Migrations::new();
+//! migrations.add("create the scores", |wtxn| {
+//!     let db: Database<Str, U32<LittleEndian>> = env.create_database(wtxn, Some("scores"))?;
+//!     db.put(wtxn, "ferris", &42)?;
+//!     Ok(())
+//! });
+//! migrations.add("store the scores in big-endian", |wtxn| {
+//!     let db: Database<Str, U32<LittleEndian>> = env.create_database(wtxn, Some("scores"))?;
+//!     let _db: Database<Str, U32<BigEndian>> = reencode(wtxn, db)?;
+//!     Ok(())
+//! });
+//!
+//! let report = env.migrate(&migrations)?;
+//! assert_eq!((report.previous_version, report.current_version), (0, 2));
+//!
+//! // The migrations already applied are skipped.
+//! let report = env.migrate(&migrations)?;
+//! assert!(report.applied.is_empty());
+//! # Ok(()) }
+//! ```
+
+use std::borrow::{Borrow, Cow};
+use std::{fmt, io};
+
+use byteorder::BigEndian;
+use types::{Bytes, U64};
+
+use crate::codec_error::{decoding_error, encoding_error};
+use crate::*;
+
+/// The key of the unnamed database storing the schema version, the NUL byte
+/// prevents it from being the name of a database.
+const SCHEMA_VERSION_KEY: &[u8] = b"\0heed.schema-version";
+
+/// How the pending migrations are run by [`Env::migrate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MigrationMode {
+    /// Every pending migration runs in the same write transaction: if one
+    /// of them fails, none of the pending migrations is applied.
+    #[default]
+    AllOrNothing,
+    /// Every pending migration runs in its own nested transaction: if one of them fails,
+    /// the ones that succeeded before it are committed and the error is returned.
+    Nested,
+}
+
+/// The ordered list of migrations to apply with [`Env::migrate`].
+///
+/// A migration must never be removed nor reordered once it has been
+/// applied to an environment, new migrations must be added at the end.
+#[derive(Default)]
+pub struct Migrations<'m> {
+    migrations: Vec<Migration<'m>>,
+    mode: MigrationMode,
+    dry_run: bool,
+}
+
+type MigrationFn<'m> = dyn Fn(&mut RwTxn) -> Result<()> + 'm;
+
+struct Migration<'m> {
+    name: String,
+    run: Box<MigrationFn<'m>>,
+}
+
+impl<'m> Migrations<'m> {
+    /// Creates an empty list of migrations.
+    pub fn new() -> Migrations<'m> {
+        Migrations::default()
+    }
+
+    /// Appends a migration, the schema version reached once it has been applied
+    /// is its position in this list, starting at one.
+    pub fn add<F>(&mut self, name: impl Into<String>, migration: F) -> &mut Self
+    where
+        F: Fn(&mut RwTxn) -> Result<()> + 'm,
+    {
+        self.migrations.push(Migration { name: name.into(), run: Box::new(migration) });
+        self
+    }
+
+    /// Sets how the pending migrations are run, [`MigrationMode::AllOrNothing`] by default.
+    pub fn mode(&mut self, mode: MigrationMode) -> &mut Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Runs the pending migrations and aborts the transaction
+    /// instead of committing it, `false` by default.
+    pub fn dry_run(&mut self, dry_run: bool) -> &mut Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// The number of migrations, the schema version once all of them are applied.
+    pub fn len(&self) -> usize {
+        self.migrations.len()
+    }
+
+    /// Returns `true` if there is no migration.
+    pub fn is_empty(&self) -> bool {
+        self.migrations.is_empty()
+    }
+}
+
+impl fmt::Debug for Migrations<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<_> = self.migrations.iter().map(|m| &m.name).collect();
+        f.debug_struct("Migrations")
+            .field("migrations", &names)
+            .field("mode", &self.mode)
+            .field("dry_run", &self.dry_run)
+            .finish()
+    }
+}
+
+/// The result of [`Env::migrate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// The schema version before running the migrations.
+    pub previous_version: u64,
+    /// The schema version after running the migrations,
+    /// not persisted when running in dry-run mode.
+    pub current_version: u64,
+    /// The names of the migrations applied, in order.
+    pub applied: Vec<String>,
+    /// Whether the migrations were run in dry-run mode.
+    pub dry_run: bool,
+}
+
+impl<T> Env<T> {
+    /// Runs the migrations registered after the schema version of this environment
+    /// and stores the new version, see the [`migrate`](crate::migrate) module.
+    ///
+    /// Returns an error if the schema version of this environment is greater than
+    /// the number of migrations, it was probably migrated by a newer program.
+    pub fn migrate(&self, migrations: &Migrations) -> Result<MigrationReport> {
+        let mut wtxn = self.write_txn()?;
+        let main = self.create_database::<Bytes, U64<BigEndian>>(&mut wtxn, None)?;
+        let previous_version = main.get(&wtxn, SCHEMA_VERSION_KEY)?.unwrap_or(0);
+
+        let Some(pending) = migrations.migrations.get(previous_version as usize..) else {
+            let msg = format!(
+                "the schema version {previous_version} is greater than the {} known migrations",
+                migrations.len()
+            );
+            return Err(io::Error::new(io::ErrorKind::InvalidData, msg).into());
+        };
+
+        let mut report = MigrationReport {
+            previous_version,
+            current_version: previous_version,
+            applied: Vec::new(),
+            dry_run: migrations.dry_run,
+        };
+
+        let mut failure = None;
+        for migration in pending {
+            let result = match migrations.mode {
+                MigrationMode::AllOrNothing => (migration.run)(&mut wtxn),
+                MigrationMode::Nested => {
+                    let mut nested = self.nested_write_txn(&mut wtxn)?;
+                    (migration.run)(&mut nested).and_then(|()| nested.commit())
+                }
+            };
+
+            match (result, migrations.mode) {
+                (Ok(()), _) => {
+                    report.current_version += 1;
+                    report.applied.push(migration.name.clone());
+                }
+                (Err(e), MigrationMode::AllOrNothing) => return Err(e),
+                (Err(e), MigrationMode::Nested) => {
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+
+        if report.current_version != previous_version {
+            main.put(&mut wtxn, SCHEMA_VERSION_KEY, &report.current_version)?;
+        }
+
+        if migrations.dry_run {
+            wtxn.abort();
+        } else {
+            wtxn.commit()?;
+        }
+
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(report),
+        }
+    }
+}
+
+/// Returns the schema version of the environment, the number of migrations applied.
+pub fn schema_version<T>(env: &Env<T>, rtxn: &RoTxn<T>) -> Result<u64> {
+    match env.open_database::<Bytes, U64<BigEndian>>(rtxn, None)? {
+        Some(main) => Ok(main.get(rtxn, SCHEMA_VERSION_KEY)?.unwrap_or(0)),
+        None => Ok(0),
+    }
+}
+
+/// Decodes every entry of the database with its current codecs and encodes them back
+/// with the new `NKC` and `NDC` codecs, then returns the database with the new codecs.
+///
+/// The old and new codecs must handle the same items, for example when going from
+/// [`SerdeBincode<T>`](crate::types::SerdeBincode) to [`SerdeJson<T>`](crate::types::SerdeJson)
+/// or from a little-endian to a big-endian integer. The re-encoded entries are kept in
+/// memory before being written back, which makes it unsuitable for very large databases.
+pub fn reencode<OKC, ODC, NKC, NDC, C, NK, ND>(
+    wtxn: &mut RwTxn,
+    database: Database<OKC, ODC, C>,
+) -> Result<Database<NKC, NDC, C>>
+where
+    OKC: for<'a> BytesDecode<'a>,
+    ODC: for<'a> BytesDecode<'a>,
+    NKC: for<'a> BytesEncode<'a, EItem = NK>,
+    NDC: for<'a> BytesEncode<'a, EItem = ND>,
+    NK: ?Sized,
+    ND: ?Sized,
+    for<'a> <OKC as BytesDecode<'a>>::DItem: Borrow<NK>,
+    for<'a> <ODC as BytesDecode<'a>>::DItem: Borrow<ND>,
+{
+    let raw = database.remap_types::<Bytes, Bytes>();
+    let name = || database.name;
+
+    let mut entries = Vec::new();
+    for result in raw.iter(wtxn)? {
+        let (key_bytes, data_bytes) = result?;
+        let key = OKC::bytes_decode(key_bytes)
+            .map_err(|e| decoding_error::<OKC>(name(), EntrySide::Key, key_bytes, e))?;
+        let data = ODC::bytes_decode(data_bytes)
+            .map_err(|e| decoding_error::<ODC>(name(), EntrySide::Data, data_bytes, e))?;
+        let key: Cow<[u8]> = NKC::bytes_encode(key.borrow())
+            .map_err(|e| encoding_error::<NKC>(name(), EntrySide::Key, e))?;
+        let data: Cow<[u8]> = NDC::bytes_encode(data.borrow())
+            .map_err(|e| encoding_error::<NDC>(name(), EntrySide::Data, e))?;
+        entries.push((key.into_owned(), data.into_owned()));
+    }
+
+    raw.clear(wtxn)?;
+    for (key, data) in entries {
+        raw.put(wtxn, &key, &data)?;
+    }
+
+    Ok(database.remap_types())
+}
+
+#[cfg(test)]
+mod tests {
+    use types::Str;
+
+    use super::*;
+
+    fn open_env(dir: &tempfile::TempDir) -> Env<WithTls> {
+        unsafe { EnvOpenOptions::new().max_dbs(10).open(dir.path()).unwrap() }
+    }
+
+    /// A migration that writes the key in the `words` database.
+    fn put_word<'m>(env: &'m Env<WithTls>, key: &'m str) -> impl Fn(&mut RwTxn) -> Result<()> + 'm {
+        move |wtxn| {
+            let db = env.create_database::<Str, Str>(wtxn, Some("words"))?;
+            db.put(wtxn, key, key)
+        }
+    }
+
+    fn failing(_wtxn: &mut RwTxn) -> Result<()> {
+        Err(io::Error::other("the migration failed").into())
+    }
+
+    fn words(env: &Env<WithTls>) -> Vec<String> {
+        let rtxn = env.read_txn().unwrap();
+        match env.open_database::<Str, Str>(&rtxn, Some("words")).unwrap() {
+            Some(db) => db.iter(&rtxn).unwrap().map(|r| r.unwrap().0.to_owned()).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn version(env: &Env<WithTls>) -> u64 {
+        schema_version(env, &env.read_txn().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn all_or_nothing_failure_applies_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = open_env(&dir);
+
+        let mut migrations = Migrations::new();
+        migrations.add("first", put_word(&env, "first"));
+        migrations.add("failing", failing);
+        assert!(env.migrate(&migrations).is_err());
+
+        assert_eq!(version(&env), 0);
+        assert!(words(&env).is_empty());
+    }
+
+    #[test]
+    fn nested_failure_keeps_the_previous_migrations() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = open_env(&dir);
+
+        let mut migrations = Migrations::new();
+        migrations.mode(MigrationMode::Nested);
+        migrations.add("first", put_word(&env, "first"));
+        migrations.add("second then failing", |wtxn| {
+            put_word(&env, "second")(wtxn)?;
+            failing(wtxn)
+        });
+        migrations.add("third", put_word(&env, "third"));
+        assert!(env.migrate(&migrations).is_err());
+
+        assert_eq!(version(&env), 1);
+        assert_eq!(words(&env), ["first"]);
+    }
+
+    #[test]
+    fn dry_run_persists_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = open_env(&dir);
+
+        let mut migrations = Migrations::new();
+        migrations.dry_run(true);
+        migrations.add("first", put_word(&env, "first"));
+        migrations.add("second", put_word(&env, "second"));
+
+        let report = env.migrate(&migrations).unwrap();
+        assert_eq!((report.previous_version, report.current_version), (0, 2));
+        assert_eq!(report.applied, ["first", "second"]);
+        assert!(report.dry_run);
+        assert_eq!(version(&env), 0);
+        assert!(words(&env).is_empty());
+
+        // A failing dry run reports the error and persists nothing either.
+        migrations.mode(MigrationMode::Nested);
+        migrations.add("failing", failing);
+        assert!(env.migrate(&migrations).is_err());
+        assert_eq!(version(&env), 0);
+        assert!(words(&env).is_empty());
+    }
+
+    #[test]
+    fn newer_schema_version_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = open_env(&dir);
+
+        let mut migrations = Migrations::new();
+        migrations.add("first", put_word(&env, "first"));
+        migrations.add("second", put_word(&env, "second"));
+        env.migrate(&migrations).unwrap();
+
+        let mut older = Migrations::new();
+        older.add("first", put_word(&env, "first"));
+        match env.migrate(&older) {
+            Err(Error::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::InvalidData),
+            otherwise => panic!("expected an invalid data error, got {otherwise:?}"),
+        }
+        assert_eq!(version(&env), 2);
+    }
+
+    #[test]
+    fn reencode_reports_undecodable_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = open_env(&dir);
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<Bytes, Bytes>(&mut wtxn, Some("raw")).unwrap();
+        db.put(&mut wtxn, b"valid", b"utf8").unwrap();
+        db.put(&mut wtxn, b"invalid", &[0xff]).unwrap();
+
+        let typed = db.remap_types::<Bytes, Str>();
+        let result = reencode::<_, _, Bytes, Str, _, _, _>(&mut wtxn, typed);
+        assert!(matches!(result, Err(Error::Decoding(_))));
+        assert_eq!(db.len(&wtxn).unwrap(), 2);
+    }
+}
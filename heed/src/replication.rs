@@ -0,0 +1,613 @@
+//! Persist the committed write transactions in a log and replay them in other environments.
+//!
+//! When an environment is opened with [`EnvOpenOptions::replication_log`] enabled, the raw
+//! operations of every committed top-level write transaction are stored, in the same
+//! transaction, in an internal database named `heed.replication-log`. The entries are keyed
+//! by a sequence number stored in the log itself, it only grows and, unlike the transaction
+//! ID, it is not reset by a compacting copy of the environment. The entries can then be read
+//! with [`read_log`], shipped to another disk or machine and replayed into a follower
+//! environment with [`apply_log`]. Once every follower acknowledged an entry it can be
+//! dropped with [`truncate_log`].
+//!
+//! The followers remember the sequence number of the last entry they applied in an internal
+//! database named `heed.replication-state`, applying the same entry twice is a no-op.
+//!
+//! The databases opened with a custom [`Comparator`] in the leader must be opened with
+//! the same comparator in the follower before applying the entries that write to them.
+//!
+//! ```
+//! use heed::replication::{apply_log, read_log, truncate_log};
+//! use heed::types::*;
+//! use heed::{Database, EnvOpenOptions};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let leader_dir = tempfile::tempdir()?;
+//! let leader = unsafe {
+//!     EnvOpenOptions::new().max_dbs(10).replication_log(true).open(leader_dir.path())?
+//! };
+//! let follower_dir = tempfile::tempdir()?;
+//! let follower = unsafe { EnvOpenOptions::new().max_dbs(10).open(follower_dir.path())? };
+//!
+//! let mut wtxn = leader.write_txn()?;
+//! let db: Database<Str, Str> = leader.create_database(&mut wtxn, Some("words"))?;
+//! db.put(&mut wtxn, "hello", "world")?;
+//! wtxn.commit()?;
+//!
+//! let rtxn = leader.read_txn()?;
+//! let entries = read_log(&leader, &rtxn, 0, 100)?;
+//! drop(rtxn);
+//! assert_eq!(apply_log(&follower, &entries)?, 1);
+//!
+//! let rtxn = follower.read_txn()?;
+//! let db: Database<Str, Str> = follower.open_database(&rtxn, Some("words"))?.unwrap();
+//! assert_eq!(db.get(&rtxn, "hello")?, Some("world"));
+//!
+//! // The follower acknowledged every entry, we can drop them.
+//! let last_sequence = entries.last().unwrap().sequence;
+//! let mut wtxn = leader.write_txn()?;
+//! assert_eq!(truncate_log(&leader, &mut wtxn, last_sequence)?, 1);
+//! wtxn.commit()?;
+//! # Ok(()) }
+//! ```
+
+use std::collections::HashMap;
+use std::io;
+use std::ops::Bound;
+
+use byteorder::BigEndian;
+use types::{Bytes, DecodeIgnore, Str, U64};
+
+use crate::codec_error::decoding_error;
+use crate::envs::DefaultComparator;
+use crate::mdb::ffi;
+use crate::mdb::lmdb_error::mdb_result;
+use crate::mdb::lmdb_flags::AllDatabaseFlags;
+#[allow(unused)] // for cargo auto doc links
+use crate::EnvOpenOptions;
+use crate::*;
+
+/// The name of the database storing the log entries, keyed by sequence number.
+pub(crate) const LOG_DATABASE_NAME: &str = "heed.replication-log";
+/// The name of the database storing the last entry applied by a follower.
+pub(crate) const STATE_DATABASE_NAME: &str = "heed.replication-state";
+
+/// Whether the database is one of the databases used by the replication itself.
+pub(crate) fn is_internal_database(name: &str) -> bool {
+    name == LOG_DATABASE_NAME || name == STATE_DATABASE_NAME
+}
+
+/// The key of the log database storing the last sequence number given to an entry,
+/// the entries are numbered from one.
+const LAST_SEQUENCE_KEY: u64 = 0;
+const LAST_APPLIED_SEQUENCE_KEY: &str = "last-applied-sequence";
+const FORMAT_VERSION: u8 = 1;
+
+const NAMED_DATABASE: u8 = 0b01;
+const CUSTOM_COMPARATOR_DATABASE: u8 = 0b10;
+
+const PUT_OP: u8 = 0;
+const DELETE_OP: u8 = 1;
+const DELETE_DUPLICATE_OP: u8 = 2;
+const CLEAR_OP: u8 = 3;
+
+/// The operations committed by a single write transaction, as stored in the log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    /// The sequence number of the entry in the log, it only grows.
+    pub sequence: u64,
+    /// The databases written by the operations,
+    /// used to create them with the same flags in the followers.
+    pub databases: Vec<LogDatabase>,
+    /// The operations, in the order they were performed in the transaction.
+    pub ops: Vec<ChangeOp>,
+}
+
+/// A database written by the operations of a [`LogEntry`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogDatabase {
+    /// The name of the database, `None` being the unnamed database.
+    pub name: Option<String>,
+    /// The flags of the database in the leader environment.
+    pub flags: DatabaseFlags,
+    /// Whether the database has been opened with a custom key [`Comparator`] in the leader.
+    pub custom_comparator: bool,
+}
+
+/// Returns at most `max_entries` log entries following the entry numbered `after_sequence`,
+/// ordered by sequence number.
+///
+/// Give zero as `after_sequence` to read the log from the start.
+pub fn read_log<T>(
+    env: &Env<T>,
+    rtxn: &RoTxn<T>,
+    after_sequence: u64,
+    max_entries: usize,
+) -> Result<Vec<LogEntry>> {
+    let Some(db) = env.open_database::<U64<BigEndian>, Bytes>(rtxn, Some(LOG_DATABASE_NAME))?
+    else {
+        return Ok(Vec::new());
+    };
+
+    let range = (Bound::Excluded(after_sequence), Bound::Unbounded);
+    let mut entries = Vec::new();
+    for result in db.range(rtxn, &range)?.take(max_entries) {
+        let (sequence, bytes) = result?;
+        let (databases, ops) = decode_entry(bytes).map_err(|e| {
+            decoding_error::<LogEntry>(Some(LOG_DATABASE_NAME), EntrySide::Data, bytes, e)
+        })?;
+        entries.push(LogEntry { sequence, databases, ops });
+    }
+
+    Ok(entries)
+}
+
+/// Removes the log entries up to and including the one numbered `up_to_sequence`,
+/// returns the number of entries removed.
+///
+/// Call it once every follower has applied those entries.
+pub fn truncate_log<T>(env: &Env<T>, wtxn: &mut RwTxn, up_to_sequence: u64) -> Result<usize> {
+    let db = env.create_database::<U64<BigEndian>, DecodeIgnore>(wtxn, Some(LOG_DATABASE_NAME))?;
+    db.delete_range(wtxn, &(LAST_SEQUENCE_KEY + 1..=up_to_sequence))
+}
+
+/// Returns the sequence number of the last log entry applied to this follower environment.
+pub fn last_applied_sequence<T>(env: &Env<T>, rtxn: &RoTxn<T>) -> Result<Option<u64>> {
+    match env.open_database::<Str, U64<BigEndian>>(rtxn, Some(STATE_DATABASE_NAME))? {
+        Some(db) => db.get(rtxn, LAST_APPLIED_SEQUENCE_KEY),
+        None => Ok(None),
+    }
+}
+
+/// Replays the log entries into the follower environment in a single write transaction,
+/// returns the number of entries applied.
+///
+/// The entries that were already applied to this environment are skipped. The databases
+/// are created with the flags they have in the leader environment. The databases using a
+/// custom key comparator in the leader are never created, they must have been opened with
+/// the same comparator in this environment beforehand, an error is returned otherwise.
+pub fn apply_log<'a, T, I>(env: &Env<T>, entries: I) -> Result<usize>
+where
+    I: IntoIterator<Item = &'a LogEntry>,
+{
+    let mut wtxn = env.write_txn()?;
+    let state = env.create_database::<Str, U64<BigEndian>>(&mut wtxn, Some(STATE_DATABASE_NAME))?;
+    let mut last_applied = state.get(&wtxn, LAST_APPLIED_SEQUENCE_KEY)?;
+
+    let mut applied = 0;
+    for entry in entries {
+        if last_applied.is_some_and(|last| entry.sequence <= last) {
+            continue;
+        }
+
+        let mut databases = HashMap::new();
+        for LogDatabase { name, flags, custom_comparator } in &entry.databases {
+            let opened_with_custom_comparator = open_database(env, &wtxn, name.as_deref())?
+                .is_some_and(|dbi| env.change_feed().has_custom_comparator(dbi));
+            if *custom_comparator && !opened_with_custom_comparator {
+                let msg = format!(
+                    "the {name:?} database uses a custom comparator, \
+                     open it with the same comparator before applying the log"
+                );
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, msg).into());
+            }
+
+            // The comparator of a database stays the one it was first opened with.
+            let mut options = env.database_options().types::<Bytes, Bytes>();
+            if let Some(name) = name {
+                options.name(name);
+            }
+            options.flags(*flags);
+            databases.insert(name.as_deref(), options.create(&mut wtxn)?);
+        }
+
+        for op in &entry.ops {
+            let db = match databases.get(&op.database()) {
+                Some(db) => db,
+                None => {
+                    let msg = "unknown database in a log entry";
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, msg).into());
+                }
+            };
+            match op {
+                ChangeOp::Put { key, data, .. } => db.put(&mut wtxn, key, data)?,
+                ChangeOp::Delete { key, data: None, .. } => {
+                    db.delete(&mut wtxn, key)?;
+                }
+                ChangeOp::Delete { key, data: Some(data), .. } => {
+                    db.delete_one_duplicate(&mut wtxn, key, data)?;
+                }
+                ChangeOp::Clear { .. } => db.clear(&mut wtxn)?,
+            }
+        }
+
+        last_applied = Some(entry.sequence);
+        applied += 1;
+    }
+
+    if let Some(last) = last_applied {
+        state.put(&mut wtxn, LAST_APPLIED_SEQUENCE_KEY, &last)?;
+    }
+    wtxn.commit()?;
+
+    Ok(applied)
+}
+
+/// Stores the operations of a top-level write transaction that is about to be committed.
+pub(crate) fn append_log_entry(
+    env: &Env<WithoutTls>,
+    wtxn: &mut RwTxn,
+    ops: &[ChangeOp],
+) -> Result<()> {
+    let mut raw_txn = wtxn.txn.txn.unwrap();
+    let dbi = env.raw_init_database::<DefaultComparator, _>(
+        wtxn,
+        Some(LOG_DATABASE_NAME),
+        AllDatabaseFlags::CREATE,
+    )?;
+
+    let mut databases: Vec<LogDatabase> = Vec::new();
+    for op in ops {
+        let name = op.database();
+        if databases.iter().any(|database| database.name.as_deref() == name) {
+            continue;
+        }
+        let Some(dbi) = open_database(env, wtxn, name)? else {
+            let msg = format!("the {name:?} database doesn't exist in this environment");
+            return Err(io::Error::new(io::ErrorKind::NotFound, msg).into());
+        };
+        let custom_comparator = env.change_feed().has_custom_comparator(dbi);
+        let mut flags = 0;
+        unsafe { mdb_result(ffi::mdb_dbi_flags(raw_txn.as_mut(), dbi, &mut flags))? };
+        databases.push(LogDatabase {
+            name: name.map(ToOwned::to_owned),
+            flags: DatabaseFlags::from_bits_truncate(flags),
+            custom_comparator,
+        });
+    }
+
+    let bytes = encode_entry(&databases, ops);
+    let db = Database::<U64<BigEndian>, Bytes>::new(
+        env.env_mut_ptr().as_ptr() as _,
+        dbi,
+        Some(LOG_DATABASE_NAME),
+    );
+    let last_sequence = match db.get(wtxn, &LAST_SEQUENCE_KEY)? {
+        Some(bytes) => bytes.try_into().map(u64::from_be_bytes).map_err(|_| {
+            let msg = "invalid last sequence number in the replication log";
+            io::Error::new(io::ErrorKind::InvalidData, msg)
+        })?,
+        None => LAST_SEQUENCE_KEY,
+    };
+    let sequence = last_sequence + 1;
+    db.put(wtxn, &LAST_SEQUENCE_KEY, &sequence.to_be_bytes())?;
+    db.put(wtxn, &sequence, &bytes)
+}
+
+/// Opens a database by its name in the write transaction, `None` if it doesn't exist.
+///
+/// A database already opened keeps its comparator, whether it is a custom one is then
+/// known by the change feed of the environment.
+fn open_database<T>(
+    env: &Env<T>,
+    wtxn: &RwTxn,
+    name: Option<&str>,
+) -> Result<Option<ffi::MDB_dbi>> {
+    match env.raw_init_database::<DefaultComparator, _>(wtxn, name, AllDatabaseFlags::empty()) {
+        Ok(dbi) => Ok(Some(dbi)),
+        Err(Error::Mdb(e)) if e.not_found() => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Encodes the databases and the operations of an entry.
+///
+/// The format is a version byte followed by the table of databases (name, flags and whether
+/// it uses a custom comparator) then by the operations, referencing the databases by their index in the table.
+/// Every length and index is a big-endian `u32`.
+fn encode_entry(databases: &[LogDatabase], ops: &[ChangeOp]) -> Vec<u8> {
+    fn push_u32(out: &mut Vec<u8>, n: usize) {
+        out.extend_from_slice(&(n as u32).to_be_bytes());
+    }
+
+    fn push_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+        push_u32(out, bytes.len());
+        out.extend_from_slice(bytes);
+    }
+
+    let mut out = vec![FORMAT_VERSION];
+    push_u32(&mut out, databases.len());
+    for LogDatabase { name, flags, custom_comparator } in databases {
+        let mut kind = 0;
+        if name.is_some() {
+            kind |= NAMED_DATABASE;
+        }
+        if *custom_comparator {
+            kind |= CUSTOM_COMPARATOR_DATABASE;
+        }
+        out.push(kind);
+        if let Some(name) = name {
+            push_bytes(&mut out, name.as_bytes());
+        }
+        out.extend_from_slice(&flags.bits().to_be_bytes());
+    }
+
+    push_u32(&mut out, ops.len());
+    for op in ops {
+        let kind = match op {
+            ChangeOp::Put { .. } => PUT_OP,
+            ChangeOp::Delete { data: None, .. } => DELETE_OP,
+            ChangeOp::Delete { data: Some(_), .. } => DELETE_DUPLICATE_OP,
+            ChangeOp::Clear { .. } => CLEAR_OP,
+        };
+        out.push(kind);
+        let index = databases.iter().position(|db| db.name.as_deref() == op.database()).unwrap();
+        push_u32(&mut out, index);
+        match op {
+            ChangeOp::Put { key, data, .. } | ChangeOp::Delete { key, data: Some(data), .. } => {
+                push_bytes(&mut out, key);
+                push_bytes(&mut out, data);
+            }
+            ChangeOp::Delete { key, data: None, .. } => push_bytes(&mut out, key),
+            ChangeOp::Clear { .. } => (),
+        }
+    }
+
+    out
+}
+
+type DecodedEntry = (Vec<LogDatabase>, Vec<ChangeOp>);
+
+fn decode_entry(mut bytes: &[u8]) -> std::result::Result<DecodedEntry, BoxedError> {
+    fn take<'a>(bytes: &mut &'a [u8], len: usize) -> std::result::Result<&'a [u8], BoxedError> {
+        if bytes.len() < len {
+            return Err("truncated replication log entry".into());
+        }
+        let (head, tail) = bytes.split_at(len);
+        *bytes = tail;
+        Ok(head)
+    }
+
+    fn take_u8(bytes: &mut &[u8]) -> std::result::Result<u8, BoxedError> {
+        Ok(take(bytes, 1)?[0])
+    }
+
+    fn take_u32(bytes: &mut &[u8]) -> std::result::Result<u32, BoxedError> {
+        Ok(u32::from_be_bytes(take(bytes, 4)?.try_into().unwrap()))
+    }
+
+    fn take_bytes(bytes: &mut &[u8]) -> std::result::Result<Vec<u8>, BoxedError> {
+        let len = take_u32(bytes)? as usize;
+        take(bytes, len).map(ToOwned::to_owned)
+    }
+
+    if take_u8(&mut bytes)? != FORMAT_VERSION {
+        return Err("unsupported replication log entry version".into());
+    }
+
+    let count = take_u32(&mut bytes)?;
+    let mut databases = Vec::new();
+    for _ in 0..count {
+        let kind = take_u8(&mut bytes)?;
+        let name = match kind & NAMED_DATABASE {
+            0 => None,
+            _ => Some(String::from_utf8(take_bytes(&mut bytes)?)?),
+        };
+        let flags = DatabaseFlags::from_bits_truncate(take_u32(&mut bytes)?);
+        let custom_comparator = kind & CUSTOM_COMPARATOR_DATABASE != 0;
+        databases.push(LogDatabase { name, flags, custom_comparator });
+    }
+
+    let count = take_u32(&mut bytes)?;
+    let mut ops = Vec::new();
+    for _ in 0..count {
+        let kind = take_u8(&mut bytes)?;
+        let index = take_u32(&mut bytes)? as usize;
+        let database = match databases.get(index) {
+            Some(database) => database.name.clone(),
+            None => return Err("invalid database index in a replication log entry".into()),
+        };
+        let op = match kind {
+            PUT_OP => {
+                let key = take_bytes(&mut bytes)?;
+                let data = take_bytes(&mut bytes)?;
+                ChangeOp::Put { database, key, data }
+            }
+            DELETE_OP => ChangeOp::Delete { database, key: take_bytes(&mut bytes)?, data: None },
+            DELETE_DUPLICATE_OP => {
+                let key = take_bytes(&mut bytes)?;
+                let data = take_bytes(&mut bytes)?;
+                ChangeOp::Delete { database, key, data: Some(data) }
+            }
+            CLEAR_OP => ChangeOp::Clear { database },
+            _ => return Err("invalid operation in a replication log entry".into()),
+        };
+        ops.push(op);
+    }
+
+    Ok((databases, ops))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::*;
+
+    fn open_env(path: &std::path::Path, logged: bool) -> Env<WithTls> {
+        unsafe { EnvOpenOptions::new().max_dbs(10).replication_log(logged).open(path).unwrap() }
+    }
+
+    fn put_words(env: &Env<WithTls>, words: &[&str]) {
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<Str, Str>(&mut wtxn, Some("words")).unwrap();
+        for word in words {
+            db.put(&mut wtxn, word, word).unwrap();
+        }
+        wtxn.commit().unwrap();
+    }
+
+    fn sequences(entries: &[LogEntry]) -> Vec<u64> {
+        entries.iter().map(|entry| entry.sequence).collect()
+    }
+
+    enum Reverse {}
+
+    impl Comparator for Reverse {
+        fn compare(a: &[u8], b: &[u8]) -> Ordering {
+            b.cmp(a)
+        }
+    }
+
+    #[test]
+    fn sequence_keeps_growing_after_truncation() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = open_env(dir.path(), true);
+        put_words(&env, &["hello"]);
+        put_words(&env, &["world"]);
+
+        let rtxn = env.read_txn().unwrap();
+        assert_eq!(sequences(&read_log(&env, &rtxn, 0, 100).unwrap()), [1, 2]);
+        drop(rtxn);
+
+        let mut wtxn = env.write_txn().unwrap();
+        assert_eq!(truncate_log(&env, &mut wtxn, 2).unwrap(), 2);
+        wtxn.commit().unwrap();
+
+        put_words(&env, &["again"]);
+        let rtxn = env.read_txn().unwrap();
+        assert_eq!(sequences(&read_log(&env, &rtxn, 0, 100).unwrap()), [3]);
+    }
+
+    #[test]
+    fn read_log_returns_at_most_max_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = open_env(dir.path(), true);
+        put_words(&env, &["a"]);
+        put_words(&env, &["b"]);
+        put_words(&env, &["c"]);
+
+        let rtxn = env.read_txn().unwrap();
+        assert_eq!(sequences(&read_log(&env, &rtxn, 0, 2).unwrap()), [1, 2]);
+        assert_eq!(sequences(&read_log(&env, &rtxn, 2, 2).unwrap()), [3]);
+        assert!(read_log(&env, &rtxn, 3, 2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn cursor_writes_are_replicated() {
+        let leader_dir = tempfile::tempdir().unwrap();
+        let leader = open_env(leader_dir.path(), true);
+        let follower_dir = tempfile::tempdir().unwrap();
+        let follower = open_env(follower_dir.path(), false);
+        put_words(&leader, &["a", "b", "c"]);
+
+        let mut wtxn = leader.write_txn().unwrap();
+        let db = leader.create_database::<Str, Str>(&mut wtxn, Some("words")).unwrap();
+        let mut iter = db.iter_mut(&mut wtxn).unwrap();
+        assert_eq!(iter.next().transpose().unwrap(), Some(("a", "a")));
+        assert!(unsafe { iter.put_current("a", "updated") }.unwrap());
+        assert_eq!(iter.next().transpose().unwrap(), Some(("b", "b")));
+        assert!(unsafe { iter.del_current() }.unwrap());
+        drop(iter);
+        wtxn.commit().unwrap();
+
+        let rtxn = leader.read_txn().unwrap();
+        let entries = read_log(&leader, &rtxn, 0, 100).unwrap();
+        assert_eq!(apply_log(&follower, &entries).unwrap(), 2);
+
+        let rtxn = follower.read_txn().unwrap();
+        let db = follower.open_database::<Str, Str>(&rtxn, Some("words")).unwrap().unwrap();
+        let words: Vec<_> = db.iter(&rtxn).unwrap().collect::<Result<_>>().unwrap();
+        assert_eq!(words, [("a", "updated"), ("c", "c")]);
+        assert_eq!(last_applied_sequence(&follower, &rtxn).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn custom_comparator_databases_must_be_opened_in_the_follower() {
+        let leader_dir = tempfile::tempdir().unwrap();
+        let leader = open_env(leader_dir.path(), true);
+        let follower_dir = tempfile::tempdir().unwrap();
+        let follower = open_env(follower_dir.path(), false);
+
+        let mut wtxn = leader.write_txn().unwrap();
+        let db = leader
+            .database_options()
+            .types::<Str, Str>()
+            .key_comparator::<Reverse>()
+            .name("reversed")
+            .create(&mut wtxn)
+            .unwrap();
+        db.put(&mut wtxn, "a", "a").unwrap();
+        db.put(&mut wtxn, "b", "b").unwrap();
+        wtxn.commit().unwrap();
+
+        let rtxn = leader.read_txn().unwrap();
+        let entries = read_log(&leader, &rtxn, 0, 100).unwrap();
+        assert!(entries[0].databases[0].custom_comparator);
+        assert!(apply_log(&follower, &entries).is_err());
+
+        let mut wtxn = follower.write_txn().unwrap();
+        let db = follower
+            .database_options()
+            .types::<Str, Str>()
+            .key_comparator::<Reverse>()
+            .name("reversed")
+            .create(&mut wtxn)
+            .unwrap();
+        wtxn.commit().unwrap();
+        assert_eq!(apply_log(&follower, &entries).unwrap(), 1);
+
+        let rtxn = follower.read_txn().unwrap();
+        let keys: Vec<_> = db.iter(&rtxn).unwrap().map(|r| r.unwrap().0).collect();
+        assert_eq!(keys, ["b", "a"]);
+    }
+
+    #[test]
+    fn recycled_database_handles_are_logged_under_their_name() {
+        let leader_dir = tempfile::tempdir().unwrap();
+        let leader = open_env(leader_dir.path(), true);
+        let follower_dir = tempfile::tempdir().unwrap();
+        let follower = open_env(follower_dir.path(), false);
+
+        // The handle freed by the aborted transaction is reused for the next database.
+        let mut wtxn = leader.write_txn().unwrap();
+        leader.create_database::<Str, Str>(&mut wtxn, Some("aborted")).unwrap();
+        wtxn.abort();
+        put_words(&leader, &["hello"]);
+
+        let rtxn = leader.read_txn().unwrap();
+        let entries = read_log(&leader, &rtxn, 0, 100).unwrap();
+        assert_eq!(entries[0].databases[0].name.as_deref(), Some("words"));
+        assert_eq!(apply_log(&follower, &entries).unwrap(), 1);
+
+        let rtxn = follower.read_txn().unwrap();
+        assert!(follower.open_database::<Str, Str>(&rtxn, Some("aborted")).unwrap().is_none());
+        let db = follower.open_database::<Str, Str>(&rtxn, Some("words")).unwrap().unwrap();
+        assert_eq!(db.get(&rtxn, "hello").unwrap(), Some("hello"));
+    }
+
+    #[test]
+    fn entry_encoding_roundtrip() {
+        let databases = vec![
+            LogDatabase { name: None, flags: DatabaseFlags::empty(), custom_comparator: false },
+            LogDatabase {
+                name: Some(String::from("dups")),
+                flags: DatabaseFlags::DUP_SORT | DatabaseFlags::DUP_FIXED,
+                custom_comparator: true,
+            },
+        ];
+        let ops = vec![
+            ChangeOp::Put { database: None, key: b"hello".to_vec(), data: b"world".to_vec() },
+            ChangeOp::Delete { database: None, key: b"hello".to_vec(), data: None },
+            ChangeOp::Delete {
+                database: Some(String::from("dups")),
+                key: b"k".to_vec(),
+                data: Some(b"v".to_vec()),
+            },
+            ChangeOp::Clear { database: Some(String::from("dups")) },
+        ];
+
+        let bytes = encode_entry(&databases, &ops);
+        assert_eq!(decode_entry(&bytes).unwrap(), (databases, ops));
+        assert!(decode_entry(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
@@ -1,12 +1,18 @@
 use std::borrow::Cow;
+use std::cell::RefCell;
 use std::marker::PhantomData;
+use std::mem;
 use std::ops::Deref;
 use std::ptr::{self, NonNull};
+use std::sync::Mutex;
+#[cfg(master3)]
+use std::sync::{MutexGuard, PoisonError};
 
 use crate::envs::Env;
 use crate::mdb::error::mdb_result;
 use crate::mdb::ffi;
-use crate::Result;
+use crate::replication;
+use crate::{ChangeBatch, ChangeOp, Error, MdbError, Result};
 
 /// A read-only transaction.
 ///
@@ -50,6 +56,9 @@ pub struct RoTxn<'e, T = WithTls> {
     /// Makes the struct covariant and !Sync
     pub(crate) txn: Option<NonNull<ffi::MDB_txn>>,
     env: Cow<'e, Env<T>>,
+    /// The databases opened for the first time by this transaction,
+    /// LMDB frees their handles if it is aborted.
+    opened_databases: Mutex<Vec<ffi::MDB_dbi>>,
     _tls_marker: PhantomData<T>,
 }
 
@@ -66,7 +75,12 @@ impl<'e, T> RoTxn<'e, T> {
             ))?
         };
 
-        Ok(RoTxn { txn: NonNull::new(txn), env: Cow::Borrowed(env), _tls_marker: PhantomData })
+        Ok(RoTxn {
+            txn: NonNull::new(txn),
+            env: Cow::Borrowed(env),
+            opened_databases: Mutex::default(),
+            _tls_marker: PhantomData,
+        })
     }
 
     pub(crate) fn static_read_txn(env: Env<T>) -> Result<RoTxn<'static, T>> {
@@ -81,13 +95,29 @@ impl<'e, T> RoTxn<'e, T> {
             ))?
         };
 
-        Ok(RoTxn { txn: NonNull::new(txn), env: Cow::Owned(env), _tls_marker: PhantomData })
+        Ok(RoTxn {
+            txn: NonNull::new(txn),
+            env: Cow::Owned(env),
+            opened_databases: Mutex::default(),
+            _tls_marker: PhantomData,
+        })
     }
 
     pub(crate) fn env_mut_ptr(&self) -> NonNull<ffi::MDB_env> {
         self.env.env_mut_ptr()
     }
 
+    /// Remembers that the database has been opened for the first time by this transaction.
+    pub(crate) fn database_opened(&self, dbi: ffi::MDB_dbi) {
+        self.opened_databases.lock().unwrap().push(dbi);
+    }
+
+    /// Forgets the databases opened by this transaction once it has been aborted.
+    fn forget_opened_databases(&self) {
+        let dbis = mem::take(&mut *self.opened_databases.lock().unwrap());
+        self.env.change_feed().forget_databases(&dbis);
+    }
+
     /// Commit a read transaction.
     ///
     /// Synchronizing some [`Env`] metadata with the global handle.
@@ -102,6 +132,9 @@ impl<'e, T> RoTxn<'e, T> {
         // committed/aborter and ensure we cannot use it twice.
         let mut txn = self.txn.take().unwrap();
         let result = unsafe { mdb_result(ffi::mdb_txn_commit(txn.as_mut())) };
+        if result.is_err() {
+            self.forget_opened_databases();
+        }
         result.map_err(Into::into)
     }
 }
@@ -112,6 +145,7 @@ impl<T> Drop for RoTxn<'_, T> {
             // Asserts that the transaction hasn't been already
             // committed/aborter and ensure we cannot use it twice.
             unsafe { ffi::mdb_txn_abort(txn.as_mut()) }
+            self.forget_opened_databases();
         }
     }
 }
@@ -161,6 +195,139 @@ impl TlsUsage for WithoutTls {
 /// Is sendable only if `MDB_NOTLS` has been used to open this transaction.
 unsafe impl Send for RoTxn<'_, WithoutTls> {}
 
+/// A read-only transaction of an encrypted environment that pins every entry read through it,
+/// see [`EncryptedEnv::pinned_read_txn`](crate::EncryptedEnv::pinned_read_txn).
+///
+/// LMDB decrypts the pages in a cache that is recycled by the next reads, that's why the
+/// reads of an [`EncryptedDatabase`](crate::EncryptedDatabase) take the transaction mutably.
+/// The `_pinned` reads of this transaction copy the entries they return into a pin set that
+/// grows until the transaction is dropped. They only take the transaction by reference
+/// and return values valid for the whole transaction, like the plain [`Database`](crate::Database).
+///
+/// The reads are serialized by a lock held while LMDB decrypts the entry and it is copied,
+/// a `PinnedRoTxn<WithoutTls>` can be shared between threads.
+///
+/// # Memory
+///
+/// Pinning copies, it doesn't keep the decrypted pages alive. Every entry returned by a
+/// `_pinned` read is copied into two heap allocations, one for the key and one for the data,
+/// that are only freed when the transaction is dropped or [`PinnedRoTxn::reset_pins`] is
+/// called. Reading the same entry twice copies it twice. The memory used by the transaction
+/// grows with the size of every entry read through it: iterating over a whole database, even with
+/// [`EncryptedDatabase::range_pinned`](crate::EncryptedDatabase::range_pinned) or
+/// [`EncryptedDatabase::prefix_iter_pinned`](crate::EncryptedDatabase::prefix_iter_pinned),
+/// keeps a decrypted copy of all of it in memory. [`PinnedRoTxn::pinned_size`] reports the
+/// number of bytes copied so far. Large scans should use the reads taking a mutable
+/// [`RoTxn`] instead, or be split across several short transactions.
+#[cfg(master3)]
+pub struct PinnedRoTxn<'e, T = WithTls> {
+    txn: RoTxn<'e, T>,
+    pins: Mutex<PinSet>,
+}
+
+#[cfg(master3)]
+impl<'e, T> PinnedRoTxn<'e, T> {
+    pub(crate) fn new(txn: RoTxn<'e, T>) -> PinnedRoTxn<'e, T> {
+        PinnedRoTxn { txn, pins: Mutex::default() }
+    }
+
+    /// Locks the transaction to read entries from it and pin them.
+    pub(crate) fn lock(&self) -> PinGuard<'_, 'e, T> {
+        let pins = self.pins.lock().unwrap_or_else(PoisonError::into_inner);
+        PinGuard { txn: &self.txn, pins }
+    }
+
+    /// Calls the function with the underlying transaction, to open a database for example.
+    ///
+    /// The transaction is locked during the call, the `_pinned` reads
+    /// of this transaction must not be called by the function.
+    pub fn with_txn<R>(&self, f: impl FnOnce(&RoTxn<'e, T>) -> R) -> R {
+        let pins = self.lock();
+        f(pins.txn)
+    }
+
+    /// Returns the number of bytes currently pinned by this transaction.
+    pub fn pinned_size(&self) -> usize {
+        self.lock().pins.size
+    }
+
+    /// Frees the copies pinned so far by this transaction.
+    ///
+    /// The transaction is borrowed by the entries returned by
+    /// the `_pinned` reads, they must have been dropped.
+    pub fn reset_pins(&mut self) {
+        self.pins.get_mut().unwrap_or_else(PoisonError::into_inner).clear();
+    }
+
+    /// Commit a read transaction, see [`RoTxn::commit`].
+    pub fn commit(self) -> Result<()> {
+        self.txn.commit()
+    }
+}
+
+/// Is sendable only if `MDB_NOTLS` has been used to open this transaction.
+#[cfg(master3)]
+unsafe impl Send for PinnedRoTxn<'_, WithoutTls> {}
+
+/// Can be shared only if `MDB_NOTLS` has been used to open this transaction,
+/// the reads are serialized by the lock of the pin set.
+#[cfg(master3)]
+unsafe impl Sync for PinnedRoTxn<'_, WithoutTls> {}
+
+/// The lock of a [`PinnedRoTxn`], held while reading the transaction and pinning the entries.
+#[cfg(master3)]
+pub(crate) struct PinGuard<'p, 'e, T> {
+    txn: &'p RoTxn<'e, T>,
+    pins: MutexGuard<'p, PinSet>,
+}
+
+#[cfg(master3)]
+impl<'p, 'e, T> PinGuard<'p, 'e, T> {
+    /// The transaction, must only be used to read while this guard is held.
+    pub(crate) fn txn(&self) -> &'p RoTxn<'e, T> {
+        self.txn
+    }
+
+    /// Copies the bytes into the pin set, the copy lives as long as the transaction.
+    pub(crate) fn pin(&mut self, bytes: &[u8]) -> &'p [u8] {
+        let pinned = NonNull::from(Box::leak(Box::<[u8]>::from(bytes)));
+        self.pins.pinned.push(pinned);
+        self.pins.size += bytes.len();
+        // Safety: the copies are never mutated and only freed when the transaction is
+        // dropped or its pins are reset, which both require it not to be borrowed anymore.
+        unsafe { pinned.as_ref() }
+    }
+}
+
+/// The pinned copies of a [`PinnedRoTxn`], freed when it is dropped.
+#[cfg(master3)]
+#[derive(Default)]
+struct PinSet {
+    pinned: Vec<NonNull<[u8]>>,
+    size: usize,
+}
+
+#[cfg(master3)]
+impl PinSet {
+    fn clear(&mut self) {
+        for pinned in self.pinned.drain(..) {
+            drop(unsafe { Box::from_raw(pinned.as_ptr()) });
+        }
+        self.size = 0;
+    }
+}
+
+#[cfg(master3)]
+impl Drop for PinSet {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// The copies are owned by the pin set.
+#[cfg(master3)]
+unsafe impl Send for PinSet {}
+
 /// A read-write transaction.
 ///
 /// ## LMDB Limitations
@@ -200,6 +367,12 @@ unsafe impl Send for RoTxn<'_, WithoutTls> {}
 /// ```
 pub struct RwTxn<'p> {
     pub(crate) txn: RoTxn<'p, WithoutTls>,
+    hooks: TxnHooks,
+    parent_hooks: Option<&'p mut TxnHooks>,
+    /// The operations recorded when the change capture is enabled.
+    changes: Option<RefCell<Vec<ChangeOp>>>,
+    parent_changes: Option<&'p mut Option<RefCell<Vec<ChangeOp>>>>,
+    parent_opened_databases: Option<&'p Mutex<Vec<ffi::MDB_dbi>>>,
 }
 
 impl<'p> RwTxn<'p> {
@@ -221,8 +394,14 @@ impl<'p> RwTxn<'p> {
             txn: RoTxn {
                 txn: NonNull::new(txn),
                 env: Cow::Borrowed(env_without_tls),
+                opened_databases: Mutex::default(),
                 _tls_marker: PhantomData,
             },
+            hooks: TxnHooks::default(),
+            parent_hooks: None,
+            changes: env.change_feed().is_enabled().then(RefCell::default),
+            parent_changes: None,
+            parent_opened_databases: None,
         })
     }
 
@@ -240,8 +419,14 @@ impl<'p> RwTxn<'p> {
             txn: RoTxn {
                 txn: NonNull::new(txn),
                 env: Cow::Borrowed(env_without_tls),
+                opened_databases: Mutex::default(),
                 _tls_marker: PhantomData,
             },
+            hooks: TxnHooks::default(),
+            parent_hooks: Some(&mut parent.hooks),
+            changes: env.change_feed().is_enabled().then(RefCell::default),
+            parent_changes: Some(&mut parent.changes),
+            parent_opened_databases: Some(&parent.txn.opened_databases),
         })
     }
 
@@ -249,13 +434,137 @@ impl<'p> RwTxn<'p> {
         self.txn.env.env_mut_ptr()
     }
 
+    /// Whether the write operations are recorded for the change feed.
+    pub(crate) fn captures_changes(&self) -> bool {
+        self.changes.is_some()
+    }
+
+    /// Records a write operation performed on the given database,
+    /// only if the change capture is enabled on the environment.
+    ///
+    /// It only needs a shared reference as the cursors of this transaction record their writes too.
+    /// A database that has never been opened in this environment can't be named in the
+    /// operation, it is reported as an [`MdbError::BadDbi`](crate::MdbError::BadDbi).
+    pub(crate) fn record_change<F>(&self, dbi: ffi::MDB_dbi, op: F) -> Result<()>
+    where
+        F: FnOnce(Option<String>) -> ChangeOp,
+    {
+        if let Some(changes) = self.changes.as_ref() {
+            let Some(name) = self.txn.env.change_feed().database_name(dbi) else {
+                return Err(Error::Mdb(MdbError::BadDbi));
+            };
+            // The writes to the replication databases are never recorded.
+            if !name.as_deref().is_some_and(replication::is_internal_database) {
+                changes.borrow_mut().push(op(name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Registers a callback that is called once the operations of this transaction
+    /// are effectively committed to the environment.
+    ///
+    /// The callbacks are called in the order they were registered, after
+    /// `mdb_txn_commit` succeeded. When registered on a nested transaction,
+    /// the callbacks are moved to the parent transaction on commit and will
+    /// only be called when the top-level transaction is committed.
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    /// use heed::EnvOpenOptions;
+    /// use heed::types::*;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let dir = tempfile::tempdir()?;
+    /// let env = unsafe { EnvOpenOptions::new().open(dir.path())? };
+    ///
+    /// let committed = Arc::new(AtomicBool::new(false));
+    ///
+    /// let mut wtxn = env.write_txn()?;
+    /// let db = env.create_database::<Str, Str>(&mut wtxn, None)?;
+    /// db.put(&mut wtxn, "hello", "world")?;
+    ///
+    /// let flag = committed.clone();
+    /// wtxn.on_commit(move || flag.store(true, Ordering::SeqCst));
+    /// assert!(!committed.load(Ordering::SeqCst));
+    ///
+    /// wtxn.commit()?;
+    /// assert!(committed.load(Ordering::SeqCst));
+    /// # Ok(()) }
+    /// ```
+    pub fn on_commit<F>(&mut self, callback: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.hooks.on_commit.push(Box::new(callback));
+    }
+
+    /// Registers a callback that is called once this transaction is aborted,
+    /// either explicitly with [`RwTxn::abort`], by being dropped or because
+    /// the commit failed.
+    ///
+    /// When registered on a nested transaction that gets committed, the
+    /// callbacks are moved to the parent transaction and will be called
+    /// if the parent is aborted.
+    pub fn on_abort<F>(&mut self, callback: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.hooks.on_abort.push(Box::new(callback));
+    }
+
     /// Commit all the operations of a transaction into the database.
     /// The transaction is reset.
     pub fn commit(mut self) -> Result<()> {
+        let txn_id = unsafe { ffi::mdb_txn_id(self.txn.txn.unwrap().as_mut()) };
+        let changes = self.changes.take().map(RefCell::into_inner);
+
+        // The log entry is written in the transaction itself, right before committing it.
+        if let Some(ops) = changes.as_deref().filter(|ops| !ops.is_empty()) {
+            if self.parent_changes.is_none() && self.txn.env.change_feed().is_logged() {
+                let env = Env::clone(&self.txn.env);
+                replication::append_log_entry(&env, &mut self, ops)?;
+            }
+        }
+
         // Asserts that the transaction hasn't been already
         // committed/aborter and ensure we cannot use it two times.
         let mut txn = self.txn.txn.take().unwrap();
         let result = unsafe { mdb_result(ffi::mdb_txn_commit(txn.as_mut())) };
+
+        if let (Ok(()), Some(changes)) = (result, changes) {
+            match self.parent_changes.as_mut() {
+                Some(Some(parent_changes)) => parent_changes.get_mut().extend(changes),
+                Some(None) => (),
+                None if changes.is_empty() => (),
+                None => {
+                    let batch = ChangeBatch { txn_id, ops: changes };
+                    self.txn.env.change_feed().publish(batch);
+                }
+            }
+        }
+
+        // The databases opened by a nested transaction are freed if its parent is aborted.
+        match (result, self.parent_opened_databases) {
+            (Ok(()), Some(parent)) => {
+                let opened = mem::take(&mut *self.txn.opened_databases.lock().unwrap());
+                parent.lock().unwrap().extend(opened);
+            }
+            (Ok(()), None) => (),
+            (Err(_), _) => self.txn.forget_opened_databases(),
+        }
+
+        let hooks = mem::take(&mut self.hooks);
+        match (result, self.parent_hooks.as_mut()) {
+            // The operations are now part of the parent transaction, and
+            // so are the callbacks, they will run when the parent ends.
+            (Ok(()), Some(parent_hooks)) => parent_hooks.extend(hooks),
+            (Ok(()), None) => hooks.run_on_commit(),
+            // LMDB frees the transaction even when the commit fails.
+            (Err(_), _) => hooks.run_on_abort(),
+        }
+
         result.map_err(Into::into)
     }
 
@@ -266,6 +575,45 @@ impl<'p> RwTxn<'p> {
         // committed/aborter and ensure we cannot use it twice.
         let mut txn = self.txn.txn.take().unwrap();
         unsafe { ffi::mdb_txn_abort(txn.as_mut()) }
+        self.txn.forget_opened_databases();
+        mem::take(&mut self.hooks).run_on_abort();
+    }
+}
+
+impl Drop for RwTxn<'_> {
+    fn drop(&mut self) {
+        if let Some(mut txn) = self.txn.txn.take() {
+            // The transaction is dropped without being committed nor aborted,
+            // we abort it here to make sure the abort callbacks run after it.
+            unsafe { ffi::mdb_txn_abort(txn.as_mut()) }
+            self.txn.forget_opened_databases();
+            mem::take(&mut self.hooks).run_on_abort();
+        }
+    }
+}
+
+type TxnHook = Box<dyn FnOnce() + Send + 'static>;
+
+/// The callbacks registered on a [`RwTxn`] with
+/// [`RwTxn::on_commit`] and [`RwTxn::on_abort`].
+#[derive(Default)]
+struct TxnHooks {
+    on_commit: Vec<TxnHook>,
+    on_abort: Vec<TxnHook>,
+}
+
+impl TxnHooks {
+    fn extend(&mut self, other: TxnHooks) {
+        self.on_commit.extend(other.on_commit);
+        self.on_abort.extend(other.on_abort);
+    }
+
+    fn run_on_commit(self) {
+        self.on_commit.into_iter().for_each(|hook| hook());
+    }
+
+    fn run_on_abort(self) {
+        self.on_abort.into_iter().for_each(|hook| hook());
     }
 }
 
@@ -284,3 +632,191 @@ impl std::ops::DerefMut for RwTxn<'_> {
         &mut self.txn
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use crate::types::Str;
+    use crate::{ChangeOp, EnvOpenOptions};
+
+    fn counter() -> (Arc<AtomicUsize>, impl Fn() -> Box<dyn FnOnce() + Send>) {
+        let count = Arc::new(AtomicUsize::new(0));
+        let cloned = count.clone();
+        let make = move || {
+            let count = cloned.clone();
+            Box::new(move || {
+                count.fetch_add(1, Ordering::SeqCst);
+            }) as Box<dyn FnOnce() + Send>
+        };
+        (count, make)
+    }
+
+    #[test]
+    fn hooks_on_commit_and_abort() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().open(dir.path()).unwrap() };
+        let (commits, on_commit) = counter();
+        let (aborts, on_abort) = counter();
+
+        let mut wtxn = env.write_txn().unwrap();
+        wtxn.on_commit(on_commit());
+        wtxn.on_abort(on_abort());
+        wtxn.commit().unwrap();
+        assert_eq!(commits.load(Ordering::SeqCst), 1);
+        assert_eq!(aborts.load(Ordering::SeqCst), 0);
+
+        let mut wtxn = env.write_txn().unwrap();
+        wtxn.on_commit(on_commit());
+        wtxn.on_abort(on_abort());
+        wtxn.abort();
+        assert_eq!(commits.load(Ordering::SeqCst), 1);
+        assert_eq!(aborts.load(Ordering::SeqCst), 1);
+
+        let mut wtxn = env.write_txn().unwrap();
+        wtxn.on_commit(on_commit());
+        wtxn.on_abort(on_abort());
+        drop(wtxn);
+        assert_eq!(commits.load(Ordering::SeqCst), 1);
+        assert_eq!(aborts.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn nested_hooks_are_moved_to_the_parent() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().open(dir.path()).unwrap() };
+        let (commits, on_commit) = counter();
+        let (aborts, on_abort) = counter();
+
+        let mut wtxn = env.write_txn().unwrap();
+
+        // An aborted nested transaction runs its abort callbacks right away.
+        let mut nested = env.nested_write_txn(&mut wtxn).unwrap();
+        nested.on_commit(on_commit());
+        nested.on_abort(on_abort());
+        nested.abort();
+        assert_eq!(commits.load(Ordering::SeqCst), 0);
+        assert_eq!(aborts.load(Ordering::SeqCst), 1);
+
+        // A committed nested transaction gives its callbacks to the parent.
+        let mut nested = env.nested_write_txn(&mut wtxn).unwrap();
+        nested.on_commit(on_commit());
+        nested.on_abort(on_abort());
+        nested.commit().unwrap();
+        assert_eq!(commits.load(Ordering::SeqCst), 0);
+        assert_eq!(aborts.load(Ordering::SeqCst), 1);
+
+        wtxn.commit().unwrap();
+        assert_eq!(commits.load(Ordering::SeqCst), 1);
+        assert_eq!(aborts.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn nested_changes_are_published_with_the_parent() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe {
+            EnvOpenOptions::new().max_dbs(1).change_capture(true).open(dir.path()).unwrap()
+        };
+        let changes = env.subscribe();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<Str, Str>(&mut wtxn, Some("words")).unwrap();
+        db.put(&mut wtxn, "hello", "world").unwrap();
+
+        let mut nested = env.nested_write_txn(&mut wtxn).unwrap();
+        db.put(&mut nested, "aborted", "value").unwrap();
+        nested.abort();
+
+        let mut nested = env.nested_write_txn(&mut wtxn).unwrap();
+        db.delete(&mut nested, "hello").unwrap();
+        nested.commit().unwrap();
+
+        // Nothing is sent before the top-level transaction commits.
+        assert!(changes.try_recv().is_err());
+        wtxn.commit().unwrap();
+
+        let database = Some(String::from("words"));
+        let batch = changes.try_recv().unwrap();
+        assert_eq!(
+            batch.ops,
+            vec![
+                ChangeOp::Put {
+                    database: database.clone(),
+                    key: b"hello".to_vec(),
+                    data: b"world".to_vec()
+                },
+                ChangeOp::Delete { database, key: b"hello".to_vec(), data: None },
+            ]
+        );
+
+        // Aborted and empty transactions are never published.
+        let wtxn = env.write_txn().unwrap();
+        wtxn.commit().unwrap();
+        let mut wtxn = env.write_txn().unwrap();
+        db.put(&mut wtxn, "hello", "world").unwrap();
+        wtxn.abort();
+        assert!(changes.try_recv().is_err());
+    }
+
+    #[test]
+    fn recycled_database_handles_are_published_under_their_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe {
+            EnvOpenOptions::new().max_dbs(2).change_capture(true).open(dir.path()).unwrap()
+        };
+        let changes = env.subscribe();
+
+        // LMDB frees the handle of a database created in an aborted transaction...
+        let mut wtxn = env.write_txn().unwrap();
+        let aborted = env.create_database::<Str, Str>(&mut wtxn, Some("aborted")).unwrap();
+        wtxn.abort();
+
+        // ...and reuses it for the next database opened.
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<Str, Str>(&mut wtxn, Some("real")).unwrap();
+        assert_eq!(db.dbi, aborted.dbi);
+        db.put(&mut wtxn, "hello", "world").unwrap();
+        wtxn.commit().unwrap();
+
+        let batch = changes.try_recv().unwrap();
+        assert_eq!(
+            batch.ops,
+            vec![ChangeOp::Put {
+                database: Some(String::from("real")),
+                key: b"hello".to_vec(),
+                data: b"world".to_vec()
+            }]
+        );
+    }
+
+    #[test]
+    fn databases_opened_by_aborted_transactions_are_forgotten() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = unsafe { EnvOpenOptions::new().max_dbs(2).open(dir.path()).unwrap() };
+
+        // A database opened in a committed nested transaction is freed with its parent.
+        let mut wtxn = env.write_txn().unwrap();
+        let mut nested = env.nested_write_txn(&mut wtxn).unwrap();
+        let db = env.create_database::<Str, Str>(&mut nested, Some("nested")).unwrap();
+        nested.commit().unwrap();
+        assert_eq!(env.change_feed().database_name(db.dbi), Some(Some(String::from("nested"))));
+        wtxn.abort();
+        assert_eq!(env.change_feed().database_name(db.dbi), None);
+
+        // The handle of a database opened by a committed transaction stays valid.
+        let mut wtxn = env.write_txn().unwrap();
+        let db = env.create_database::<Str, Str>(&mut wtxn, Some("kept")).unwrap();
+        wtxn.commit().unwrap();
+        let rtxn = env.read_txn().unwrap();
+        env.open_database::<Str, Str>(&rtxn, Some("kept")).unwrap().unwrap();
+        drop(rtxn);
+        assert_eq!(env.change_feed().database_name(db.dbi), Some(Some(String::from("kept"))));
+
+        // The unnamed database is never freed.
+        let rtxn = env.read_txn().unwrap();
+        let main = env.open_database::<Str, Str>(&rtxn, None).unwrap().unwrap();
+        drop(rtxn);
+        assert_eq!(env.change_feed().database_name(main.dbi), Some(None));
+    }
+}